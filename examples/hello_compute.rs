@@ -0,0 +1,37 @@
+use wgpu_engine::engine::servers::renderer::resources::{Buffer, ComputePipeline};
+use wgpu_engine::engine::Engine;
+
+fn main() {
+    env_logger::init();
+    // compute work doesn't need a window
+    let mut eng = pollster::block_on(Engine::new_headless((1, 1), false));
+    let values: Vec<f32> = (0..1000).map(|i| i as f32).collect();
+    {
+        let renderer_server = eng.get_renderer_server();
+        let params_buffer = Buffer::new("params")
+            .new_content(bytemuck::cast_slice(&[2.0f32, 0.0, 0.0, 0.0]))
+            .new_usage(wgpu::BufferUsages::UNIFORM)
+            .build(&renderer_server.device);
+        let values_buffer = Buffer::new("values")
+            .new_content(bytemuck::cast_slice(&values))
+            .new_usage(wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC)
+            .build(&renderer_server.device);
+        let pipeline = ComputePipeline::new()
+            .new_shader(include_str!("shaders/hello_compute.wgsl"))
+            .build("multiply pipeline", &renderer_server.device);
+        eng.set_buffer(params_buffer);
+        eng.set_buffer(values_buffer);
+        eng.set_compute_pipeline("multiply pipeline", pipeline);
+    }
+    eng.bind_resources_to_compute_pipeline("multiply pipeline", 0, &["params", "values"]);
+    // one invocation per value, the workgroup count is derived from @workgroup_size
+    eng.dispatch_compute("multiply pipeline", [values.len() as u32, 1, 1], |_| 0);
+
+    let values_buffer = eng.get_buffer("values").unwrap();
+    let data = eng
+        .get_renderer_server()
+        .read_buffer(values_buffer.get_native_buffer());
+    let result: &[f32] = bytemuck::cast_slice(&data);
+    println!("first values: {:?}", &result[..8]);
+    println!("last value: {}", result[result.len() - 1]);
+}
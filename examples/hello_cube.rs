@@ -1,70 +1,13 @@
-use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+use wgpu_engine::engine::scene::nodes::mesh_instance::mesh::primitives::create_cube;
+use wgpu_engine::engine::scene::nodes::mesh_instance::mesh::{VertexAttribute, VertexLayout};
 use wgpu_engine::engine::servers::renderer::resources::{
-    Buffer, CommandBuffer, RenderPassBuilder, RenderPipeline, VertexBufferLayout,
+    Buffer, CommandBuffer, RenderPassBuilder, RenderPipeline, Sampler, Texture,
 };
+use wgpu_engine::engine::servers::renderer::RenderPipelineSettings;
 use wgpu_engine::engine::Engine;
 
-use std::{f32::consts, mem};
-
-#[repr(C)]
-#[derive(Clone, Copy, Pod, Zeroable)]
-struct Vertex {
-    _pos: [f32; 4],
-    _tex_coord: [f32; 2],
-}
-
-fn vertex(pos: [i8; 3], tc: [i8; 2]) -> Vertex {
-    Vertex {
-        _pos: [pos[0] as f32, pos[1] as f32, pos[2] as f32, 1.0],
-        _tex_coord: [tc[0] as f32, tc[1] as f32],
-    }
-}
-
-fn create_vertices() -> (Vec<Vertex>, Vec<u16>) {
-    let vertex_data = [
-        // top (0, 0, 1)
-        vertex([-1, -1, 1], [0, 0]),
-        vertex([1, -1, 1], [1, 0]),
-        vertex([1, 1, 1], [1, 1]),
-        vertex([-1, 1, 1], [0, 1]),
-        // bottom (0, 0, -1)
-        vertex([-1, 1, -1], [1, 0]),
-        vertex([1, 1, -1], [0, 0]),
-        vertex([1, -1, -1], [0, 1]),
-        vertex([-1, -1, -1], [1, 1]),
-        // right (1, 0, 0)
-        vertex([1, -1, -1], [0, 0]),
-        vertex([1, 1, -1], [1, 0]),
-        vertex([1, 1, 1], [1, 1]),
-        vertex([1, -1, 1], [0, 1]),
-        // left (-1, 0, 0)
-        vertex([-1, -1, 1], [1, 0]),
-        vertex([-1, 1, 1], [0, 0]),
-        vertex([-1, 1, -1], [0, 1]),
-        vertex([-1, -1, -1], [1, 1]),
-        // front (0, 1, 0)
-        vertex([1, 1, -1], [1, 0]),
-        vertex([-1, 1, -1], [0, 0]),
-        vertex([-1, 1, 1], [0, 1]),
-        vertex([1, 1, 1], [1, 1]),
-        // back (0, -1, 0)
-        vertex([1, -1, 1], [0, 0]),
-        vertex([-1, -1, 1], [1, 0]),
-        vertex([-1, -1, -1], [1, 1]),
-        vertex([1, -1, -1], [0, 1]),
-    ];
-
-    let index_data: &[u16] = &[
-        0, 1, 2, 2, 3, 0, // top
-        4, 5, 6, 6, 7, 4, // bottom
-        8, 9, 10, 10, 11, 8, // right
-        12, 13, 14, 14, 15, 12, // left
-        16, 17, 18, 18, 19, 16, // front
-        20, 21, 22, 22, 23, 20, // back
-    ];
-
-    (vertex_data.to_vec(), index_data.to_vec())
-}
+use std::{cell::RefCell, f32::consts, rc::Rc};
 
 fn generate_matrix(aspect_ratio: f32) -> glam::Mat4 {
     let projection = glam::Mat4::perspective_rh(consts::FRAC_PI_4, aspect_ratio, 1.0, 10.0);
@@ -76,93 +19,138 @@ fn generate_matrix(aspect_ratio: f32) -> glam::Mat4 {
     projection * view
 }
 
+pub struct HelloCube {
+    pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    uniform_buffer: Buffer,
+    _texture: Texture,
+    _sampler: Sampler,
+    index_count: u32,
+}
+
+impl HelloCube {
+    pub fn new(eng: &Engine) -> HelloCube {
+        let res = eng.get_resolution();
+        let renderer_server = eng.get_renderer_server();
+        let mx_total = generate_matrix(res.0 as f32 / res.1 as f32);
+        let mx_ref: &[f32; 16] = mx_total.as_ref();
+        let uniform_buffer = Buffer::new("uniform buffer")
+            .new_content(bytemuck::cast_slice(mx_ref))
+            .new_usage(wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST)
+            .build(&renderer_server.device);
+
+        // only the position at location 0 and tex_coord at location 2 are uploaded
+        let vertex_layout = VertexLayout::new(
+            &[VertexAttribute::Position, VertexAttribute::TexCoord0],
+            true,
+        );
+        let vertex_layouts = vertex_layout.get_vertex_buffer_layouts();
+        let mut pipeline = RenderPipeline::new()
+            .new_shader(include_str!("shaders/hello_cube.wgsl"))
+            .new_vertex_buffer(&vertex_layouts[0])
+            .new_target(renderer_server.get_swapchain().get_format().into())
+            .new_settings(&RenderPipelineSettings {
+                depth_testing: true,
+                depth_format: renderer_server.get_swapchain().get_depth_format(),
+                ..Default::default()
+            })
+            .build("triangle pipeline", &renderer_server.device);
+        let image = image::open(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/examples/assets/checker.png"
+        ))
+        .unwrap()
+        .to_rgba8();
+        let texture = Texture::new("checker texture")
+            .new_image(&image)
+            .new_mipmaps(true)
+            .build(&renderer_server.device, &renderer_server.queue);
+        let sampler = Sampler::new("checker sampler")
+            .new_address_mode(wgpu::AddressMode::Repeat)
+            .build(&renderer_server.device);
+        pipeline.bind_resource(
+            &renderer_server.device,
+            0,
+            &[
+                uniform_buffer.get_native_buffer().as_entire_binding(),
+                texture.get_binding(),
+                sampler.get_binding(),
+            ],
+        );
+
+        //create vertex & index buffer
+        let (vertex_data, index_data) = create_cube(Vec3::splat(2.0)).build_vertex_data();
+        let vertex_buffer = Buffer::new("vertex buffer")
+            .new_content(&vertex_layout.pack(&vertex_data)[0])
+            .new_usage(wgpu::BufferUsages::VERTEX)
+            .build(&renderer_server.device);
+        let index_buffer = Buffer::new("index buffer")
+            .new_content(bytemuck::cast_slice(&index_data))
+            .new_usage(wgpu::BufferUsages::INDEX)
+            .build(&renderer_server.device);
+        HelloCube {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            uniform_buffer,
+            _texture: texture,
+            _sampler: sampler,
+            index_count: index_data.len() as u32,
+        }
+    }
+
+    pub fn render(&self, engine: &Engine) {
+        let renderer_server = engine.get_renderer_server();
+        let Some((frame, frame_view, depth_view)) = renderer_server.get_new_frame() else {
+            return;
+        };
+        let mut main_buffer =
+            CommandBuffer::new_command_buffer(&renderer_server.device, "main_buffer");
+        {
+            let mut main_pass = RenderPassBuilder::new("main_pass")
+                .color_attachment(&frame_view, [0.1, 0.5, 0.3, 1.0])
+                .depth_stencil_attachment(depth_view)
+                .depth_ops(1.0)
+                .build(&mut main_buffer);
+            main_pass.set_pipeline(self.pipeline.get_native_pipeline());
+            let bind_groups = self.pipeline.get_bind_groups();
+            main_pass.set_bind_group(0, bind_groups[0], &[0]);
+            main_pass.set_vertex_buffer(0, self.vertex_buffer.get_native_buffer().slice(..));
+            main_pass.set_index_buffer(
+                self.index_buffer.get_native_buffer().slice(..),
+                wgpu::IndexFormat::Uint32,
+            );
+            main_pass.draw_indexed(0..self.index_count, 0, 0..1);
+        }
+        main_buffer.finish_command_buffer(&renderer_server.queue);
+        frame.present();
+    }
+
+    pub fn resize(&mut self, engine: &Engine, resolution: (u32, u32)) {
+        let mx_total = generate_matrix(resolution.0 as f32 / resolution.1 as f32);
+        let mx_ref: &[f32; 16] = mx_total.as_ref();
+        self.uniform_buffer.write(
+            &engine.get_renderer_server().queue,
+            0,
+            bytemuck::cast_slice(mx_ref),
+        );
+    }
+}
+
 fn main() {
     #[cfg(not(target_arch = "wasm32"))]
     {
         env_logger::init();
         let eng = pollster::block_on(Engine::new("hello cube", (1280, 720)));
-        let mut triangle_pipeline: RenderPipeline;
-        let vertex_buffer: Buffer;
-        let index_buffer: Buffer;
-        let mut uniform_buffer: Buffer;
-        let index_count: u32;
-        {
-            let res: (u32, u32);
-            {
-                res = eng.get_resolution();
-            }
-            let renderer_server = eng.get_renderer_server();
-            let mx_total = generate_matrix(res.0 as f32 / res.1 as f32);
-            let mx_ref: &[f32; 16] = mx_total.as_ref();
-            uniform_buffer = Buffer::new("uniform buffer")
-                .new_content(bytemuck::cast_slice(mx_ref))
-                .new_usage(wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST)
-                .build(&renderer_server.device);
-
-            triangle_pipeline = RenderPipeline::new()
-                .new_shader(include_str!("shaders/hello_cube.wgsl"))
-                .new_vertex_buffer(
-                    &VertexBufferLayout::new()
-                        .new_array_stride(mem::size_of::<Vertex>() as u64)
-                        .new_step_mode(wgpu::VertexStepMode::Vertex)
-                        .new_attribute(wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x4,
-                            offset: 0,
-                            shader_location: 0,
-                        })
-                        .build(),
-                )
-                .new_target(renderer_server.get_swapchain().get_format().into())
-                .build("triangle pipeline", &renderer_server.device);
-            triangle_pipeline.bind_resource(
-                &renderer_server.device,
-                0,
-                &[uniform_buffer.get_native_buffer().as_entire_binding()],
-            );
-
-            //create vertex & index buffer
-            let (vertex_data, index_data) = create_vertices();
-            index_count = index_data.len() as u32;
-            vertex_buffer = Buffer::new("vertex buffer")
-                .new_content(bytemuck::cast_slice(&vertex_data))
-                .new_usage(wgpu::BufferUsages::VERTEX)
-                .build(&renderer_server.device);
-            index_buffer = Buffer::new("index buffer")
-                .new_content(bytemuck::cast_slice(&index_data))
-                .new_usage(wgpu::BufferUsages::INDEX)
-                .build(&renderer_server.device);
-        }
+        let hello_cube = Rc::new(RefCell::new(HelloCube::new(&eng)));
+        let resized_cube = hello_cube.clone();
         eng.app_loop(
             Box::new(move |engine| {
-                let renderer_server = engine.get_renderer_server();
-                let (frame, frame_view, _depth_view) = renderer_server.get_new_frame();
-                let mut main_buffer =
-                    CommandBuffer::new_command_buffer(&renderer_server.device, "main_buffer");
-                {
-                    let mut main_pass = RenderPassBuilder::new("main_pass")
-                        .color_attachment(&frame_view, [0.1, 0.5, 0.3, 1.0])
-                        .build(&mut main_buffer);
-                    main_pass.set_pipeline(triangle_pipeline.get_native_pipeline());
-                    let bind_groups = triangle_pipeline.get_bind_groups();
-                    main_pass.set_bind_group(0, bind_groups[0], &[0]);
-                    main_pass.set_vertex_buffer(0, vertex_buffer.get_native_buffer().slice(..));
-                    main_pass.set_index_buffer(
-                        index_buffer.get_native_buffer().slice(..),
-                        wgpu::IndexFormat::Uint16,
-                    );
-                    main_pass.draw_indexed(0..index_count, 0, 0..1);
-                }
-                main_buffer.finish_command_buffer(&renderer_server.queue);
-                frame.present();
+                hello_cube.borrow().render(engine);
             }),
             Box::new(move |engine, resolution| {
-                let mx_total = generate_matrix(resolution.0 as f32 / resolution.1 as f32);
-                let mx_ref: &[f32; 16] = mx_total.as_ref();
-                uniform_buffer.write(
-                    &engine.get_renderer_server().queue,
-                    0,
-                    bytemuck::cast_slice(mx_ref),
-                );
+                resized_cube.borrow_mut().resize(engine, resolution);
             }),
         );
     }
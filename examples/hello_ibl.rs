@@ -0,0 +1,119 @@
+use glam::{Vec3, Vec4};
+use std::f32::consts::PI;
+use wgpu_engine::engine::scene::nodes::camera_3d::Camera3D;
+use wgpu_engine::engine::scene::nodes::light::DirectionalLight;
+use wgpu_engine::engine::scene::nodes::mesh_instance::mesh::{Mesh, VertexDataBuilder};
+use wgpu_engine::engine::scene::nodes::mesh_instance::MeshInstance;
+use wgpu_engine::engine::scene::nodes::node::Node;
+use wgpu_engine::engine::scene::nodes::BaseNode;
+use wgpu_engine::engine::servers::renderer::material::Material;
+use wgpu_engine::engine::servers::renderer::PBR_PIPELINE;
+use wgpu_engine::engine::Engine;
+
+fn create_sphere(eng: &mut Engine, rings: u32, segments: u32) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut tex_coords: Vec<[f32; 2]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for ring in 0..=rings {
+        let v = ring as f32 / rings as f32;
+        let polar = v * PI;
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let azimuth = u * 2.0 * PI;
+            positions.push([
+                polar.sin() * azimuth.cos(),
+                polar.sin() * azimuth.sin(),
+                polar.cos(),
+            ]);
+            tex_coords.push([u, v]);
+        }
+    }
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let first = ring * (segments + 1) + segment;
+            let second = first + segments + 1;
+            indices.extend_from_slice(&[first, second, first + 1, first + 1, second, second + 1]);
+        }
+    }
+    // the normals of a unit sphere are its positions
+    VertexDataBuilder::new()
+        .set_vertex_positions(&positions)
+        .set_vertex_normals(&positions)
+        .set_vertex_tex_coords(&tex_coords)
+        .set_indicies(&indices)
+        .build("ibl sphere", eng)
+}
+
+pub fn setup(eng: &mut Engine) {
+    eng.load_environment(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/examples/assets/sky.hdr"
+    ))
+    .unwrap();
+    let sphere = create_sphere(eng, 24, 48);
+
+    eng.add_root_node(Node::new("scene root"));
+    let root = eng.get_root_node_mut::<Node>().unwrap();
+    let mut camera = Camera3D::new("camera");
+    camera.transform.set_translation(Vec3::new(0.0, -9.0, 2.0));
+    camera.transform.look_at(Vec3::new(0.0, 0.0, 0.5), Vec3::Z);
+    root.add_node(Box::new(camera));
+
+    // roughness grows from left to right, the top row is metallic
+    for row in 0..2 {
+        for column in 0..4 {
+            let name = format!("sphere {} {}", row, column);
+            let material = Material::new_pbr(&name)
+                .new_base_color(Vec4::new(0.95, 0.95, 0.95, 1.0))
+                .new_metallic(row as f32)
+                .new_roughness(0.05 + column as f32 * 0.3)
+                .build(&eng.get_renderer_server().device);
+            eng.set_material(material);
+            let root = eng.get_root_node_mut::<Node>().unwrap();
+            let mut instance = MeshInstance::new(&name);
+            instance.mesh = Some(sphere.clone());
+            instance.pipeline_id = PBR_PIPELINE.to_string();
+            instance.material_id = name.clone();
+            instance.transform.set_translation(Vec3::new(
+                (column as f32 - 1.5) * 2.2,
+                0.0,
+                row as f32 * 2.2 - 0.6,
+            ));
+            root.add_node(Box::new(instance));
+        }
+    }
+
+    // lines up with the sun painted into sky.hdr
+    let root = eng.get_root_node_mut::<Node>().unwrap();
+    let mut sun = DirectionalLight::new("sun");
+    sun.intensity = 1.0;
+    sun.transform.set_rotation(Vec3::new(40.0, -60.0, 0.0));
+    root.add_node(Box::new(sun));
+}
+
+pub fn update(engine: &mut Engine, time: &mut f32) {
+    *time += 0.01;
+    if let Some(root) = engine.get_root_node_mut::<Node>() {
+        if let Some(camera) = root.get_node_mut("camera") {
+            let camera = camera.as_any_mut().downcast_mut::<Camera3D>().unwrap();
+            let angle = time.sin() * 0.5;
+            camera.transform.set_translation(Vec3::new(
+                -9.0 * angle.sin(),
+                -9.0 * angle.cos(),
+                2.0,
+            ));
+            camera.transform.look_at(Vec3::new(0.0, 0.0, 0.5), Vec3::Z);
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let mut eng = pollster::block_on(Engine::new("hello ibl", (1280, 720)));
+    setup(&mut eng);
+    let mut time: f32 = 0.0;
+    eng.app_loop(
+        Box::new(move |engine| update(engine, &mut time)),
+        Box::new(|_engine, _resolution| {}),
+    );
+}
@@ -0,0 +1,141 @@
+use glam::{Vec3, Vec4};
+use wgpu_engine::engine::scene::nodes::camera_3d::Camera3D;
+use wgpu_engine::engine::scene::nodes::light::{DirectionalLight, PointLight, SpotLight};
+use wgpu_engine::engine::scene::nodes::mesh_instance::mesh::{Mesh, VertexDataBuilder};
+use wgpu_engine::engine::scene::nodes::mesh_instance::MeshInstance;
+use wgpu_engine::engine::scene::nodes::node::Node;
+use wgpu_engine::engine::scene::nodes::BaseNode;
+use wgpu_engine::engine::servers::renderer::material::Material;
+use wgpu_engine::engine::servers::renderer::LIT_PIPELINE;
+use wgpu_engine::engine::Engine;
+
+fn create_cube(eng: &mut Engine) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    // every face gets its own vertices so that the edges stay sharp
+    for normal in [
+        Vec3::X,
+        Vec3::NEG_X,
+        Vec3::Y,
+        Vec3::NEG_Y,
+        Vec3::Z,
+        Vec3::NEG_Z,
+    ] {
+        let tangent = if normal.z.abs() > 0.5 {
+            Vec3::X
+        } else {
+            Vec3::Z
+        };
+        let bitangent = normal.cross(tangent);
+        let first = positions.len() as u32;
+        for (u, v) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            positions.push((normal + tangent * u + bitangent * v).to_array());
+            normals.push(normal.to_array());
+        }
+        indices.extend_from_slice(&[first, first + 1, first + 2, first + 2, first + 3, first]);
+    }
+    VertexDataBuilder::new()
+        .set_vertex_positions(&positions)
+        .set_vertex_normals(&normals)
+        .set_indicies(&indices)
+        .build("lit cube", eng)
+}
+
+fn add_cube(root: &mut Node, name: &str, mesh: &Mesh, material_id: &str) {
+    let mut instance = MeshInstance::new(name);
+    instance.mesh = Some(mesh.clone());
+    instance.pipeline_id = LIT_PIPELINE.to_string();
+    instance.material_id = material_id.to_string();
+    root.add_node(Box::new(instance));
+}
+
+pub fn setup(eng: &mut Engine) {
+    let cube = create_cube(eng);
+    for (material_id, color) in [
+        ("floor material", Vec4::new(0.8, 0.8, 0.8, 1.0)),
+        ("red material", Vec4::new(0.9, 0.2, 0.2, 1.0)),
+    ] {
+        let material =
+            Material::new_lit(material_id, color).build(&eng.get_renderer_server().device);
+        eng.set_material(material);
+    }
+
+    eng.add_root_node(Node::new("scene root"));
+    let root = eng.get_root_node_mut::<Node>().unwrap();
+    let mut camera = Camera3D::new("camera");
+    camera.transform.set_translation(Vec3::new(0.0, -9.0, 6.0));
+    camera.transform.look_at(Vec3::ZERO, Vec3::Z);
+    root.add_node(Box::new(camera));
+
+    add_cube(root, "floor", &cube, "floor material");
+    if let Some(floor) = root.get_node_mut("floor") {
+        let floor = floor.as_any_mut().downcast_mut::<MeshInstance>().unwrap();
+        floor.transform.set_translation(Vec3::new(0.0, 0.0, -1.1));
+        floor.transform.set_scale(Vec3::new(5.0, 5.0, 0.1));
+    }
+    // drawn with the lit pipeline's default material
+    add_cube(root, "white cube", &cube, "");
+    if let Some(cube) = root.get_node_mut("white cube") {
+        let cube = cube.as_any_mut().downcast_mut::<MeshInstance>().unwrap();
+        cube.transform.set_translation(Vec3::new(-2.0, 0.0, 0.0));
+    }
+    add_cube(root, "red cube", &cube, "red material");
+    if let Some(cube) = root.get_node_mut("red cube") {
+        let cube = cube.as_any_mut().downcast_mut::<MeshInstance>().unwrap();
+        cube.transform.set_translation(Vec3::new(2.0, 0.0, 0.0));
+    }
+
+    let mut sun = DirectionalLight::new("sun");
+    sun.intensity = 0.6;
+    sun.cast_shadows = true;
+    sun.shadow_distance = 20.0;
+    sun.transform.set_rotation(Vec3::new(30.0, 20.0, 0.0));
+    root.add_node(Box::new(sun));
+
+    let mut point_light = PointLight::new("point light");
+    point_light.color = Vec3::new(1.0, 0.7, 0.3);
+    point_light.intensity = 6.0;
+    point_light
+        .transform
+        .set_translation(Vec3::new(0.0, -2.0, 1.5));
+    root.add_node(Box::new(point_light));
+
+    let mut spot_light = SpotLight::new("spot light");
+    spot_light.color = Vec3::new(0.3, 0.5, 1.0);
+    spot_light.intensity = 40.0;
+    spot_light.range = 15.0;
+    spot_light.inner_angle = 15.0;
+    spot_light.outer_angle = 25.0;
+    spot_light.cast_shadows = true;
+    spot_light
+        .transform
+        .set_translation(Vec3::new(-2.0, -1.0, 6.0));
+    spot_light
+        .transform
+        .look_at(Vec3::new(-2.0, 0.0, 0.0), Vec3::Y);
+    root.add_node(Box::new(spot_light));
+}
+
+pub fn update(engine: &mut Engine, time: &mut f32) {
+    *time += 0.02;
+    if let Some(root) = engine.get_root_node_mut::<Node>() {
+        if let Some(light) = root.get_node_mut("point light") {
+            let light = light.as_any_mut().downcast_mut::<PointLight>().unwrap();
+            light
+                .transform
+                .set_translation(Vec3::new(3.0 * time.sin(), -2.0, 1.5));
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let mut eng = pollster::block_on(Engine::new("hello lights", (1280, 720)));
+    setup(&mut eng);
+    let mut time: f32 = 0.0;
+    eng.app_loop(
+        Box::new(move |engine| update(engine, &mut time)),
+        Box::new(|_engine, _resolution| {}),
+    );
+}
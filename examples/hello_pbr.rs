@@ -0,0 +1,165 @@
+use glam::{Vec3, Vec4};
+use std::f32::consts::PI;
+use wgpu_engine::engine::scene::nodes::camera_3d::Camera3D;
+use wgpu_engine::engine::scene::nodes::light::{DirectionalLight, PointLight};
+use wgpu_engine::engine::scene::nodes::mesh_instance::mesh::{Mesh, VertexDataBuilder};
+use wgpu_engine::engine::scene::nodes::mesh_instance::MeshInstance;
+use wgpu_engine::engine::scene::nodes::node::Node;
+use wgpu_engine::engine::scene::nodes::BaseNode;
+use wgpu_engine::engine::servers::renderer::material::Material;
+use wgpu_engine::engine::servers::renderer::PBR_PIPELINE;
+use wgpu_engine::engine::Engine;
+
+fn create_sphere(eng: &mut Engine, rings: u32, segments: u32) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut tex_coords: Vec<[f32; 2]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for ring in 0..=rings {
+        let v = ring as f32 / rings as f32;
+        let polar = v * PI;
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let azimuth = u * 2.0 * PI;
+            let normal = Vec3::new(
+                polar.sin() * azimuth.cos(),
+                polar.sin() * azimuth.sin(),
+                polar.cos(),
+            );
+            positions.push(normal.to_array());
+            normals.push(normal.to_array());
+            tex_coords.push([u, v]);
+        }
+    }
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let first = ring * (segments + 1) + segment;
+            let second = first + segments + 1;
+            indices.extend_from_slice(&[first, second, first + 1, first + 1, second, second + 1]);
+        }
+    }
+    VertexDataBuilder::new()
+        .set_vertex_positions(&positions)
+        .set_vertex_normals(&normals)
+        .set_vertex_tex_coords(&tex_coords)
+        .set_indicies(&indices)
+        .build("pbr sphere", eng)
+}
+
+fn create_plane(eng: &mut Engine) -> Mesh {
+    VertexDataBuilder::new()
+        .set_vertex_positions(&[
+            [-1.0, -1.0, 0.0],
+            [1.0, -1.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [-1.0, 1.0, 0.0],
+        ])
+        .set_vertex_normals(&[[0.0, 0.0, 1.0]; 4])
+        .set_vertex_tex_coords(&[[0.0, 4.0], [4.0, 4.0], [4.0, 0.0], [0.0, 0.0]])
+        .set_indicies(&[0, 1, 2, 2, 3, 0])
+        .build("pbr plane", eng)
+}
+
+fn add_instance(root: &mut Node, name: &str, mesh: &Mesh, material_id: &str) {
+    let mut instance = MeshInstance::new(name);
+    instance.mesh = Some(mesh.clone());
+    instance.pipeline_id = PBR_PIPELINE.to_string();
+    instance.material_id = material_id.to_string();
+    root.add_node(Box::new(instance));
+}
+
+pub fn setup(eng: &mut Engine) {
+    let sphere = create_sphere(eng, 16, 32);
+    let plane = create_plane(eng);
+    eng.load_texture(
+        "floor texture",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/examples/assets/checker.png"),
+    )
+    .unwrap();
+    let floor_material = Material::new_pbr("floor material")
+        .new_base_color_texture("floor texture")
+        .new_roughness(0.8)
+        .build(&eng.get_renderer_server().device);
+    eng.set_material(floor_material);
+
+    eng.add_root_node(Node::new("scene root"));
+    let root = eng.get_root_node_mut::<Node>().unwrap();
+    let mut camera = Camera3D::new("camera");
+    camera.transform.set_translation(Vec3::new(0.0, -10.0, 5.0));
+    camera.transform.look_at(Vec3::new(0.0, 0.0, 0.5), Vec3::Z);
+    root.add_node(Box::new(camera));
+
+    add_instance(root, "floor", &plane, "floor material");
+    if let Some(floor) = root.get_node_mut("floor") {
+        let floor = floor.as_any_mut().downcast_mut::<MeshInstance>().unwrap();
+        floor.transform.set_translation(Vec3::new(0.0, 0.0, -0.6));
+        floor.transform.set_scale(Vec3::new(6.0, 6.0, 1.0));
+    }
+
+    // roughness grows from left to right, the back row is metallic
+    for row in 0..2 {
+        for column in 0..5 {
+            let name = format!("sphere {} {}", row, column);
+            let material = Material::new_pbr(&name)
+                .new_base_color(Vec4::new(1.0, 0.6, 0.2, 1.0))
+                .new_metallic(row as f32)
+                .new_roughness(0.1 + column as f32 * 0.225)
+                .build(&eng.get_renderer_server().device);
+            eng.set_material(material);
+            let root = eng.get_root_node_mut::<Node>().unwrap();
+            add_instance(root, &name, &sphere, &name);
+            if let Some(instance) = root.get_node_mut(&name) {
+                let instance = instance
+                    .as_any_mut()
+                    .downcast_mut::<MeshInstance>()
+                    .unwrap();
+                instance.transform.set_translation(Vec3::new(
+                    (column as f32 - 2.0) * 2.2,
+                    row as f32 * 2.5,
+                    0.5,
+                ));
+                instance.transform.set_scale(Vec3::splat(0.9));
+            }
+        }
+    }
+
+    let root = eng.get_root_node_mut::<Node>().unwrap();
+    let mut sun = DirectionalLight::new("sun");
+    sun.intensity = 1.5;
+    sun.cast_shadows = true;
+    sun.shadow_distance = 25.0;
+    sun.transform.set_rotation(Vec3::new(40.0, -30.0, 0.0));
+    root.add_node(Box::new(sun));
+
+    let mut point_light = PointLight::new("point light");
+    point_light.color = Vec3::new(0.4, 0.6, 1.0);
+    point_light.intensity = 30.0;
+    point_light.range = 12.0;
+    point_light
+        .transform
+        .set_translation(Vec3::new(0.0, -3.0, 3.0));
+    root.add_node(Box::new(point_light));
+}
+
+pub fn update(engine: &mut Engine, time: &mut f32) {
+    *time += 0.02;
+    if let Some(root) = engine.get_root_node_mut::<Node>() {
+        if let Some(light) = root.get_node_mut("point light") {
+            let light = light.as_any_mut().downcast_mut::<PointLight>().unwrap();
+            light
+                .transform
+                .set_translation(Vec3::new(4.0 * time.sin(), -3.0, 3.0));
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let mut eng = pollster::block_on(Engine::new("hello pbr", (1280, 720)));
+    setup(&mut eng);
+    let mut time: f32 = 0.0;
+    eng.app_loop(
+        Box::new(move |engine| update(engine, &mut time)),
+        Box::new(|_engine, _resolution| {}),
+    );
+}
@@ -0,0 +1,179 @@
+use glam::{Vec3, Vec4};
+use std::f32::consts::PI;
+use wgpu_engine::engine::scene::nodes::camera_3d::Camera3D;
+use wgpu_engine::engine::scene::nodes::light::DirectionalLight;
+use wgpu_engine::engine::scene::nodes::mesh_instance::mesh::{Mesh, VertexDataBuilder};
+use wgpu_engine::engine::scene::nodes::mesh_instance::MeshInstance;
+use wgpu_engine::engine::scene::nodes::node::Node;
+use wgpu_engine::engine::scene::nodes::BaseNode;
+use wgpu_engine::engine::servers::renderer::material::Material;
+use wgpu_engine::engine::servers::renderer::post_process::{
+    get_neutral_lut_image, PostEffect, Tonemapper,
+};
+use wgpu_engine::engine::servers::renderer::resources::Texture;
+use wgpu_engine::engine::servers::renderer::PBR_PIPELINE;
+use wgpu_engine::engine::Engine;
+
+fn create_sphere(eng: &mut Engine, rings: u32, segments: u32) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut tex_coords: Vec<[f32; 2]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for ring in 0..=rings {
+        let v = ring as f32 / rings as f32;
+        let polar = v * PI;
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let azimuth = u * 2.0 * PI;
+            positions.push([
+                polar.sin() * azimuth.cos(),
+                polar.sin() * azimuth.sin(),
+                polar.cos(),
+            ]);
+            tex_coords.push([u, v]);
+        }
+    }
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let first = ring * (segments + 1) + segment;
+            let second = first + segments + 1;
+            indices.extend_from_slice(&[first, second, first + 1, first + 1, second, second + 1]);
+        }
+    }
+    VertexDataBuilder::new()
+        .set_vertex_positions(&positions)
+        .set_vertex_normals(&positions)
+        .set_vertex_tex_coords(&tex_coords)
+        .set_indicies(&indices)
+        .build("post sphere", eng)
+}
+
+// warm highlights and slightly lifted, cooler shadows
+fn create_lut(eng: &mut Engine) {
+    let mut lut = get_neutral_lut_image(16);
+    for pixel in lut.pixels_mut() {
+        let [r, g, b, _] = pixel.0.map(|c| c as f32 / 255.0);
+        let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let graded = [
+            r * 1.06 + 0.02 * (1.0 - luminance),
+            g * 1.01 + 0.02 * (1.0 - luminance),
+            b * 0.92 + 0.05 * (1.0 - luminance),
+        ];
+        for (channel, value) in graded.iter().enumerate() {
+            pixel.0[channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+    let renderer_server = eng.get_renderer_server();
+    let texture = Texture::new("warm lut")
+        .new_image(&lut)
+        .new_format(wgpu::TextureFormat::Rgba8Unorm)
+        .build(&renderer_server.device, &renderer_server.queue);
+    eng.set_texture(texture);
+}
+
+fn add_sphere(eng: &mut Engine, sphere: &Mesh, name: &str, material: Material, position: Vec3) {
+    eng.set_material(material);
+    let root = eng.get_root_node_mut::<Node>().unwrap();
+    let mut instance = MeshInstance::new(name);
+    instance.mesh = Some(sphere.clone());
+    instance.pipeline_id = PBR_PIPELINE.to_string();
+    instance.material_id = name.to_string();
+    instance.transform.set_translation(position);
+    root.add_node(Box::new(instance));
+}
+
+pub fn setup(eng: &mut Engine) {
+    eng.load_environment(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/examples/assets/sky.hdr"
+    ))
+    .unwrap();
+    create_lut(eng);
+    let sphere = create_sphere(eng, 24, 48);
+
+    eng.add_root_node(Node::new("scene root"));
+    let root = eng.get_root_node_mut::<Node>().unwrap();
+    let mut camera = Camera3D::new("camera");
+    camera.transform.set_translation(Vec3::new(0.0, -8.0, 1.5));
+    camera.transform.look_at(Vec3::new(0.0, 0.0, 0.3), Vec3::Z);
+    root.add_node(Box::new(camera));
+    let mut sun = DirectionalLight::new("sun");
+    sun.intensity = 2.0;
+    sun.transform.set_rotation(Vec3::new(40.0, -60.0, 0.0));
+    root.add_node(Box::new(sun));
+
+    // glowing spheres far brighter than 1 are what bloom picks up
+    for (index, color) in [
+        Vec3::new(4.0, 0.8, 0.1),
+        Vec3::new(0.1, 1.5, 4.0),
+        Vec3::new(3.0, 0.2, 3.0),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let name = format!("glowing sphere {}", index);
+        let material = Material::new_pbr(&name)
+            .new_base_color(Vec4::new(0.05, 0.05, 0.05, 1.0))
+            .new_emissive(color)
+            .build(&eng.get_renderer_server().device);
+        let position = Vec3::new((index as f32 - 1.0) * 2.6, 0.0, 1.2);
+        add_sphere(eng, &sphere, &name, material, position);
+    }
+    for (index, metallic) in [0.0, 1.0].into_iter().enumerate() {
+        let name = format!("sphere {}", index);
+        let material = Material::new_pbr(&name)
+            .new_base_color(Vec4::new(0.9, 0.9, 0.9, 1.0))
+            .new_metallic(metallic)
+            .new_roughness(0.25)
+            .build(&eng.get_renderer_server().device);
+        let position = Vec3::new((index as f32 - 0.5) * 2.6, 0.0, -1.0);
+        add_sphere(eng, &sphere, &name, material, position);
+    }
+
+    let post_process = eng.get_post_process_mut();
+    post_process.add_effect("exposure", PostEffect::Exposure { stops: 0.0 });
+    post_process.add_effect(
+        "bloom",
+        PostEffect::Bloom {
+            threshold: 1.0,
+            intensity: 0.3,
+        },
+    );
+    post_process.add_effect("tonemap", PostEffect::Tonemap(Tonemapper::Aces));
+    post_process.add_effect("fxaa", PostEffect::Fxaa);
+    post_process.add_effect(
+        "vignette",
+        PostEffect::Vignette {
+            intensity: 0.6,
+            radius: 0.45,
+            smoothness: 0.5,
+        },
+    );
+    post_process.add_effect(
+        "color grading",
+        PostEffect::ColorGrading {
+            lut_texture: "warm lut".to_string(),
+            intensity: 1.0,
+        },
+    );
+}
+
+// the exposure slowly pulses, effects can be changed, added and removed between frames
+pub fn update(engine: &mut Engine, time: &mut f32) {
+    *time += 0.02;
+    if let Some(PostEffect::Exposure { stops }) =
+        engine.get_post_process_mut().get_effect_mut("exposure")
+    {
+        *stops = time.sin() * 0.5;
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let mut eng = pollster::block_on(Engine::new("hello post processing", (1280, 720)));
+    setup(&mut eng);
+    let mut time: f32 = 0.0;
+    eng.app_loop(
+        Box::new(move |engine| update(engine, &mut time)),
+        Box::new(|_engine, _resolution| {}),
+    );
+}
@@ -7,7 +7,7 @@ fn main() {
     #[cfg(not(target_arch = "wasm32"))]
     {
         env_logger::init();
-        let mut eng = pollster::block_on(Engine::new("hello triangle", (1280, 720)));
+        let eng = pollster::block_on(Engine::new("hello triangle", (1280, 720)));
         let triangle_pipeline: RenderPipeline;
         {
             let renderer_server = eng.get_renderer_server();
@@ -19,7 +19,9 @@ fn main() {
         eng.app_loop(
             Box::new(move |engine| {
                 let renderer_server = engine.get_renderer_server();
-                let (frame, frame_view, _depth_view) = renderer_server.get_new_frame();
+                let Some((frame, frame_view, _depth_view)) = renderer_server.get_new_frame() else {
+                    return;
+                };
                 let mut main_buffer =
                     CommandBuffer::new_command_buffer(&renderer_server.device, "main_buffer");
                 {
@@ -9,7 +9,9 @@ fn main() {
         eng.app_loop(
             Box::new(move |engine| {
                 let renderer_server = engine.get_renderer_server();
-                let (frame, frame_view, depth_view) = renderer_server.get_new_frame();
+                let Some((frame, frame_view, depth_view)) = renderer_server.get_new_frame() else {
+                    return;
+                };
                 let mut main_buffer =
                     CommandBuffer::new_command_buffer(&renderer_server.device, "main_buffer");
                 RenderPassBuilder::new("main_pass")
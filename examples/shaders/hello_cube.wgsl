@@ -1,5 +1,6 @@
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
 };
 
 struct Camera {
@@ -8,18 +9,25 @@ struct Camera {
 @group(0)
 @binding(0)
 var<uniform> camera: Camera;
+@group(0)
+@binding(1)
+var color_texture: texture_2d<f32>;
+@group(0)
+@binding(2)
+var color_sampler: sampler;
 
 @vertex
 fn vs_main(
-    @location(0) position: vec4<f32>,
+    @location(0) position: vec3<f32>,
+    @location(2) tex_coord: vec2<f32>,
 ) -> VertexOutput {
     var result: VertexOutput;
-    result.position = camera.vp_matrix * position;
+    result.position = camera.vp_matrix * vec4<f32>(position, 1.0);
+    result.tex_coord = tex_coord;
     return result;
 }
 
 @fragment
 fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+    return textureSample(color_texture, color_sampler, vertex.tex_coord);
 }
-
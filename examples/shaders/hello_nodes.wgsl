@@ -4,6 +4,9 @@ struct VertexOutput {
 
 struct Camera {
     vp_matrix: mat4x4<f32>,
+    view_matrix: mat4x4<f32>,
+    projection_matrix: mat4x4<f32>,
+    position: vec4<f32>,
 }
 @group(0)
 @binding(0)
@@ -11,22 +14,31 @@ var<uniform> camera: Camera;
 
 struct Mesh {
     model_matrix: mat4x4<f32>,
+    normal_matrix: mat3x3<f32>,
+    flags: vec4<u32>,
 }
 @group(0)
 @binding(1)
-var<storage> meshes: Mesh;
+var<storage> meshes: array<Mesh>;
+
+struct Material {
+    color: vec4<f32>,
+}
+@group(2)
+@binding(0)
+var<uniform> material: Material;
 
 @vertex
 fn vs_main(
+    @builtin(instance_index) instance: u32,
     @location(0) position: vec3<f32>,
 ) -> VertexOutput {
     var result: VertexOutput;
-    result.position = camera.vp_matrix * meshes.model_matrix * vec4<f32>(position.x, position.y, position.z, 1.0);
+    result.position = camera.vp_matrix * meshes[instance].model_matrix * vec4<f32>(position.x, position.y, position.z, 1.0);
     return result;
 }
 
 @fragment
 fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+    return material.color;
 }
-
@@ -1,56 +1,567 @@
 pub mod scene;
 pub mod servers;
+pub mod testing;
 use servers::renderer;
+use servers::renderer::environment::{
+    EnvironmentMaps, BRDF_LUT_TEXTURE, ENVIRONMENT_SAMPLER, ENVIRONMENT_TEXTURE,
+    IRRADIANCE_TEXTURE, PREFILTERED_MIP_COUNT, PREFILTERED_TEXTURE,
+};
+use servers::renderer::material::{
+    get_lit_parameters, get_pbr_parameters, pack_material_parameters, Material, MaterialParameter,
+    PBR_DEFAULT_NORMAL_TEXTURE, PBR_DEFAULT_SAMPLER, PBR_DEFAULT_WHITE_TEXTURE,
+};
+use servers::renderer::post_process::PostProcess;
 use servers::renderer::resources::Buffer;
+use servers::renderer::{
+    BindingGroupType, GPUCamera, GPUEnvironment, GPULight, GPUMesh, GPUShadow,
+    RenderPipelineSettings, LIT_PIPELINE, MAX_INSTANCES_PER_DRAW, MAX_LIGHTS, MAX_MESH_INSTANCES,
+    MAX_SHADOW_CASCADES, MAX_SHADOW_MAPS, PBR_PIPELINE, SCENE_COLOR, SCENE_COLOR_FORMAT,
+    SHADOW_MAP_SIZE, SHADOW_PIPELINE, SKYBOX_PIPELINE,
+};
 use winit::{
     dpi::PhysicalSize,
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
 
 use scene::nodes::BaseNode;
 
-use std::{collections::VecDeque, mem};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use self::{
-    scene::nodes::mesh_instance::{mesh::Mesh, MeshInstance},
-    servers::renderer::resources::{CommandBuffer, RenderPassBuilder, RenderPipeline},
+    scene::nodes::camera_3d::Camera3D,
+    scene::nodes::light::{get_shadow_cascades, DirectionalLight, PointLight, SpotLight},
+    scene::nodes::mesh_instance::{
+        mesh::{get_vertex_buffer_name, Mesh, Vertex, VertexLayout},
+        MeshInstance,
+    },
+    scene::nodes::multi_mesh_instance::MultiMeshInstance,
+    scene::utils::{Aabb, Frustum},
+    servers::renderer::render_graph::{
+        RenderGraph, RenderGraphPass, RenderGraphResources, RenderGraphTexture, SWAPCHAIN_COLOR,
+        SWAPCHAIN_DEPTH,
+    },
+    servers::renderer::resources::{
+        CommandBuffer, ComputePipeline, RenderPassBuilder, RenderPipeline, Sampler, Texture,
+        VertexBufferLayout,
+    },
 };
 
+// global group of the pbr pipeline, the scene data followed by the image based lighting maps
+const PBR_GLOBAL_RESOURCES: [&str; 11] = [
+    "camera_buffer",
+    "mesh_buffer",
+    "light_buffer",
+    "shadow_buffer",
+    "shadow_maps",
+    "shadow_sampler",
+    "environment_buffer",
+    IRRADIANCE_TEXTURE,
+    PREFILTERED_TEXTURE,
+    BRDF_LUT_TEXTURE,
+    ENVIRONMENT_SAMPLER,
+];
+const SKYBOX_GLOBAL_RESOURCES: [&str; 4] = [
+    "camera_buffer",
+    "environment_buffer",
+    ENVIRONMENT_TEXTURE,
+    ENVIRONMENT_SAMPLER,
+];
+
+pub type UpdateCallback = Box<dyn FnMut(&mut Engine)>;
+pub type ResizeCallback = Box<dyn FnMut(&mut Engine, (u32, u32))>;
+
+// mesh instances found during the scene traversal, culled once the camera is known
+struct DrawCandidate {
+    mesh_id: String,
+    pipeline_id: String,
+    material_id: String,
+    cast_shadows: bool,
+    receive_shadows: bool,
+    vertex_layout: VertexLayout,
+    index_count: u32,
+    // world matrix and world space bounds of every instance
+    instances: Vec<(glam::Mat4, Aabb)>,
+}
+
+// instances collected during the scene traversal that can be drawn together
+struct DrawBatch {
+    mesh_id: String,
+    pipeline_id: String,
+    material_id: String,
+    cast_shadows: bool,
+    receive_shadows: bool,
+    // false for instances outside the view that are only drawn into the shadow maps
+    visible: bool,
+    vertex_layout: VertexLayout,
+    index_count: u32,
+    transforms: Vec<glam::Mat4>,
+}
+
+struct DrawCall {
+    mesh_id: String,
+    pipeline_id: String,
+    material_id: String,
+    cast_shadows: bool,
+    visible: bool,
+    vertex_layout: VertexLayout,
+    index_count: u32,
+    // dynamic offset of the draw's first GPUMesh inside mesh_buffer
+    mesh_offset: u32,
+    instance_count: u32,
+}
+
+// what the frustum culling did to the last prepared frame
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CullingStats {
+    pub total_instances: u32,
+    pub visible_instances: u32,
+    pub culled_instances: u32,
+    // culled instances that are still drawn into the shadow maps
+    pub shadow_only_instances: u32,
+    // draw calls of the main pass
+    pub draw_calls: u32,
+}
+
 pub struct Engine {
     renderer_server: renderer::RendererServer,
-    window: Window,
+    // window and event loop are None when running headless
+    window: Option<Window>,
     event_loop: Option<EventLoop<()>>,
     root_node: Option<Box<dyn BaseNode>>,
     buffers: Vec<Buffer>,
+    textures: Vec<Texture>,
+    samplers: Vec<Sampler>,
     render_pipelines: Vec<RenderPipeline>,
+    materials: Vec<Material>,
+    compute_pipelines: Vec<ComputePipeline>,
+    draw_calls: Vec<DrawCall>,
+    frustum_culling: bool,
+    // culls with this matrix instead of the one of the current Camera3D when set
+    culling_view_projection: Option<glam::Mat4>,
+    culling_stats: CullingStats,
+    // meshes without positions that were reported as not casting shadows
+    shadowless_meshes: HashSet<String>,
+    // one render target per layer of the shadow_maps texture
+    shadow_layer_views: Vec<wgpu::TextureView>,
+    // layers that are rendered this frame
+    shadow_layer_count: u32,
+    environment: GPUEnvironment,
+    // draws the environment behind the scene once one has been loaded
+    skybox_enabled: bool,
+    post_process: PostProcess,
+    // taken out while the passes are executed since they get access to the engine
+    render_graph: Option<RenderGraph>,
+    // written to disk at the end of the next rendered frame
+    pending_screenshot: Option<PathBuf>,
+    screenshot_key: Option<VirtualKeyCode>,
 }
 
 impl Engine {
     pub async fn new(name: &str, resolution: (u32, u32)) -> Engine {
         let event_loop = EventLoop::new();
         let window = Window::new(&event_loop).unwrap();
-        window.set_title(&name);
+        window.set_title(name);
         window.set_inner_size(PhysicalSize::new(resolution.0, resolution.1));
         let renderer_server = renderer::RendererServer::new(&window).await;
+        Self::with_renderer_server(renderer_server, Some(window), Some(event_loop))
+    }
 
+    // Creates an engine without a window that renders into an offscreen texture.
+    // Frames are produced by calling step() instead of app_loop()
+    pub async fn new_headless(resolution: (u32, u32), force_fallback_adapter: bool) -> Engine {
+        let renderer_server =
+            renderer::RendererServer::new_headless(resolution, force_fallback_adapter).await;
+        Self::with_renderer_server(renderer_server, None, None)
+    }
+
+    fn with_renderer_server(
+        renderer_server: renderer::RendererServer,
+        window: Option<Window>,
+        event_loop: Option<EventLoop<()>>,
+    ) -> Engine {
         // global buffers
-        let mut buffers: Vec<Buffer> = Vec::new();
+        // every MeshInstance gets a GPUMesh slot, shaders index it with the instance index
+        // relative to the dynamic offset of the draw
         let mesh_buffer = Buffer::new("mesh_buffer")
-            .new_size(mem::size_of::<glam::Mat4>() as u64 * 10000)
+            .new_size(GPUMesh::get_size() * (MAX_MESH_INSTANCES + MAX_INSTANCES_PER_DRAW))
+            .new_usage(wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST)
+            .new_binding_size(GPUMesh::get_size() * MAX_INSTANCES_PER_DRAW)
+            .build(&renderer_server.device);
+        // view and projection of the current Camera3D, rewritten every frame
+        let camera_buffer = Buffer::new("camera_buffer")
+            .new_content(bytemuck::bytes_of(&GPUCamera::new()))
+            .new_usage(wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST)
+            .build(&renderer_server.device);
+        // light count followed by every light of the scene, rewritten every frame
+        let light_buffer = Buffer::new("light_buffer")
+            .new_size(16 + GPULight::get_size() * MAX_LIGHTS)
+            .new_usage(wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST)
+            .build(&renderer_server.device);
+        // light space matrices of every shadow map layer, read by the lit shader
+        let shadow_buffer = Buffer::new("shadow_buffer")
+            .new_size(GPUShadow::get_size() * MAX_SHADOW_MAPS)
             .new_usage(wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST)
             .build(&renderer_server.device);
-        buffers.push(mesh_buffer);
+        // the same matrices in aligned slots, the shadow pass picks a layer with the dynamic offset
+        let shadow_view_buffer = Buffer::new("shadow_view_buffer")
+            .new_size(renderer_server.get_uniform_aligned_buffer_size(64) * MAX_SHADOW_MAPS)
+            .new_usage(wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST)
+            .new_binding_size(64)
+            .build(&renderer_server.device);
+        // image based lighting settings, rewritten when they change
+        let environment_buffer = Buffer::new("environment_buffer")
+            .new_content(bytemuck::bytes_of(&GPUEnvironment::new()))
+            .new_usage(wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST)
+            .build(&renderer_server.device);
+        let buffers: Vec<Buffer> = vec![
+            mesh_buffer,
+            camera_buffer,
+            light_buffer,
+            shadow_buffer,
+            shadow_view_buffer,
+            environment_buffer,
+        ];
+        let shadow_maps = Texture::new("shadow_maps")
+            .new_size((SHADOW_MAP_SIZE, SHADOW_MAP_SIZE))
+            .new_array_layers(MAX_SHADOW_MAPS as u32)
+            .new_format(wgpu::TextureFormat::Depth32Float)
+            .new_usage(
+                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            )
+            .new_view_dimension(wgpu::TextureViewDimension::D2Array)
+            .build(&renderer_server.device, &renderer_server.queue);
+        let shadow_layer_views: Vec<wgpu::TextureView> = (0..MAX_SHADOW_MAPS as u32)
+            .map(|layer| shadow_maps.create_layer_view(layer, 0))
+            .collect();
+        let shadow_sampler = Sampler::new("shadow_sampler")
+            .new_address_mode(wgpu::AddressMode::ClampToEdge)
+            .new_filter(wgpu::FilterMode::Linear)
+            .new_compare(wgpu::CompareFunction::LessEqual)
+            .build(&renderer_server.device);
+
+        let post_process = PostProcess::new(
+            &renderer_server.device,
+            renderer_server.get_swapchain().get_format(),
+        );
+        let mut render_graph = RenderGraph::new();
+        render_graph.import_texture("shadow_maps");
+        render_graph.add_texture(
+            SCENE_COLOR,
+            RenderGraphTexture {
+                format: SCENE_COLOR_FORMAT,
+                ..Default::default()
+            },
+        );
+        PostProcess::add_textures(&mut render_graph);
+        render_graph.add_pass(
+            RenderGraphPass::new("shadow_pass")
+                .new_texture_write("shadow_maps")
+                .new_buffer_read("mesh_buffer")
+                .new_buffer_read("shadow_view_buffer")
+                .build(Box::new(|engine, command_buffer, _| {
+                    engine.record_shadow_pass(command_buffer)
+                })),
+        );
+        render_graph.add_pass(
+            RenderGraphPass::new("main_pass")
+                .new_texture_write(SCENE_COLOR)
+                .new_texture_write(SWAPCHAIN_DEPTH)
+                .new_texture_read("shadow_maps")
+                .new_buffer_read("mesh_buffer")
+                .new_buffer_read("camera_buffer")
+                .new_buffer_read("light_buffer")
+                .new_buffer_read("shadow_buffer")
+                .new_buffer_read("environment_buffer")
+                .build(Box::new(|engine, command_buffer, resources| {
+                    engine.record_main_pass(command_buffer, resources)
+                })),
+        );
+        let mut post_process_pass = RenderGraphPass::new("post_process_pass");
+        post_process_pass
+            .new_texture_read(SCENE_COLOR)
+            .new_texture_write(SWAPCHAIN_COLOR);
+        for name in PostProcess::get_texture_names() {
+            post_process_pass.new_texture_write(&name);
+        }
+        render_graph.add_pass(post_process_pass.build(Box::new(
+            |engine, command_buffer, resources| {
+                engine.post_process.record(
+                    &engine.renderer_server.device,
+                    command_buffer,
+                    resources,
+                    |id| engine.get_texture(id),
+                )
+            },
+        )));
 
-        Engine {
+        let mut engine = Engine {
             renderer_server,
             window,
-            event_loop: Some(event_loop),
+            event_loop,
             root_node: None,
-            buffers: buffers,
+            buffers,
+            textures: vec![shadow_maps],
+            samplers: vec![shadow_sampler],
             render_pipelines: Vec::new(),
-        }
+            materials: Vec::new(),
+            compute_pipelines: Vec::new(),
+            draw_calls: Vec::new(),
+            shadow_layer_views,
+            shadow_layer_count: 0,
+            environment: GPUEnvironment::new(),
+            skybox_enabled: true,
+            frustum_culling: true,
+            culling_view_projection: None,
+            shadowless_meshes: HashSet::new(),
+            culling_stats: CullingStats::default(),
+            post_process,
+            render_graph: Some(render_graph),
+            pending_screenshot: None,
+            screenshot_key: Some(VirtualKeyCode::F12),
+        };
+        engine.create_shadow_pipeline(std::mem::size_of::<Vertex>() as u64);
+        engine.create_lit_pipeline();
+        engine.create_pbr_pipeline();
+        engine.create_skybox_pipeline();
+        engine
+    }
+
+    // Depth only pipeline the shadow casters are drawn with. It only reads the positions at
+    // the start of the first vertex buffer, so one pipeline serves every layout with the same stride
+    fn create_shadow_pipeline(&mut self, vertex_stride: u64) {
+        let pipeline_id = get_shadow_pipeline_id(vertex_stride);
+        let mut vertex_layout = VertexBufferLayout::new();
+        vertex_layout
+            .new_attribute(wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x3,
+                offset: 0,
+                shader_location: 0,
+            })
+            .new_array_stride(vertex_stride)
+            .new_step_mode(wgpu::VertexStepMode::Vertex);
+        let pipeline = RenderPipeline::new()
+            .new_shader(include_str!("engine/servers/renderer/shaders/shadow.wgsl"))
+            .new_vertex_buffer(&vertex_layout)
+            .new_settings(&RenderPipelineSettings {
+                depth_testing: true,
+                depth_format: wgpu::TextureFormat::Depth32Float,
+                ..Default::default()
+            })
+            .new_depth_bias(2, 2.0)
+            .build(&pipeline_id, &self.renderer_server.device);
+        self.set_render_pipeline(&pipeline_id, pipeline);
+        self.bind_resources_to_pipeline(
+            &pipeline_id,
+            BindingGroupType::Global as u8,
+            &["shadow_view_buffer", "mesh_buffer"],
+        );
+    }
+
+    // Built-in pipeline for meshes with normals, it's lit by every light node in the scene.
+    // Instances without a material are drawn with the default parameters of Material::new_lit
+    fn create_lit_pipeline(&mut self) {
+        let vertex_layout = Vertex::get_vertex_buffer_layout();
+        let pipeline = RenderPipeline::new()
+            .new_shader(concat!(
+                include_str!("engine/servers/renderer/shaders/scene.wgsl"),
+                include_str!("engine/servers/renderer/shaders/lit.wgsl")
+            ))
+            .new_vertex_buffer(&vertex_layout)
+            .new_target(SCENE_COLOR_FORMAT.into())
+            .new_settings(&RenderPipelineSettings {
+                depth_testing: true,
+                depth_format: self.renderer_server.get_swapchain().get_depth_format(),
+                ..Default::default()
+            })
+            .build(LIT_PIPELINE, &self.renderer_server.device);
+        self.set_render_pipeline(LIT_PIPELINE, pipeline);
+        self.bind_resources_to_pipeline(
+            LIT_PIPELINE,
+            BindingGroupType::Global as u8,
+            &[
+                "camera_buffer",
+                "mesh_buffer",
+                "light_buffer",
+                "shadow_buffer",
+                "shadow_maps",
+                "shadow_sampler",
+            ],
+        );
+        // bind groups of the pipeline itself are used when a draw has no material
+        let default_material = Buffer::new("lit_default_material")
+            .new_content(&pack_material_parameters(&get_lit_parameters(
+                glam::Vec4::ONE,
+            )))
+            .new_usage(wgpu::BufferUsages::UNIFORM)
+            .build(&self.renderer_server.device);
+        self.set_buffer(default_material);
+        self.bind_resources_to_pipeline(
+            LIT_PIPELINE,
+            BindingGroupType::Resource as u8,
+            &["lit_default_material"],
+        );
+    }
+
+    // Built-in metallic-roughness pipeline lit like the lit pipeline. Instances without a
+    // material are drawn white and fully rough
+    fn create_pbr_pipeline(&mut self) {
+        // black maps until an environment is loaded
+        let maps =
+            EnvironmentMaps::new_empty(&self.renderer_server.device, &self.renderer_server.queue);
+        self.set_environment_maps(maps);
+        let white = Texture::new(PBR_DEFAULT_WHITE_TEXTURE)
+            .new_content(&[255, 255, 255, 255])
+            .build(&self.renderer_server.device, &self.renderer_server.queue);
+        self.set_texture(white);
+        let normal = Texture::new(PBR_DEFAULT_NORMAL_TEXTURE)
+            .new_content(&[128, 128, 255, 255])
+            .build(&self.renderer_server.device, &self.renderer_server.queue);
+        self.set_texture(normal);
+        let sampler = Sampler::new(PBR_DEFAULT_SAMPLER)
+            .new_address_mode(wgpu::AddressMode::Repeat)
+            .new_filter(wgpu::FilterMode::Linear)
+            .new_mipmap_filter(wgpu::FilterMode::Linear)
+            .build(&self.renderer_server.device);
+        self.set_sampler(sampler);
+
+        let vertex_layout = Vertex::get_vertex_buffer_layout();
+        let pipeline = RenderPipeline::new()
+            .new_shader(concat!(
+                include_str!("engine/servers/renderer/shaders/scene.wgsl"),
+                include_str!("engine/servers/renderer/shaders/pbr.wgsl")
+            ))
+            .new_vertex_buffer(&vertex_layout)
+            .new_target(SCENE_COLOR_FORMAT.into())
+            .new_settings(&RenderPipelineSettings {
+                depth_testing: true,
+                depth_format: self.renderer_server.get_swapchain().get_depth_format(),
+                ..Default::default()
+            })
+            .build(PBR_PIPELINE, &self.renderer_server.device);
+        self.set_render_pipeline(PBR_PIPELINE, pipeline);
+        self.bind_resources_to_pipeline(
+            PBR_PIPELINE,
+            BindingGroupType::Global as u8,
+            &PBR_GLOBAL_RESOURCES,
+        );
+        let default_material = Buffer::new("pbr_default_material")
+            .new_content(&pack_material_parameters(&get_pbr_parameters()))
+            .new_usage(wgpu::BufferUsages::UNIFORM)
+            .build(&self.renderer_server.device);
+        self.set_buffer(default_material);
+        self.bind_resources_to_pipeline(
+            PBR_PIPELINE,
+            BindingGroupType::Resource as u8,
+            &[
+                "pbr_default_material",
+                PBR_DEFAULT_WHITE_TEXTURE,
+                PBR_DEFAULT_WHITE_TEXTURE,
+                PBR_DEFAULT_NORMAL_TEXTURE,
+                PBR_DEFAULT_WHITE_TEXTURE,
+                PBR_DEFAULT_WHITE_TEXTURE,
+                PBR_DEFAULT_SAMPLER,
+            ],
+        );
+    }
+
+    // Draws the loaded environment where the scene left the depth buffer cleared
+    fn create_skybox_pipeline(&mut self) {
+        let pipeline = RenderPipeline::new()
+            .new_shader(include_str!("engine/servers/renderer/shaders/skybox.wgsl"))
+            .new_target(SCENE_COLOR_FORMAT.into())
+            .new_settings(&RenderPipelineSettings {
+                depth_testing: true,
+                depth_write_enabled: false,
+                depth_format: self.renderer_server.get_swapchain().get_depth_format(),
+                ..Default::default()
+            })
+            .build(SKYBOX_PIPELINE, &self.renderer_server.device);
+        self.set_render_pipeline(SKYBOX_PIPELINE, pipeline);
+        self.bind_resources_to_pipeline(
+            SKYBOX_PIPELINE,
+            BindingGroupType::Global as u8,
+            &SKYBOX_GLOBAL_RESOURCES,
+        );
+    }
+
+    // registers the maps under their engine ids and rebinds the pipelines that read them
+    fn set_environment_maps(&mut self, maps: EnvironmentMaps) {
+        self.set_texture(maps.environment);
+        self.set_texture(maps.irradiance);
+        self.set_texture(maps.prefiltered);
+        self.set_texture(maps.brdf_lut);
+        self.set_sampler(maps.sampler);
+        self.bind_resources_to_pipeline(
+            PBR_PIPELINE,
+            BindingGroupType::Global as u8,
+            &PBR_GLOBAL_RESOURCES,
+        );
+        self.bind_resources_to_pipeline(
+            SKYBOX_PIPELINE,
+            BindingGroupType::Global as u8,
+            &SKYBOX_GLOBAL_RESOURCES,
+        );
+    }
+
+    // Loads an equirectangular image with Z up, usually a Radiance .hdr file, and bakes it into
+    // the maps the pbr pipeline uses for ambient light and the skybox draws
+    pub fn load_environment<P: AsRef<Path>>(&mut self, path: P) -> image::ImageResult<()> {
+        let image = image::open(path)?.to_rgba32f();
+        let maps = EnvironmentMaps::from_equirect(
+            &self.renderer_server.device,
+            &self.renderer_server.queue,
+            &image,
+        );
+        self.set_environment_maps(maps);
+        self.environment.enabled = 1;
+        self.environment.prefiltered_mip_count = PREFILTERED_MIP_COUNT as f32;
+        self.write_environment();
+        Ok(())
+    }
+
+    // scales both the ambient light and the skybox
+    pub fn set_environment_intensity(&mut self, intensity: f32) {
+        self.environment.intensity = intensity;
+        self.write_environment();
+    }
+
+    pub fn set_skybox_enabled(&mut self, enabled: bool) {
+        self.skybox_enabled = enabled;
+    }
+
+    // instances outside the view of the current camera are skipped by the main pass
+    pub fn set_frustum_culling(&mut self, enabled: bool) {
+        self.frustum_culling = enabled;
+    }
+
+    // for scenes that compute their own camera matrices instead of using a Camera3D,
+    // None goes back to the matrix of the current camera
+    pub fn set_culling_view_projection(&mut self, view_proj_mx: Option<glam::Mat4>) {
+        self.culling_view_projection = view_proj_mx;
+    }
+
+    pub fn get_culling_stats(&self) -> &CullingStats {
+        &self.culling_stats
+    }
+
+    fn write_environment(&mut self) {
+        let environment = self.environment;
+        self.write_to_buffer("environment_buffer", 0, bytemuck::bytes_of(&environment));
+    }
+
+    // effects applied to the scene before it is presented, they can be changed between frames
+    pub fn get_post_process(&self) -> &PostProcess {
+        &self.post_process
+    }
+
+    pub fn get_post_process_mut(&mut self) -> &mut PostProcess {
+        &mut self.post_process
     }
 
     pub fn get_renderer_server(&self) -> &renderer::RendererServer {
@@ -58,36 +569,39 @@ impl Engine {
     }
 
     pub fn set_vertex_buffer(&mut self, mesh_id: &str, data: &[u8]) {
-        if let Some(idx) = self
-            .buffers
-            .iter()
-            .position(|buf| buf.get_buffer_id() == format!("{} vertex_buffer", mesh_id).as_str())
-        {
-            self.buffers[idx].write(&self.renderer_server.queue, 0, data);
-        } else {
-            self.buffers.push(
-                Buffer::new(format!("{} vertex_buffer", mesh_id).as_str())
-                    .new_content(data)
-                    .new_usage(wgpu::BufferUsages::VERTEX)
-                    .build(&self.renderer_server.device),
-            );
-        }
+        self.set_vertex_stream(mesh_id, 0, data);
+    }
+
+    // meshes with a buffer per vertex attribute have one stream per buffer
+    pub fn set_vertex_stream(&mut self, mesh_id: &str, stream: u32, data: &[u8]) {
+        let buffer_id = get_vertex_buffer_name(mesh_id, stream);
+        self.set_mesh_buffer(&buffer_id, wgpu::BufferUsages::VERTEX, data);
     }
 
     pub fn set_index_buffer(&mut self, mesh_id: &str, data: &[u8]) {
-        if let Some(idx) = self
+        let buffer_id = format!("{} index_buffer", mesh_id);
+        self.set_mesh_buffer(&buffer_id, wgpu::BufferUsages::INDEX, data);
+    }
+
+    // rebuilt meshes can change size, so the buffer is only written in place when it still fits
+    fn set_mesh_buffer(&mut self, buffer_id: &str, usage: wgpu::BufferUsages, data: &[u8]) {
+        let index = self
             .buffers
             .iter()
-            .position(|buf| buf.get_buffer_id() == format!("{} index_buffer", mesh_id).as_str())
-        {
-            self.buffers[idx].write(&self.renderer_server.queue, 0, data);
-        } else {
-            self.buffers.push(
-                Buffer::new(format!("{} index_buffer", mesh_id).as_str())
-                    .new_content(data)
-                    .new_usage(wgpu::BufferUsages::INDEX)
-                    .build(&self.renderer_server.device),
-            );
+            .position(|buf| buf.get_buffer_id() == buffer_id);
+        if let Some(index) = index {
+            if self.buffers[index].get_native_buffer().size() == data.len() as u64 {
+                self.buffers[index].write(&self.renderer_server.queue, 0, data);
+                return;
+            }
+        }
+        let buffer = Buffer::new(buffer_id)
+            .new_content(data)
+            .new_usage(usage | wgpu::BufferUsages::COPY_DST)
+            .build(&self.renderer_server.device);
+        match index {
+            Some(index) => self.buffers[index] = buffer,
+            None => self.buffers.push(buffer),
         }
     }
 
@@ -111,6 +625,65 @@ impl Engine {
         }
     }
 
+    pub fn set_texture(&mut self, texture: Texture) {
+        if let Some(index) = self
+            .textures
+            .iter()
+            .position(|t| t.get_texture_id() == texture.get_texture_id())
+        {
+            self.textures[index] = texture;
+        } else {
+            self.textures.push(texture);
+        }
+    }
+
+    pub fn get_texture(&self, id: &str) -> Option<&Texture> {
+        self.textures.iter().find(|t| t.get_texture_id() == id)
+    }
+
+    // Loads a PNG or JPEG file into an sRGB texture with a full mip chain
+    pub fn load_texture<P: AsRef<Path>>(&mut self, id: &str, path: P) -> image::ImageResult<()> {
+        let image = image::open(path)?.to_rgba8();
+        let texture = Texture::new(id)
+            .new_image(&image)
+            .new_mipmaps(true)
+            .build(&self.renderer_server.device, &self.renderer_server.queue);
+        self.set_texture(texture);
+        Ok(())
+    }
+
+    // Like load_texture but keeps the values linear, for data such as normal or roughness maps
+    pub fn load_linear_texture<P: AsRef<Path>>(
+        &mut self,
+        id: &str,
+        path: P,
+    ) -> image::ImageResult<()> {
+        let image = image::open(path)?.to_rgba8();
+        let texture = Texture::new(id)
+            .new_image(&image)
+            .new_format(wgpu::TextureFormat::Rgba8Unorm)
+            .new_mipmaps(true)
+            .build(&self.renderer_server.device, &self.renderer_server.queue);
+        self.set_texture(texture);
+        Ok(())
+    }
+
+    pub fn set_sampler(&mut self, sampler: Sampler) {
+        if let Some(index) = self
+            .samplers
+            .iter()
+            .position(|s| s.get_sampler_id() == sampler.get_sampler_id())
+        {
+            self.samplers[index] = sampler;
+        } else {
+            self.samplers.push(sampler);
+        }
+    }
+
+    pub fn get_sampler(&self, id: &str) -> Option<&Sampler> {
+        self.samplers.iter().find(|s| s.get_sampler_id() == id)
+    }
+
     pub fn set_render_pipeline(&mut self, pipeline_id: &str, pipeline: RenderPipeline) {
         if let Some(i) = self
             .render_pipelines
@@ -132,27 +705,181 @@ impl Engine {
         if let Some(pipeline) = self
             .render_pipelines
             .iter_mut()
-            .find(|p| p.get_id() == pipeline_id.to_string())
+            .find(|p| p.get_id() == pipeline_id)
         {
-            let mut binding_resources: Vec<wgpu::BindingResource> = Vec::new();
-            for i in 0..resource_ids.len() {
-                if let Some(buffer) = self
-                    .buffers
-                    .iter()
-                    .find(|buf| buf.get_buffer_id() == resource_ids[i])
-                {
-                    binding_resources.push(buffer.get_native_buffer().as_entire_binding());
-                }
-            }
-            pipeline.bind_resource(&self.renderer_server.device, group, &binding_resources)
+            let binding_resources =
+                get_binding_resources(&self.buffers, &self.textures, &self.samplers, resource_ids);
+            pipeline.bind_resource(&self.renderer_server.device, group, &binding_resources);
+            pipeline.set_resource_ids(group, resource_ids);
         }
     }
 
+    // Binds the material's parameter buffer, textures and samplers to the Resource group of its pipeline,
+    // so its pipeline has to be set first
+    pub fn set_material(&mut self, mut material: Material) {
+        if let Some(pipeline) = self
+            .render_pipelines
+            .iter()
+            .find(|p| p.get_id() == material.get_pipeline_id())
+        {
+            let resource_ids: Vec<String> = material.get_resource_ids().to_vec();
+            let resource_ids: Vec<&str> = resource_ids.iter().map(|id| id.as_str()).collect();
+            let binding_resources =
+                get_binding_resources(&self.buffers, &self.textures, &self.samplers, &resource_ids);
+            material.bind_resource(&self.renderer_server.device, pipeline, &binding_resources);
+        } else {
+            println!(
+                "Couldn't find pipeline: {} for material: {}",
+                material.get_pipeline_id(),
+                material.get_id()
+            );
+        }
+        if let Some(index) = self
+            .materials
+            .iter()
+            .position(|m| m.get_id() == material.get_id())
+        {
+            self.materials[index] = material;
+        } else {
+            self.materials.push(material);
+        }
+    }
+
+    pub fn get_material(&self, id: &str) -> Option<&Material> {
+        self.materials.iter().find(|m| m.get_id() == id)
+    }
+
+    pub fn set_material_parameter(&mut self, id: &str, name: &str, value: MaterialParameter) {
+        if let Some(material) = self.materials.iter_mut().find(|m| m.get_id() == id) {
+            material.set_parameter(&self.renderer_server.queue, name, value);
+        } else {
+            println!("Couldn't find a material with id: {}", id);
+        }
+    }
+
+    pub fn get_buffer(&self, id: &str) -> Option<&Buffer> {
+        self.buffers.iter().find(|b| b.get_buffer_id() == id)
+    }
+
+    // whether any buffer, texture, sampler or material id starts with the prefix
+    pub fn is_id_prefix_used(&self, prefix: &str) -> bool {
+        self.buffers
+            .iter()
+            .any(|b| b.get_buffer_id().starts_with(prefix))
+            || self
+                .textures
+                .iter()
+                .any(|t| t.get_texture_id().starts_with(prefix))
+            || self
+                .samplers
+                .iter()
+                .any(|s| s.get_sampler_id().starts_with(prefix))
+            || self
+                .materials
+                .iter()
+                .any(|m| m.get_id().starts_with(prefix))
+    }
+
+    pub fn set_compute_pipeline(&mut self, pipeline_id: &str, pipeline: ComputePipeline) {
+        if let Some(i) = self
+            .compute_pipelines
+            .iter()
+            .position(|p| p.get_id() == pipeline_id)
+        {
+            self.compute_pipelines[i] = pipeline;
+        } else {
+            self.compute_pipelines.push(pipeline);
+        }
+    }
+
+    pub fn get_compute_pipeline(&self, pipeline_id: &str) -> Option<&ComputePipeline> {
+        self.compute_pipelines
+            .iter()
+            .find(|p| p.get_id() == pipeline_id)
+    }
+
+    pub fn bind_resources_to_compute_pipeline(
+        &mut self,
+        pipeline_id: &str,
+        group: u8,
+        resource_ids: &[&str],
+    ) {
+        if let Some(pipeline) = self
+            .compute_pipelines
+            .iter_mut()
+            .find(|p| p.get_id() == pipeline_id)
+        {
+            let binding_resources =
+                get_binding_resources(&self.buffers, &self.textures, &self.samplers, resource_ids);
+            pipeline.bind_resource(&self.renderer_server.device, group, &binding_resources);
+            pipeline.set_resource_ids(group, resource_ids);
+        } else {
+            println!("Couldn't find a compute pipeline with id: {}", pipeline_id);
+        }
+    }
+
+    // Runs a compute pipeline right away, outside of the render graph. offset_for gives the
+    // dynamic offset of every buffer the pipeline binds
+    pub fn dispatch_compute<F: Fn(&str) -> u32>(
+        &self,
+        pipeline_id: &str,
+        invocations: [u32; 3],
+        offset_for: F,
+    ) {
+        let Some(pipeline) = self.get_compute_pipeline(pipeline_id) else {
+            println!("Couldn't find a compute pipeline with id: {}", pipeline_id);
+            return;
+        };
+        let mut command_buffer =
+            CommandBuffer::new_command_buffer(&self.renderer_server.device, pipeline_id);
+        {
+            let mut compute_pass = command_buffer.begin_compute_pass(pipeline_id);
+            pipeline.dispatch(&mut compute_pass, invocations, offset_for);
+        }
+        command_buffer.finish_command_buffer(&self.renderer_server.queue);
+    }
+
     pub fn get_resolution(&self) -> (u32, u32) {
-        (
-            self.window.inner_size().width,
-            self.window.inner_size().height,
-        )
+        if let Some(window) = self.window.as_ref() {
+            (window.inner_size().width, window.inner_size().height)
+        } else {
+            self.renderer_server.get_swapchain().get_resolution()
+        }
+    }
+
+    // Resizes the window, or the offscreen targets when running headless
+    pub fn set_resolution(&mut self, resolution: (u32, u32)) {
+        if let Some(window) = self.window.as_ref() {
+            // the swapchain gets updated once the window reports the resize event
+            window.set_inner_size(PhysicalSize::new(resolution.0, resolution.1));
+        } else {
+            self.resize(resolution);
+        }
+    }
+
+    // Fifo is vsync, Mailbox and Immediate render without waiting for the display
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) {
+        self.renderer_server.set_present_mode(mode);
+    }
+
+    pub fn get_present_mode(&self) -> wgpu::PresentMode {
+        self.renderer_server.get_swapchain().get_present_mode()
+    }
+
+    // Reads back the last rendered frame, only possible when running headless since
+    // swapchain images can't be accessed after they have been presented
+    pub fn capture_frame(&self) -> Option<image::RgbaImage> {
+        self.renderer_server.read_frame()
+    }
+
+    // Saves the next rendered frame as a PNG file
+    pub fn request_screenshot<P: AsRef<Path>>(&mut self, path: P) {
+        self.pending_screenshot = Some(path.as_ref().to_path_buf());
+    }
+
+    // Key that saves a screenshot into the working directory while app_loop() is running, F12 by default
+    pub fn set_screenshot_key(&mut self, key: Option<VirtualKeyCode>) {
+        self.screenshot_key = key;
     }
 
     pub fn add_root_node<T: BaseNode + 'static>(&mut self, node: T) {
@@ -173,14 +900,567 @@ impl Engine {
         }
     }
 
-    fn draw_a_mesh() {}
+    // Advances the scene by one frame and renders it, usable without entering app_loop().
+    // Without a root node the frame is only cleared, unless the application already rendered
+    // one on its own with get_new_frame() since the last step
+    pub fn step(&mut self) {
+        self.update_scene();
+        self.render();
+    }
+
+    fn update_scene(&mut self) {
+        if let Some(root) = self.root_node.as_mut() {
+            root.update(0.0);
+        }
+    }
+
+    fn render(&mut self) {
+        let rendered_by_application = self.renderer_server.take_frame_acquired();
+        if self.root_node.is_some() || !rendered_by_application {
+            self.draw_frame();
+            // the engine's own frame doesn't count as one of the application
+            self.renderer_server.take_frame_acquired();
+        }
+    }
+
+    // Updates global transforms and groups every MeshInstance sharing a mesh and pipeline
+    // into one instanced draw, their GPUMesh slots are laid out next to each other in mesh_buffer
+    fn prepare_draw_calls(&mut self) {
+        self.draw_calls.clear();
+        self.culling_stats = CullingStats::default();
+        let aspect_ratio = self.renderer_server.get_swapchain().get_aspect_ratio();
+        let Some(root) = self.root_node.as_mut() else {
+            return;
+        };
+        let mut camera: Option<GPUCamera> = None;
+        // view projection, near and far of the camera for the shadow cascades
+        let mut camera_frustum: Option<(glam::Mat4, (f32, f32))> = None;
+        let mut lights: Vec<GPULight> = Vec::new();
+        // light index, direction, cascade count and shadow distance of shadow casting directional lights
+        let mut directional_shadows: Vec<(usize, glam::Vec3, u32, f32)> = Vec::new();
+        let mut spot_shadows: Vec<(usize, GPUShadow)> = Vec::new();
+        let mut candidates: Vec<DrawCandidate> = Vec::new();
+        let mut add_candidate =
+            |mesh: &Mesh,
+             pipeline_id: &str,
+             material_id: &str,
+             (cast_shadows, receive_shadows): (bool, bool),
+             instances: Vec<(glam::Mat4, Aabb)>| {
+                candidates.push(DrawCandidate {
+                    mesh_id: mesh.get_mesh_id().to_string(),
+                    pipeline_id: pipeline_id.to_string(),
+                    material_id: material_id.to_string(),
+                    cast_shadows,
+                    receive_shadows,
+                    vertex_layout: mesh.get_vertex_layout().clone(),
+                    index_count: mesh.get_index_count(),
+                    instances,
+                });
+            };
+        let mut stack: Vec<(&mut Box<dyn BaseNode>, glam::Mat4)> =
+            vec![(root, glam::Mat4::IDENTITY)];
+        while let Some((node, parent_mx)) = stack.pop() {
+            let global_mx = parent_mx * *node.get_transformation_matrix();
+            node.set_global_transformation_matrix(global_mx);
+            if let Some(camera_3d) = node.as_any().downcast_ref::<Camera3D>() {
+                if camera.is_none() && camera_3d.current {
+                    camera = Some(GPUCamera::from_matrices(
+                        &camera_3d.get_view_matrix(),
+                        &camera_3d.get_projection_matrix(aspect_ratio),
+                    ));
+                    camera_frustum = Some((
+                        camera_3d.get_view_projection_matrix(aspect_ratio),
+                        (camera_3d.near, camera_3d.far),
+                    ));
+                }
+            } else if let Some(light) = node.as_any().downcast_ref::<DirectionalLight>() {
+                if light.cast_shadows {
+                    directional_shadows.push((
+                        lights.len(),
+                        light.get_direction(),
+                        light.shadow_cascade_count.clamp(1, MAX_SHADOW_CASCADES),
+                        light.shadow_distance,
+                    ));
+                }
+                lights.push(light.get_gpu_light());
+            } else if let Some(light) = node.as_any().downcast_ref::<PointLight>() {
+                lights.push(light.get_gpu_light());
+            } else if let Some(light) = node.as_any().downcast_ref::<SpotLight>() {
+                if light.cast_shadows {
+                    spot_shadows.push((lights.len(), light.get_shadow()));
+                }
+                lights.push(light.get_gpu_light());
+            } else if let Some(mesh_instance) = node.as_any().downcast_ref::<MeshInstance>() {
+                if let Some(mesh) = mesh_instance.mesh.as_ref() {
+                    let bounds = mesh_instance
+                        .get_world_aabb()
+                        .copied()
+                        .unwrap_or_else(|| mesh.get_aabb().transform(&global_mx));
+                    add_candidate(
+                        mesh,
+                        &mesh_instance.pipeline_id,
+                        &mesh_instance.material_id,
+                        (mesh_instance.cast_shadows, mesh_instance.receive_shadows),
+                        vec![(global_mx, bounds)],
+                    );
+                }
+            } else if let Some(multi_mesh_instance) =
+                node.as_any().downcast_ref::<MultiMeshInstance>()
+            {
+                if let Some(mesh) = multi_mesh_instance.mesh.as_ref() {
+                    add_candidate(
+                        mesh,
+                        &multi_mesh_instance.pipeline_id,
+                        &multi_mesh_instance.material_id,
+                        (
+                            multi_mesh_instance.cast_shadows,
+                            multi_mesh_instance.receive_shadows,
+                        ),
+                        multi_mesh_instance
+                            .get_instance_matrices()
+                            .iter()
+                            .map(|instance_mx| {
+                                let world_mx = global_mx * *instance_mx;
+                                (world_mx, mesh.get_aabb().transform(&world_mx))
+                            })
+                            .collect(),
+                    );
+                }
+            }
+            // pushed in reverse so siblings are popped in tree order
+            for child in node.get_children_mut().iter_mut().rev() {
+                stack.push((child, global_mx));
+            }
+        }
+
+        if let Some(camera) = camera {
+            self.write_to_buffer("camera_buffer", 0, bytemuck::bytes_of(&camera));
+        }
+
+        let frustum = self
+            .culling_view_projection
+            .or(camera_frustum.map(|(view_proj_mx, _)| view_proj_mx))
+            .filter(|_| self.frustum_culling)
+            .map(|view_proj_mx| Frustum::from_view_projection(&view_proj_mx));
+        let mut culling_stats = CullingStats::default();
+        let mut batches: Vec<DrawBatch> = Vec::new();
+        let mut batch_indices: HashMap<(String, String, String, bool, bool, bool), usize> =
+            HashMap::new();
+        for candidate in candidates {
+            // instances with a material are drawn with the material's pipeline
+            let pipeline_id = self
+                .materials
+                .iter()
+                .find(|m| !candidate.material_id.is_empty() && m.get_id() == candidate.material_id)
+                .map(|m| m.get_pipeline_id())
+                .unwrap_or(&candidate.pipeline_id)
+                .to_string();
+            let (visible, culled): (Vec<_>, Vec<_>) =
+                candidate.instances.iter().partition(|(_, bounds)| {
+                    frustum.is_none_or(|frustum| frustum.intersects_aabb(bounds))
+                });
+            culling_stats.total_instances += candidate.instances.len() as u32;
+            culling_stats.visible_instances += visible.len() as u32;
+            culling_stats.culled_instances += culled.len() as u32;
+            // instances outside the view can still cast shadows into it
+            let shadow_only = if candidate.cast_shadows {
+                culled
+            } else {
+                Vec::new()
+            };
+            culling_stats.shadow_only_instances += shadow_only.len() as u32;
+            for (is_visible, instances) in [(true, visible), (false, shadow_only)] {
+                if instances.is_empty() {
+                    continue;
+                }
+                let key = (
+                    candidate.mesh_id.clone(),
+                    pipeline_id.clone(),
+                    candidate.material_id.clone(),
+                    candidate.cast_shadows,
+                    candidate.receive_shadows,
+                    is_visible,
+                );
+                let index = *batch_indices.entry(key).or_insert_with(|| {
+                    batches.push(DrawBatch {
+                        mesh_id: candidate.mesh_id.clone(),
+                        pipeline_id: pipeline_id.clone(),
+                        material_id: candidate.material_id.clone(),
+                        cast_shadows: candidate.cast_shadows,
+                        receive_shadows: candidate.receive_shadows,
+                        visible: is_visible,
+                        vertex_layout: candidate.vertex_layout.clone(),
+                        index_count: candidate.index_count,
+                        transforms: Vec::new(),
+                    });
+                    batches.len() - 1
+                });
+                batches[index]
+                    .transforms
+                    .extend(instances.iter().map(|(transform, _)| *transform));
+            }
+        }
+        // every shadow casting light gets consecutive shadow map layers, directional lights first
+        let mut shadows: Vec<GPUShadow> = Vec::new();
+        if let Some((camera_view_proj_mx, near_far)) = camera_frustum {
+            for (light_index, direction, cascade_count, shadow_distance) in directional_shadows {
+                if shadows.len() as u64 + cascade_count as u64 > MAX_SHADOW_MAPS {
+                    println!("shadow_maps are full, skipping shadows of a directional light");
+                    continue;
+                }
+                lights[light_index].set_shadow_layers(shadows.len() as u32, cascade_count);
+                shadows.extend(get_shadow_cascades(
+                    direction,
+                    cascade_count,
+                    shadow_distance,
+                    &camera_view_proj_mx,
+                    near_far,
+                ));
+            }
+        }
+        for (light_index, shadow) in spot_shadows {
+            if shadows.len() as u64 >= MAX_SHADOW_MAPS {
+                println!("shadow_maps are full, skipping shadows of a spot light");
+                continue;
+            }
+            lights[light_index].set_shadow_layers(shadows.len() as u32, 1);
+            shadows.push(shadow);
+        }
+        self.shadow_layer_count = shadows.len() as u32;
+        if !shadows.is_empty() {
+            self.write_to_buffer("shadow_buffer", 0, bytemuck::cast_slice(&shadows));
+            let alignment = self.renderer_server.get_uniform_aligned_buffer_size(64);
+            for (layer, shadow) in shadows.iter().enumerate() {
+                self.write_to_buffer(
+                    "shadow_view_buffer",
+                    layer as u64 * alignment,
+                    bytemuck::cast_slice(&shadow.view_proj_mx),
+                );
+            }
+        }
+
+        if lights.len() as u64 > MAX_LIGHTS {
+            println!(
+                "light_buffer is full, skipping {} lights",
+                lights.len() as u64 - MAX_LIGHTS
+            );
+            lights.truncate(MAX_LIGHTS as usize);
+        }
+        let light_count: [u32; 4] = [lights.len() as u32, 0, 0, 0];
+        self.write_to_buffer("light_buffer", 0, bytemuck::cast_slice(&light_count));
+        if !lights.is_empty() {
+            self.write_to_buffer("light_buffer", 16, bytemuck::cast_slice(&lights));
+        }
+
+        let alignment = self
+            .renderer_server
+            .get_storage_aligned_buffer_size(GPUMesh::get_size());
+        let mut meshes: Vec<GPUMesh> = Vec::new();
+        'batches: for batch in batches {
+            for transforms in batch.transforms.chunks(MAX_INSTANCES_PER_DRAW as usize) {
+                // dynamic offsets have to be aligned, so each draw starts on an aligned slot
+                let mesh_offset =
+                    wgpu::util::align_to(meshes.len() as u64 * GPUMesh::get_size(), alignment);
+                let slot = mesh_offset / GPUMesh::get_size();
+                if slot + transforms.len() as u64 > MAX_MESH_INSTANCES {
+                    println!(
+                        "mesh_buffer is full, skipping instances of mesh: {}",
+                        batch.mesh_id
+                    );
+                    break 'batches;
+                }
+                meshes.resize(slot as usize, GPUMesh::new());
+                meshes.extend(transforms.iter().map(|transform| {
+                    let mut mesh = GPUMesh::from_matrix(transform);
+                    mesh.set_receive_shadows(batch.receive_shadows);
+                    mesh
+                }));
+                self.draw_calls.push(DrawCall {
+                    mesh_id: batch.mesh_id.clone(),
+                    pipeline_id: batch.pipeline_id.clone(),
+                    material_id: batch.material_id.clone(),
+                    cast_shadows: batch.cast_shadows,
+                    visible: batch.visible,
+                    vertex_layout: batch.vertex_layout.clone(),
+                    index_count: batch.index_count,
+                    mesh_offset: mesh_offset as u32,
+                    instance_count: transforms.len() as u32,
+                });
+            }
+        }
+        if !meshes.is_empty() {
+            self.write_to_buffer("mesh_buffer", 0, bytemuck::cast_slice(&meshes));
+        }
+        // shadow casters with a vertex stride no shadow pipeline has been built for yet
+        let mut new_strides: Vec<u64> = Vec::new();
+        for draw_call in self.draw_calls.iter().filter(|d| d.cast_shadows) {
+            match draw_call.vertex_layout.get_position_stride() {
+                Some(stride) => {
+                    let pipeline_id = get_shadow_pipeline_id(stride);
+                    if !new_strides.contains(&stride)
+                        && !self
+                            .render_pipelines
+                            .iter()
+                            .any(|p| p.get_id() == pipeline_id)
+                    {
+                        new_strides.push(stride);
+                    }
+                }
+                None => {
+                    if self.shadowless_meshes.insert(draw_call.mesh_id.clone()) {
+                        println!(
+                            "mesh: {} has no positions in its vertex layout and can't cast shadows",
+                            draw_call.mesh_id
+                        );
+                    }
+                }
+            }
+        }
+        for stride in new_strides {
+            self.create_shadow_pipeline(stride);
+        }
+        culling_stats.draw_calls = self.draw_calls.iter().filter(|d| d.visible).count() as u32;
+        self.culling_stats = culling_stats;
+    }
+
+    fn draw_scene<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        for draw_call in self.draw_calls.iter().filter(|d| d.visible) {
+            let Some(pipeline) = self
+                .render_pipelines
+                .iter()
+                .find(|p| p.get_id() == draw_call.pipeline_id)
+            else {
+                continue;
+            };
+            let vertex_buffers: Option<Vec<&Buffer>> = (0..draw_call
+                .vertex_layout
+                .get_stream_count())
+                .map(|stream| self.get_buffer(&get_vertex_buffer_name(&draw_call.mesh_id, stream)))
+                .collect();
+            let Some(vertex_buffers) = vertex_buffers else {
+                continue;
+            };
+            let Some(index_buffer) = self.buffers.iter().find(|buff| {
+                buff.get_buffer_id() == format!("{} index_buffer", draw_call.mesh_id).as_str()
+            }) else {
+                continue;
+            };
+            let material_bind_group = self
+                .materials
+                .iter()
+                .find(|m| !draw_call.material_id.is_empty() && m.get_id() == draw_call.material_id)
+                .and_then(|m| m.get_bind_group());
+            pass.set_pipeline(pipeline.get_native_pipeline());
+            for group in 0..pipeline.get_bind_group_count() {
+                let bind_group =
+                    if group == BindingGroupType::Resource as u8 && material_bind_group.is_some() {
+                        material_bind_group
+                    } else {
+                        pipeline.get_bind_group(group)
+                    };
+                let Some(bind_group) = bind_group else {
+                    continue;
+                };
+                let offsets = pipeline.get_dynamic_offsets(group, |id| {
+                    if id == "mesh_buffer" {
+                        draw_call.mesh_offset
+                    } else {
+                        0
+                    }
+                });
+                pass.set_bind_group(group as u32, bind_group, &offsets);
+            }
+            for (slot, vertex_buffer) in vertex_buffers.iter().enumerate() {
+                pass.set_vertex_buffer(slot as u32, vertex_buffer.get_native_buffer().slice(..));
+            }
+            pass.set_index_buffer(
+                index_buffer.get_native_buffer().slice(..),
+                wgpu::IndexFormat::Uint32,
+            );
+            pass.draw_indexed(0..draw_call.index_count, 0, 0..draw_call.instance_count);
+        }
+    }
 
-    pub fn app_loop(
-        mut self,
-        mut update: Box<dyn FnMut(&mut Self)>,
-        mut resize: Box<dyn FnMut(&mut Self, (u32, u32))>,
+    // draws every shadow caster into the shadow map layers used this frame
+    fn record_shadow_pass(&self, command_buffer: &mut CommandBuffer) {
+        let alignment = self.renderer_server.get_uniform_aligned_buffer_size(64);
+        for layer in 0..self.shadow_layer_count {
+            let mut shadow_pass = RenderPassBuilder::new("shadow_pass")
+                .depth_stencil_attachment(&self.shadow_layer_views[layer as usize])
+                .depth_ops(1.0)
+                .build(command_buffer);
+            for draw_call in self.draw_calls.iter().filter(|d| d.cast_shadows) {
+                let Some(stride) = draw_call.vertex_layout.get_position_stride() else {
+                    continue;
+                };
+                let pipeline_id = get_shadow_pipeline_id(stride);
+                let Some(pipeline) = self
+                    .render_pipelines
+                    .iter()
+                    .find(|p| p.get_id() == pipeline_id)
+                else {
+                    continue;
+                };
+                let Some(bind_group) = pipeline.get_bind_group(BindingGroupType::Global as u8)
+                else {
+                    continue;
+                };
+                // the positions are always in the first stream
+                let Some(vertex_buffer) =
+                    self.get_buffer(&get_vertex_buffer_name(&draw_call.mesh_id, 0))
+                else {
+                    continue;
+                };
+                let Some(index_buffer) =
+                    self.get_buffer(&format!("{} index_buffer", draw_call.mesh_id))
+                else {
+                    continue;
+                };
+                let offsets =
+                    pipeline.get_dynamic_offsets(BindingGroupType::Global as u8, |id| match id {
+                        "shadow_view_buffer" => (layer as u64 * alignment) as u32,
+                        "mesh_buffer" => draw_call.mesh_offset,
+                        _ => 0,
+                    });
+                shadow_pass.set_pipeline(pipeline.get_native_pipeline());
+                shadow_pass.set_bind_group(0, bind_group, &offsets);
+                shadow_pass.set_vertex_buffer(0, vertex_buffer.get_native_buffer().slice(..));
+                shadow_pass.set_index_buffer(
+                    index_buffer.get_native_buffer().slice(..),
+                    wgpu::IndexFormat::Uint32,
+                );
+                shadow_pass.draw_indexed(0..draw_call.index_count, 0, 0..draw_call.instance_count);
+            }
+        }
+    }
+
+    fn record_main_pass(
+        &self,
+        command_buffer: &mut CommandBuffer,
+        resources: &RenderGraphResources,
     ) {
-        let event_loop = self.event_loop.take().unwrap();
+        let (Some(color_view), Some(depth_view)) = (
+            resources.get_texture_view(SCENE_COLOR),
+            resources.get_texture_view(SWAPCHAIN_DEPTH),
+        ) else {
+            return;
+        };
+        let mut main_pass = RenderPassBuilder::new("main_pass")
+            .color_attachment(color_view, [0.1, 0.5, 0.3, 1.0])
+            .depth_stencil_attachment(depth_view)
+            .depth_ops(1.0)
+            .build(command_buffer);
+        self.draw_scene(&mut main_pass);
+        if self.skybox_enabled && self.environment.enabled != 0 {
+            self.draw_skybox(&mut main_pass);
+        }
+    }
+
+    fn draw_skybox<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        let Some(pipeline) = self
+            .render_pipelines
+            .iter()
+            .find(|p| p.get_id() == SKYBOX_PIPELINE)
+        else {
+            return;
+        };
+        let group = BindingGroupType::Global as u8;
+        if let Some(bind_group) = pipeline.get_bind_group(group) {
+            pass.set_pipeline(pipeline.get_native_pipeline());
+            pass.set_bind_group(
+                group as u32,
+                bind_group,
+                &pipeline.get_dynamic_offsets(group, |_| 0),
+            );
+            pass.draw(0..3, 0..1);
+        }
+    }
+
+    // Passes are ordered by the textures and buffers they read and write, the built-in
+    // "main_pass" draws the scene into SCENE_COLOR and SWAPCHAIN_DEPTH, then "post_process_pass"
+    // applies the post effects and writes SWAPCHAIN_COLOR
+    pub fn add_render_pass(&mut self, pass: RenderGraphPass) {
+        self.get_render_graph_mut().add_pass(pass);
+    }
+
+    pub fn remove_render_pass(&mut self, id: &str) {
+        self.get_render_graph_mut().remove_pass(id);
+    }
+
+    // declares a transient texture the graph allocates and resizes with the swapchain
+    pub fn add_render_texture(&mut self, name: &str, description: RenderGraphTexture) {
+        self.get_render_graph_mut().add_texture(name, description);
+    }
+
+    pub fn get_render_graph_mut(&mut self) -> &mut RenderGraph {
+        self.render_graph
+            .as_mut()
+            .expect("render graph can't be accessed while it is executed")
+    }
+
+    fn draw_frame(&mut self) {
+        self.prepare_draw_calls();
+        let Some((frame, frame_view, depth_view)) = self.renderer_server.get_new_frame() else {
+            return;
+        };
+        let mut render_graph = self
+            .render_graph
+            .take()
+            .expect("render graph is already being executed");
+        if let Err(error) = render_graph.compile(
+            &self.renderer_server.device,
+            self.renderer_server.get_swapchain().get_resolution(),
+        ) {
+            println!("Couldn't compile the render graph: {}", error);
+            self.render_graph = Some(render_graph);
+            return;
+        }
+        let textures = &self.textures;
+        self.post_process.prepare(
+            &self.renderer_server.queue,
+            self.renderer_server.get_swapchain().get_resolution(),
+            |id| textures.iter().any(|t| t.get_texture_id() == id),
+        );
+        let mut main_buffer =
+            CommandBuffer::new_command_buffer(&self.renderer_server.device, "main_buffer");
+        render_graph.execute(
+            self,
+            &mut main_buffer,
+            frame.get_texture(),
+            &frame_view,
+            depth_view,
+        );
+        self.render_graph = Some(render_graph);
+        main_buffer.finish_command_buffer(&self.renderer_server.queue);
+        if let Some(path) = self.pending_screenshot.take() {
+            if frame
+                .get_texture()
+                .usage()
+                .contains(wgpu::TextureUsages::COPY_SRC)
+            {
+                if let Some(screenshot) = self.renderer_server.read_texture(frame.get_texture()) {
+                    if let Err(error) = screenshot.save(&path) {
+                        println!("Couldn't save screenshot {}: {}", path.display(), error);
+                    }
+                }
+            } else {
+                println!("Current surface doesn't support reading back frames for screenshots");
+            }
+        }
+        frame.present();
+    }
+
+    fn request_redraw(&self) {
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    pub fn app_loop(mut self, mut update: UpdateCallback, mut resize: ResizeCallback) {
+        let event_loop = self
+            .event_loop
+            .take()
+            .expect("app_loop() requires a windowed engine, use step() when running headless");
         event_loop.run(move |event, _, control_flow| {
             *control_flow = ControlFlow::Wait;
             match event {
@@ -192,105 +1472,33 @@ impl Engine {
                     // On macos the window needs to be redrawn manually after resizing
                     self.resize((size.width, size.height));
                     resize(&mut self, (size.width, size.height));
-                    self.window.request_redraw();
+                    self.request_redraw();
+                }
+                Event::WindowEvent {
+                    event:
+                        WindowEvent::KeyboardInput {
+                            input:
+                                KeyboardInput {
+                                    state: ElementState::Pressed,
+                                    virtual_keycode: Some(key),
+                                    ..
+                                },
+                            ..
+                        },
+                    ..
+                } if self.screenshot_key == Some(key) => {
+                    let timestamp = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    self.request_screenshot(format!("screenshot_{}.png", timestamp));
                 }
                 Event::RedrawRequested(_) => {
-                    if let Some(root) = self.root_node.as_mut() {
-                        root.update(0.0);
-                    }
+                    // the scene is updated before the callback and drawn after it
+                    self.update_scene();
                     update(&mut self);
-                    let (frame, frame_view, _depth_view) = self.renderer_server.get_new_frame();
-                    let mut main_buffer = CommandBuffer::new_command_buffer(
-                        &self.renderer_server.device,
-                        "main_buffer",
-                    );
-                    if let Some(root) = self.root_node.as_mut() {
-                        if let Some(mesh_buffer) = self
-                            .buffers
-                            .iter_mut()
-                            .find(|buf| buf.get_buffer_id() == "mesh_buffer")
-                        {
-                            let trans_mx = root.get_transformation_matrix().as_ref();
-                            mesh_buffer.write(
-                                &self.renderer_server.queue,
-                                0,
-                                bytemuck::cast_slice(trans_mx),
-                            );
-                        }
-                        let mut main_pass = RenderPassBuilder::new("main_pass")
-                            .color_attachment(&frame_view, [0.1, 0.5, 0.3, 1.0])
-                            .build(&mut main_buffer);
-                        let mut stack = VecDeque::new();
-                        stack.push_back(root);
-
-                        while let Some(node) = stack.pop_back() {
-                            if let Some(mesh_instance) =
-                                node.as_any().downcast_ref::<MeshInstance>()
-                            {
-                                if let Some(mesh) = mesh_instance.mesh.as_ref() {
-                                    {
-                                        if let Some(pipeline) = self
-                                            .render_pipelines
-                                            .iter()
-                                            .find(|p| p.get_id() == &mesh_instance.pipeline_id)
-                                        {
-                                            main_pass.set_pipeline(pipeline.get_native_pipeline());
-                                            for i in 0..pipeline.get_bind_groups().len() {
-                                                main_pass.set_bind_group(
-                                                    i as u32,
-                                                    pipeline.get_bind_groups()[i],
-                                                    &[0, 0],
-                                                )
-                                            }
-                                        } else {
-                                            continue;
-                                        }
-                                        if let Some(vertex_buffer) =
-                                            self.buffers.iter().find(|buff| {
-                                                buff.get_buffer_id()
-                                                    == format!(
-                                                        "{} vertex_buffer",
-                                                        mesh.get_mesh_id()
-                                                    )
-                                                    .as_str()
-                                            })
-                                        {
-                                            main_pass.set_vertex_buffer(
-                                                0,
-                                                vertex_buffer.get_native_buffer().slice(..),
-                                            );
-                                        } else {
-                                            continue;
-                                        }
-                                        if let Some(index_buffer) =
-                                            self.buffers.iter().find(|buff| {
-                                                buff.get_buffer_id()
-                                                    == format!(
-                                                        "{} index_buffer",
-                                                        mesh.get_mesh_id()
-                                                    )
-                                                    .as_str()
-                                            })
-                                        {
-                                            main_pass.set_index_buffer(
-                                                index_buffer.get_native_buffer().slice(..),
-                                                wgpu::IndexFormat::Uint32,
-                                            );
-                                        } else {
-                                            continue;
-                                        }
-                                        main_pass.draw_indexed(0..mesh.get_index_count(), 0, 0..1);
-                                    }
-                                }
-                            }
-                            for child in node.get_children_mut() {
-                                stack.push_back(child);
-                            }
-                        }
-                    }
-                    main_buffer.finish_command_buffer(&self.renderer_server.queue);
-                    frame.present();
-                    self.window.request_redraw(); // with this call inside RedrawRequested event, we can tell the window to basically redraw every frame
+                    self.render();
+                    self.request_redraw(); // with this call inside RedrawRequested event, we can tell the window to basically redraw every frame
                 }
                 Event::WindowEvent {
                     event: WindowEvent::CloseRequested,
@@ -305,3 +1513,149 @@ impl Engine {
         self.renderer_server.update_swapchain(new_size);
     }
 }
+
+// the shadow pipeline of the default Vertex layout keeps the plain id
+fn get_shadow_pipeline_id(vertex_stride: u64) -> String {
+    if vertex_stride == std::mem::size_of::<Vertex>() as u64 {
+        SHADOW_PIPELINE.to_string()
+    } else {
+        format!("{} {}", SHADOW_PIPELINE, vertex_stride)
+    }
+}
+
+// looks up the buffers, textures and samplers that are bound to a pipeline group, in binding order
+fn get_binding_resources<'a>(
+    buffers: &'a [Buffer],
+    textures: &'a [Texture],
+    samplers: &'a [Sampler],
+    resource_ids: &[&str],
+) -> Vec<wgpu::BindingResource<'a>> {
+    let mut binding_resources: Vec<wgpu::BindingResource> = Vec::new();
+    for resource_id in resource_ids {
+        if let Some(buffer) = buffers
+            .iter()
+            .find(|buf| buf.get_buffer_id() == *resource_id)
+        {
+            binding_resources.push(buffer.get_binding());
+        } else if let Some(texture) = textures
+            .iter()
+            .find(|tex| tex.get_texture_id() == *resource_id)
+        {
+            binding_resources.push(texture.get_binding());
+        } else if let Some(sampler) = samplers
+            .iter()
+            .find(|sam| sam.get_sampler_id() == *resource_id)
+        {
+            binding_resources.push(sampler.get_binding());
+        } else {
+            println!("Couldn't find a resource with id: {}", resource_id);
+        }
+    }
+    binding_resources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scene::nodes::camera_3d::Camera3D;
+    use scene::nodes::mesh_instance::mesh::primitives::create_cube;
+    use scene::nodes::node::Node;
+
+    fn get_camera(name: &str, translation: glam::Vec3, target: glam::Vec3) -> Camera3D {
+        let mut camera = Camera3D::new(name);
+        camera.transform.set_translation(translation);
+        camera.transform.look_at(target, glam::Vec3::Z);
+        camera
+    }
+
+    // a cube at the origin and two current cameras, only the one named "front" sees the cube
+    fn get_visible_instances(camera_names: [&str; 2]) -> u32 {
+        let mut engine = pollster::block_on(Engine::new_headless((64, 64), true));
+        let mut cube = MeshInstance::new("cube");
+        cube.mesh = Some(create_cube(glam::Vec3::ONE).build("cube", &mut engine));
+        let mut root = Node::new("root");
+        root.add_node(Box::new(cube));
+        for name in camera_names {
+            let camera = if name == "front" {
+                get_camera(name, glam::Vec3::new(0.0, -5.0, 0.0), glam::Vec3::ZERO)
+            } else {
+                get_camera(
+                    name,
+                    glam::Vec3::new(0.0, 5.0, 0.0),
+                    glam::Vec3::new(0.0, 10.0, 0.0),
+                )
+            };
+            root.add_node(Box::new(camera));
+        }
+        engine.add_root_node(root);
+        engine.step();
+        engine.get_culling_stats().visible_instances
+    }
+
+    // group 0 is skipped by the shader, the multiplier sits at a dynamic offset of params
+    const OFFSET_COMPUTE_SHADER: &str = "
+struct Params {
+    multiplier: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> params: Params;
+@group(2) @binding(0)
+var<storage, read_write> values: array<f32>;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= arrayLength(&values) {
+        return;
+    }
+    values[id.x] = values[id.x] * params.multiplier;
+}
+";
+
+    #[test]
+    fn compute_dispatch_binds_groups_at_their_index_with_dynamic_offsets() {
+        let mut engine = pollster::block_on(Engine::new_headless((1, 1), true));
+        let values: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        let mut params = [0.0f32; 128];
+        params[0] = 2.0;
+        params[64] = 3.0;
+        let renderer_server = engine.get_renderer_server();
+        let params_buffer = Buffer::new("params")
+            .new_content(bytemuck::cast_slice(&params))
+            .new_usage(wgpu::BufferUsages::UNIFORM)
+            .new_binding_size(16)
+            .build(&renderer_server.device);
+        let values_buffer = Buffer::new("values")
+            .new_content(bytemuck::cast_slice(&values))
+            .new_usage(wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC)
+            .build(&renderer_server.device);
+        let pipeline = ComputePipeline::new()
+            .new_shader(OFFSET_COMPUTE_SHADER)
+            .build("offset pipeline", &renderer_server.device);
+        engine.set_buffer(params_buffer);
+        engine.set_buffer(values_buffer);
+        engine.set_compute_pipeline("offset pipeline", pipeline);
+        engine.bind_resources_to_compute_pipeline("offset pipeline", 1, &["params"]);
+        engine.bind_resources_to_compute_pipeline("offset pipeline", 2, &["values"]);
+        engine.dispatch_compute("offset pipeline", [values.len() as u32, 1, 1], |id| {
+            if id == "params" {
+                256
+            } else {
+                0
+            }
+        });
+
+        let data = engine
+            .get_renderer_server()
+            .read_buffer(engine.get_buffer("values").unwrap().get_native_buffer());
+        let result: &[f32] = bytemuck::cast_slice(&data);
+        let expected: Vec<f32> = values.iter().map(|value| value * 3.0).collect();
+        assert_eq!(result, expected.as_slice());
+    }
+
+    #[test]
+    fn first_current_camera_is_used() {
+        assert_eq!(get_visible_instances(["front", "back"]), 1);
+        assert_eq!(get_visible_instances(["back", "front"]), 0);
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::engine::Engine;
+
+pub mod gltf;
+pub mod obj;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImportSettings {
+    // OBJ and glTF files are Y up while the engine is Z up. When set the returned root node is
+    // rotated 90 degrees around X and everything below it keeps the transforms of the file,
+    // otherwise the root is left at identity
+    pub y_up_to_z_up: bool,
+}
+
+impl Default for ImportSettings {
+    fn default() -> Self {
+        Self { y_up_to_z_up: true }
+    }
+}
+
+// Start of the ids of everything an import registers. The path keeps files with the same name
+// in different directories apart, and importing a file again numbers the prefix instead of
+// replacing the resources the nodes of the earlier import use
+fn get_id_prefix(engine: &Engine, path: &Path) -> String {
+    let path = path.to_string_lossy().to_string();
+    let mut prefix = path.clone();
+    let mut count = 1;
+    while engine.is_id_prefix_used(&format!("{} ", prefix)) {
+        count += 1;
+        prefix = format!("{} #{}", path, count);
+    }
+    prefix
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(PathBuf, std::io::Error),
+    // line numbers start at 1
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    Gltf(PathBuf, ::gltf::Error),
+    // the file can't be displayed correctly without an extension the importer doesn't support
+    UnsupportedExtension(PathBuf, String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            ImportError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            ImportError::Gltf(path, error) => write!(f, "{}: {}", path.display(), error),
+            ImportError::UnsupportedExtension(path, extension) => write!(
+                f,
+                "{}: required extension {} isn't supported",
+                path.display(),
+                extension
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
@@ -0,0 +1,664 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use glam::{Vec3, Vec4};
+
+use super::{get_id_prefix, ImportError, ImportSettings};
+use crate::engine::scene::nodes::camera_3d::{Camera3D, Projection};
+use crate::engine::scene::nodes::mesh_instance::mesh::generation::NormalMode;
+use crate::engine::scene::nodes::mesh_instance::mesh::{Mesh, VertexDataBuilder};
+use crate::engine::scene::nodes::mesh_instance::MeshInstance;
+use crate::engine::scene::nodes::node::Node;
+use crate::engine::scene::nodes::BaseNode;
+use crate::engine::scene::utils::Transform;
+use crate::engine::servers::renderer::material::Material;
+use crate::engine::servers::renderer::resources::{Sampler, Texture};
+use crate::engine::servers::renderer::PBR_PIPELINE;
+use crate::engine::Engine;
+
+// extensions the importer reads, the others are reported and ignored
+pub const SUPPORTED_GLTF_EXTENSIONS: &[&str] = &["KHR_materials_emissive_strength"];
+
+struct GltfImporter<'a> {
+    engine: &'a mut Engine,
+    path: &'a Path,
+    // start of every registered id, see get_id_prefix
+    id_prefix: String,
+    buffers: Vec<gltf::buffer::Data>,
+    images: Vec<gltf::image::Data>,
+    // meshes are shared by every node that references them, keyed by mesh and primitive index
+    meshes: HashMap<(usize, usize), Mesh>,
+    // material id and whether it has a normal map, keyed by material index
+    materials: HashMap<Option<usize>, (String, bool)>,
+}
+
+// glTF rotations are quaternions, the engine stores XYZ euler angles in degrees
+fn set_transform(transform: &mut Transform, node: &gltf::Node) {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    let (x, y, z) = glam::Quat::from_array(rotation).to_euler(glam::EulerRot::XYZ);
+    transform.set_translation(Vec3::from(translation));
+    transform.set_rotation(Vec3::new(x.to_degrees(), y.to_degrees(), z.to_degrees()));
+    transform.set_scale(Vec3::from(scale));
+}
+
+// decoded glTF images come in the format of their file, the engine only takes 8 bit rgba
+fn get_rgba_image(data: &gltf::image::Data) -> Option<image::RgbaImage> {
+    use gltf::image::Format;
+    let (width, height) = (data.width, data.height);
+    let pixels = data.pixels.clone();
+    let to_u16 = |pixels: Vec<u8>| -> Vec<u16> {
+        pixels
+            .chunks_exact(2)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+            .collect()
+    };
+    let to_f32 = |pixels: Vec<u8>| -> Vec<f32> {
+        pixels
+            .chunks_exact(4)
+            .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect()
+    };
+    let image = match data.format {
+        Format::R8 => image::GrayImage::from_raw(width, height, pixels).map(Into::into),
+        Format::R8G8 => image::GrayAlphaImage::from_raw(width, height, pixels).map(Into::into),
+        Format::R8G8B8 => image::RgbImage::from_raw(width, height, pixels).map(Into::into),
+        Format::R8G8B8A8 => image::RgbaImage::from_raw(width, height, pixels).map(Into::into),
+        Format::R16 => {
+            image::ImageBuffer::<image::Luma<u16>, _>::from_raw(width, height, to_u16(pixels))
+                .map(Into::into)
+        }
+        Format::R16G16 => {
+            image::ImageBuffer::<image::LumaA<u16>, _>::from_raw(width, height, to_u16(pixels))
+                .map(Into::into)
+        }
+        Format::R16G16B16 => {
+            image::ImageBuffer::<image::Rgb<u16>, _>::from_raw(width, height, to_u16(pixels))
+                .map(Into::into)
+        }
+        Format::R16G16B16A16 => {
+            image::ImageBuffer::<image::Rgba<u16>, _>::from_raw(width, height, to_u16(pixels))
+                .map(Into::into)
+        }
+        Format::R32G32B32FLOAT => {
+            image::Rgb32FImage::from_raw(width, height, to_f32(pixels)).map(Into::into)
+        }
+        Format::R32G32B32A32FLOAT => {
+            image::Rgba32FImage::from_raw(width, height, to_f32(pixels)).map(Into::into)
+        }
+    };
+    image.map(|image: image::DynamicImage| image.to_rgba8())
+}
+
+// glTF lists triangle strips and fans as their own modes, everything else is drawn as a list
+fn get_triangle_list(mode: gltf::mesh::Mode, indices: Vec<u32>) -> Option<Vec<u32>> {
+    let count = indices.len().saturating_sub(2);
+    match mode {
+        gltf::mesh::Mode::Triangles => Some(indices),
+        gltf::mesh::Mode::TriangleStrip => Some(
+            (0..count)
+                .flat_map(|i| {
+                    // every other triangle is flipped to keep the winding
+                    if i % 2 == 0 {
+                        [indices[i], indices[i + 1], indices[i + 2]]
+                    } else {
+                        [indices[i + 1], indices[i], indices[i + 2]]
+                    }
+                })
+                .collect(),
+        ),
+        gltf::mesh::Mode::TriangleFan => Some(
+            (0..count)
+                .flat_map(|i| [indices[i + 1], indices[i + 2], indices[0]])
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+impl GltfImporter<'_> {
+    fn get_sampler(&mut self, sampler: gltf::texture::Sampler) -> Option<String> {
+        use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+        // the default sampler of a glTF file matches the one pbr materials use
+        let id = format!("{} sampler {}", self.id_prefix, sampler.index()?);
+        if self.engine.get_sampler(&id).is_some() {
+            return Some(id);
+        }
+        // the engine uses one address mode for both directions
+        let address_mode = match sampler.wrap_s() {
+            WrappingMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+            WrappingMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+            WrappingMode::Repeat => wgpu::AddressMode::Repeat,
+        };
+        let filter = match sampler.mag_filter() {
+            Some(MagFilter::Nearest) => wgpu::FilterMode::Nearest,
+            _ => wgpu::FilterMode::Linear,
+        };
+        let mipmap_filter = match sampler.min_filter() {
+            Some(MinFilter::NearestMipmapNearest) | Some(MinFilter::LinearMipmapNearest) => {
+                wgpu::FilterMode::Nearest
+            }
+            _ => wgpu::FilterMode::Linear,
+        };
+        let sampler = Sampler::new(&id)
+            .new_address_mode(address_mode)
+            .new_filter(filter)
+            .new_mipmap_filter(mipmap_filter)
+            .build(&self.engine.get_renderer_server().device);
+        self.engine.set_sampler(sampler);
+        Some(id)
+    }
+
+    // textures are created once per image and color space
+    fn get_texture(
+        &mut self,
+        texture: gltf::Texture,
+        tex_coord: u32,
+        srgb: bool,
+    ) -> Option<String> {
+        if tex_coord != 0 {
+            println!(
+                "{}: textures using texture coordinate set {} aren't supported",
+                self.path.display(),
+                tex_coord
+            );
+            return None;
+        }
+        let index = texture.source().index();
+        let id = format!(
+            "{} image {}{}",
+            self.id_prefix,
+            index,
+            if srgb { "" } else { " linear" }
+        );
+        if self.engine.get_texture(&id).is_some() {
+            return Some(id);
+        }
+        let Some(image) = get_rgba_image(&self.images[index]) else {
+            println!("{}: image {} is invalid", self.path.display(), index);
+            return None;
+        };
+        let renderer_server = self.engine.get_renderer_server();
+        let mut builder = Texture::new(&id);
+        builder.new_image(&image).new_mipmaps(true);
+        if !srgb {
+            builder.new_format(wgpu::TextureFormat::Rgba8Unorm);
+        }
+        let texture = builder.build(&renderer_server.device, &renderer_server.queue);
+        self.engine.set_texture(texture);
+        Some(id)
+    }
+
+    fn get_material(&mut self, material: gltf::Material) -> (String, bool) {
+        if let Some(material) = self.materials.get(&material.index()) {
+            return material.clone();
+        }
+        let id = match material.index() {
+            Some(index) => format!("{} material {}", self.id_prefix, index),
+            None => format!("{} default material", self.id_prefix),
+        };
+        if material.alpha_mode() != gltf::material::AlphaMode::Opaque {
+            println!(
+                "{}: alpha mode {:?} of material {} isn't supported, it's drawn opaque",
+                self.path.display(),
+                material.alpha_mode(),
+                id
+            );
+        }
+        let pbr = material.pbr_metallic_roughness();
+        let mut builder = Material::new_pbr(&id);
+        builder
+            .new_base_color(Vec4::from(pbr.base_color_factor()))
+            .new_metallic(pbr.metallic_factor())
+            .new_roughness(pbr.roughness_factor())
+            .new_emissive(
+                Vec3::from(material.emissive_factor())
+                    * material.emissive_strength().unwrap_or(1.0),
+            );
+        // the engine samples every map of a material with one sampler
+        let mut sampler = None;
+        if let Some(info) = pbr.base_color_texture() {
+            sampler = Some(info.texture().sampler());
+            if let Some(id) = self.get_texture(info.texture(), info.tex_coord(), true) {
+                builder.new_base_color_texture(&id);
+            }
+        }
+        if let Some(info) = pbr.metallic_roughness_texture() {
+            sampler.get_or_insert(info.texture().sampler());
+            if let Some(id) = self.get_texture(info.texture(), info.tex_coord(), false) {
+                builder.new_metallic_roughness_texture(&id);
+            }
+        }
+        let mut has_normal_texture = false;
+        if let Some(info) = material.normal_texture() {
+            sampler.get_or_insert(info.texture().sampler());
+            if let Some(id) = self.get_texture(info.texture(), info.tex_coord(), false) {
+                builder
+                    .new_normal_texture(&id)
+                    .new_normal_scale(info.scale());
+                has_normal_texture = true;
+            }
+        }
+        if let Some(info) = material.occlusion_texture() {
+            sampler.get_or_insert(info.texture().sampler());
+            if let Some(id) = self.get_texture(info.texture(), info.tex_coord(), false) {
+                builder
+                    .new_occlusion_texture(&id)
+                    .new_occlusion_strength(info.strength());
+            }
+        }
+        if let Some(info) = material.emissive_texture() {
+            sampler.get_or_insert(info.texture().sampler());
+            if let Some(id) = self.get_texture(info.texture(), info.tex_coord(), true) {
+                builder.new_emissive_texture(&id);
+            }
+        }
+        if let Some(sampler_id) = sampler.and_then(|sampler| self.get_sampler(sampler)) {
+            builder.new_sampler(&sampler_id);
+        }
+        let built = builder.build(&self.engine.get_renderer_server().device);
+        self.engine.set_material(built);
+        self.materials
+            .insert(material.index(), (id.clone(), has_normal_texture));
+        (id, has_normal_texture)
+    }
+
+    fn get_mesh(&mut self, mesh: &gltf::Mesh, primitive: &gltf::Primitive) -> Option<Mesh> {
+        let key = (mesh.index(), primitive.index());
+        if let Some(mesh) = self.meshes.get(&key) {
+            return Some(mesh.clone());
+        }
+        let (_, has_normal_texture) = self.get_material(primitive.material());
+        let buffers = &self.buffers;
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+        let Some(positions) = reader.read_positions() else {
+            println!(
+                "{}: primitive {} of mesh {} has no positions",
+                self.path.display(),
+                primitive.index(),
+                mesh.index()
+            );
+            return None;
+        };
+        let positions: Vec<[f32; 3]> = positions.collect();
+        // primitives without indices use every vertex in order
+        let indices = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        let Some(indices) = get_triangle_list(primitive.mode(), indices) else {
+            println!(
+                "{}: {:?} primitives aren't supported, skipping primitive {} of mesh {}",
+                self.path.display(),
+                primitive.mode(),
+                primitive.index(),
+                mesh.index()
+            );
+            return None;
+        };
+
+        let mut builder = VertexDataBuilder::new();
+        builder
+            .set_vertex_positions(&positions)
+            .set_indicies(&indices);
+        match reader.read_normals() {
+            Some(normals) => {
+                builder.set_vertex_normals(&normals.collect::<Vec<_>>());
+            }
+            // the specification asks for flat normals when a primitive has none
+            None => {
+                builder.set_generate_normals(NormalMode::Flat);
+            }
+        }
+        match reader.read_tangents() {
+            Some(tangents) => {
+                builder.set_vertex_tangents(&tangents.collect::<Vec<_>>());
+            }
+            None => {
+                builder.set_generate_tangents(has_normal_texture);
+            }
+        }
+        if let Some(tex_coords) = reader.read_tex_coords(0) {
+            builder.set_vertex_tex_coords(&tex_coords.into_f32().collect::<Vec<_>>());
+        }
+        if let Some(tex_coords) = reader.read_tex_coords(1) {
+            builder.set_vertex_tex_coords1(&tex_coords.into_f32().collect::<Vec<_>>());
+        }
+        if let Some(colors) = reader.read_colors(0) {
+            builder.set_vertex_colors(&colors.into_rgba_f32().collect::<Vec<_>>());
+        }
+        if let Some(joints) = reader.read_joints(0) {
+            builder.set_vertex_joints(&joints.into_u16().collect::<Vec<_>>());
+        }
+        if let Some(weights) = reader.read_weights(0) {
+            builder.set_vertex_weights(&weights.into_f32().collect::<Vec<_>>());
+        }
+        let mesh_id = format!(
+            "{} mesh {} {}",
+            self.id_prefix,
+            mesh.index(),
+            primitive.index()
+        );
+        let built = builder.build(&mesh_id, self.engine);
+        self.meshes.insert(key, built.clone());
+        Some(built)
+    }
+
+    fn create_camera(&self, name: &str, camera: gltf::Camera) -> Camera3D {
+        let mut camera_3d = Camera3D::new(name);
+        // the aspect ratio always follows the resolution of the engine
+        match camera.projection() {
+            gltf::camera::Projection::Perspective(perspective) => {
+                camera_3d.projection = Projection::Perspective {
+                    fov: perspective.yfov().to_degrees(),
+                };
+                camera_3d.near = perspective.znear();
+                // the engine has no infinite projection, such cameras keep the default far plane
+                if let Some(far) = perspective.zfar() {
+                    camera_3d.far = far;
+                }
+            }
+            gltf::camera::Projection::Orthographic(orthographic) => {
+                camera_3d.projection = Projection::Orthographic {
+                    size: orthographic.ymag() * 2.0,
+                };
+                camera_3d.near = orthographic.znear();
+                camera_3d.far = orthographic.zfar();
+            }
+        }
+        camera_3d
+    }
+
+    // Nodes with a camera become a Camera3D and nodes with a single primitive a MeshInstance.
+    // Meshes with several primitives get one MeshInstance child per primitive
+    fn create_node(&mut self, node: gltf::Node) -> Box<dyn BaseNode> {
+        let name = node
+            .name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("node {}", node.index()));
+        let mut instances: Vec<MeshInstance> = Vec::new();
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                let Some(built) = self.get_mesh(&mesh, &primitive) else {
+                    continue;
+                };
+                let (material_id, _) = self.get_material(primitive.material());
+                let mut instance =
+                    MeshInstance::new(&format!("{} primitive {}", name, primitive.index()));
+                instance.mesh = Some(built);
+                instance.pipeline_id = PBR_PIPELINE.to_string();
+                instance.material_id = material_id;
+                instances.push(instance);
+            }
+        }
+
+        let mut result: Box<dyn BaseNode> = if let Some(camera) = node.camera() {
+            let mut camera_3d = self.create_camera(&name, camera);
+            set_transform(&mut camera_3d.transform, &node);
+            Box::new(camera_3d)
+        } else if instances.len() == 1 {
+            let mut instance = instances.remove(0);
+            instance.name = name;
+            set_transform(&mut instance.transform, &node);
+            Box::new(instance)
+        } else {
+            let mut group = Node::new(&name);
+            set_transform(&mut group.transform, &node);
+            Box::new(group)
+        };
+        for instance in instances {
+            result.add_node(Box::new(instance));
+        }
+        for child in node.children() {
+            let child = self.create_node(child);
+            result.add_node(child);
+        }
+        result
+    }
+}
+
+// Loads a .gltf or .glb file with its buffers and images into pbr materials, meshes and a
+// node tree of the default scene. The nodes keep the transforms of the file below the returned
+// root. Skins, animations and morph targets aren't imported
+pub fn load_gltf<P: AsRef<Path>>(
+    engine: &mut Engine,
+    path: P,
+    settings: &ImportSettings,
+) -> Result<Node, ImportError> {
+    let path = path.as_ref();
+    let (document, buffers, images) =
+        gltf::import(path).map_err(|error| ImportError::Gltf(path.to_path_buf(), error))?;
+    for extension in document.extensions_required() {
+        if !SUPPORTED_GLTF_EXTENSIONS.contains(&extension) {
+            return Err(ImportError::UnsupportedExtension(
+                path.to_path_buf(),
+                extension.to_string(),
+            ));
+        }
+    }
+    for extension in document.extensions_used() {
+        if !SUPPORTED_GLTF_EXTENSIONS.contains(&extension) {
+            println!(
+                "{}: extension {} isn't supported and is ignored",
+                path.display(),
+                extension
+            );
+        }
+    }
+
+    let file_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut root = Node::new(&file_name);
+    if settings.y_up_to_z_up {
+        // turns the Y up of the file into Z up, +Y becomes +Z and +Z becomes -Y
+        root.transform.set_rotation(Vec3::new(90.0, 0.0, 0.0));
+    }
+    let id_prefix = get_id_prefix(engine, path);
+    let mut importer = GltfImporter {
+        engine,
+        path,
+        id_prefix,
+        buffers,
+        images,
+        meshes: HashMap::new(),
+        materials: HashMap::new(),
+    };
+    // without scenes every node that isn't a child of another one is a root
+    let root_nodes: Vec<gltf::Node> = match document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        Some(scene) => scene.nodes().collect(),
+        None => {
+            let children: HashSet<usize> = document
+                .nodes()
+                .flat_map(|node| node.children().map(|child| child.index()))
+                .collect();
+            document
+                .nodes()
+                .filter(|node| !children.contains(&node.index()))
+                .collect()
+        }
+    };
+    for node in root_nodes {
+        let node = importer.create_node(node);
+        root.add_node(node);
+    }
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gltf::image::Format;
+    use gltf::mesh::Mode;
+
+    // a parent node with a triangle below it and a camera next to it, the buffer holds three
+    // positions followed by three u16 indices
+    const TEST_GLTF: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0, 2] }],
+        "nodes": [
+            { "name": "parent", "translation": [1.0, 2.0, 3.0], "children": [1] },
+            { "name": "triangle", "translation": [0.0, 1.0, 0.0], "mesh": 0 },
+            { "name": "camera", "translation": [0.0, 0.0, 5.0], "camera": 0 }
+        ],
+        "cameras": [{
+            "type": "perspective",
+            "perspective": { "yfov": 1.0, "znear": 0.5, "zfar": 50.0 }
+        }],
+        "meshes": [{
+            "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }]
+        }],
+        "materials": [{ "pbrMetallicRoughness": { "baseColorFactor": [1.0, 0.0, 0.0, 1.0] } }],
+        "accessors": [
+            {
+                "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+            },
+            { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+        ],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 6 }
+        ],
+        "buffers": [{
+            "byteLength": 44,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA="
+        }]
+    }"#;
+
+    fn get_image_data(format: Format, pixels: Vec<u8>) -> gltf::image::Data {
+        gltf::image::Data {
+            pixels,
+            format,
+            width: 2,
+            height: 1,
+        }
+    }
+
+    fn get_child<'a>(node: &'a dyn BaseNode, name: &str) -> &'a dyn BaseNode {
+        node.get_children()
+            .iter()
+            .find(|child| child.get_node_name() == name)
+            .unwrap_or_else(|| panic!("{} has no child {}", node.get_node_name(), name))
+            .as_ref()
+    }
+
+    fn get_global_translation(node: &dyn BaseNode) -> Vec3 {
+        node.get_global_transformation_matrix()
+            .transform_point3(Vec3::ZERO)
+    }
+
+    #[test]
+    fn triangle_strips_flip_every_other_triangle() {
+        assert_eq!(
+            get_triangle_list(Mode::TriangleStrip, vec![0, 1, 2, 3, 4]),
+            Some(vec![0, 1, 2, 2, 1, 3, 2, 3, 4])
+        );
+        assert_eq!(
+            get_triangle_list(Mode::TriangleStrip, vec![0, 1]),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn triangle_fans_share_the_first_vertex() {
+        assert_eq!(
+            get_triangle_list(Mode::TriangleFan, vec![0, 1, 2, 3]),
+            Some(vec![1, 2, 0, 2, 3, 0])
+        );
+        assert_eq!(
+            get_triangle_list(Mode::Triangles, vec![0, 1, 2]),
+            Some(vec![0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn lines_and_points_have_no_triangle_list() {
+        for mode in [Mode::Points, Mode::Lines, Mode::LineLoop, Mode::LineStrip] {
+            assert_eq!(get_triangle_list(mode, vec![0, 1, 2]), None);
+        }
+    }
+
+    #[test]
+    fn images_are_converted_to_rgba() {
+        let image = get_rgba_image(&get_image_data(Format::R16, vec![0, 0, 255, 255])).unwrap();
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn images_with_the_wrong_size_are_skipped() {
+        assert!(get_rgba_image(&get_image_data(Format::R16, vec![0; 3])).is_none());
+        assert!(get_rgba_image(&get_image_data(Format::R8G8B8A8, vec![0; 4])).is_none());
+        // one float short of two rgb pixels
+        assert!(get_rgba_image(&get_image_data(Format::R32G32B32FLOAT, vec![0; 20])).is_none());
+    }
+
+    #[test]
+    fn scenes_are_imported_below_a_z_up_root() {
+        let path = std::env::temp_dir().join(format!("gltf_import_{}.gltf", std::process::id()));
+        std::fs::write(&path, TEST_GLTF).unwrap();
+        let mut engine = pollster::block_on(Engine::new_headless((64, 64), true));
+        let root = load_gltf(&mut engine, &path, &ImportSettings::default());
+        let flat_root = load_gltf(
+            &mut engine,
+            &path,
+            &ImportSettings {
+                y_up_to_z_up: false,
+            },
+        );
+        std::fs::remove_file(&path).unwrap();
+        let (root, flat_root) = (root.unwrap(), flat_root.unwrap());
+
+        let names: Vec<&str> = root
+            .get_children()
+            .iter()
+            .map(|child| child.get_node_name())
+            .collect();
+        assert_eq!(names, ["parent", "camera"]);
+        let triangle = get_child(get_child(&root, "parent"), "triangle");
+        let triangle = triangle.as_any().downcast_ref::<MeshInstance>().unwrap();
+        let prefix = path.to_string_lossy().to_string();
+        assert_eq!(
+            triangle.mesh.as_ref().unwrap().get_mesh_id(),
+            format!("{} mesh 0 0", prefix)
+        );
+        assert_eq!(triangle.material_id, format!("{} material 0", prefix));
+        assert!(engine.get_material(&triangle.material_id).is_some());
+        let camera = get_child(&root, "camera");
+        let camera = camera.as_any().downcast_ref::<Camera3D>().unwrap();
+        assert_eq!((camera.near, camera.far), (0.5, 50.0));
+        // the second import gets its own ids
+        let flat_triangle = get_child(get_child(&flat_root, "parent"), "triangle");
+        assert_eq!(
+            flat_triangle
+                .as_any()
+                .downcast_ref::<MeshInstance>()
+                .unwrap()
+                .material_id,
+            format!("{} #2 material 0", prefix)
+        );
+
+        engine.add_root_node(root);
+        engine.step();
+        let root = engine.get_root_node_mut::<Node>().unwrap();
+        // +Y of the file becomes +Z and +Z becomes -Y
+        let parent = get_child(root, "parent");
+        assert!(get_global_translation(parent).abs_diff_eq(Vec3::new(1.0, -3.0, 2.0), 1e-5));
+        let triangle = get_child(parent, "triangle");
+        assert!(get_global_translation(triangle).abs_diff_eq(Vec3::new(1.0, -3.0, 3.0), 1e-5));
+        let camera = get_child(root, "camera");
+        assert!(get_global_translation(camera).abs_diff_eq(Vec3::new(0.0, -5.0, 0.0), 1e-5));
+
+        engine.add_root_node(flat_root);
+        engine.step();
+        let flat_root = engine.get_root_node_mut::<Node>().unwrap();
+        let triangle = get_child(get_child(flat_root, "parent"), "triangle");
+        assert!(get_global_translation(triangle).abs_diff_eq(Vec3::new(1.0, 3.0, 3.0), 1e-5));
+    }
+}
@@ -0,0 +1,796 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use glam::{Vec2, Vec3};
+
+use super::{get_id_prefix, ImportError, ImportSettings};
+use crate::engine::scene::nodes::mesh_instance::mesh::generation::NormalMode;
+use crate::engine::scene::nodes::mesh_instance::mesh::VertexDataBuilder;
+use crate::engine::scene::nodes::mesh_instance::MeshInstance;
+use crate::engine::scene::nodes::node::Node;
+use crate::engine::scene::nodes::BaseNode;
+use crate::engine::servers::renderer::material::Material;
+use crate::engine::servers::renderer::PBR_PIPELINE;
+use crate::engine::Engine;
+
+// Faces of one group that use the same material, indexed without duplicate vertices.
+// Texture coordinates are flipped so v = 0 is the top of the image like everywhere else
+pub struct ObjGroup {
+    pub name: String,
+    pub material: Option<String>,
+    pub positions: Vec<[f32; 3]>,
+    // empty when not every face had normals
+    pub normals: Vec<[f32; 3]>,
+    pub tex_coords: Vec<[f32; 2]>,
+    pub colors: Vec<[f32; 4]>,
+    pub indices: Vec<u32>,
+    // whether any smoothing group was active, used when normals have to be generated
+    pub smooth: bool,
+}
+
+pub struct ObjData {
+    pub groups: Vec<ObjGroup>,
+    // file names of the mtllib statements, relative to the OBJ file
+    pub material_libraries: Vec<String>,
+}
+
+// MTL material converted to metallic-roughness values
+pub struct ObjMaterial {
+    pub name: String,
+    pub base_color: glam::Vec4,
+    pub emissive: Vec3,
+    pub metallic: f32,
+    pub roughness: f32,
+    // file names relative to the MTL file
+    pub base_color_texture: Option<String>,
+    pub normal_texture: Option<String>,
+    pub emissive_texture: Option<String>,
+}
+
+impl ObjMaterial {
+    fn new(name: &str) -> ObjMaterial {
+        ObjMaterial {
+            name: name.to_string(),
+            base_color: glam::Vec4::ONE,
+            emissive: Vec3::ZERO,
+            metallic: 0.0,
+            roughness: 1.0,
+            base_color_texture: None,
+            normal_texture: None,
+            emissive_texture: None,
+        }
+    }
+}
+
+// the source split into statements, lines ending with a backslash continue on the next one
+fn get_statements(source: &str) -> Vec<(usize, String)> {
+    let mut statements = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (index, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let (line_number, mut statement) = pending.take().unwrap_or((index + 1, String::new()));
+        match line.trim_end().strip_suffix('\\') {
+            Some(line) => {
+                statement.push_str(line);
+                statement.push(' ');
+                pending = Some((line_number, statement));
+            }
+            None => {
+                statement.push_str(line);
+                statements.push((line_number, statement));
+            }
+        }
+    }
+    statements.extend(pending);
+    statements
+}
+
+struct StatementParser<'a> {
+    path: &'a Path,
+    line: usize,
+}
+
+impl StatementParser<'_> {
+    fn error(&self, message: String) -> ImportError {
+        ImportError::Parse {
+            path: self.path.to_path_buf(),
+            line: self.line,
+            message,
+        }
+    }
+    // at least min and at most N numbers, the missing ones are filled in from the defaults
+    fn parse_floats<const N: usize>(
+        &self,
+        arguments: &[&str],
+        min: usize,
+        defaults: [f32; N],
+    ) -> Result<[f32; N], ImportError> {
+        if arguments.len() < min {
+            return Err(self.error(format!(
+                "expected at least {} values, found {}",
+                min,
+                arguments.len()
+            )));
+        }
+        let mut values = defaults;
+        for (value, argument) in values.iter_mut().zip(arguments) {
+            *value = argument
+                .parse()
+                .map_err(|_| self.error(format!("invalid number '{}'", argument)))?;
+        }
+        Ok(values)
+    }
+    // OBJ indices start at 1, negative ones count back from the last element
+    fn parse_index(&self, argument: &str, count: usize, kind: &str) -> Result<usize, ImportError> {
+        let index: i64 = argument
+            .parse()
+            .map_err(|_| self.error(format!("invalid {} index '{}'", kind, argument)))?;
+        let resolved = if index < 0 {
+            count as i64 + index
+        } else {
+            index - 1
+        };
+        if index == 0 || resolved < 0 || resolved >= count as i64 {
+            return Err(self.error(format!(
+                "{} index {} is out of range, {} defined so far",
+                kind, index, count
+            )));
+        }
+        Ok(resolved as usize)
+    }
+}
+
+// Splits a polygon into triangles by clipping ears off its projection onto the plane it lies
+// in, so concave polygons work. Returns indices into the points
+fn triangulate(points: &[Vec3]) -> Vec<[usize; 3]> {
+    if points.len() == 3 {
+        return vec![[0, 1, 2]];
+    }
+    // Newell's method, robust for polygons that aren't quite planar
+    let mut normal = Vec3::ZERO;
+    for (index, current) in points.iter().enumerate() {
+        let next = points[(index + 1) % points.len()];
+        normal += Vec3::new(
+            (current.y - next.y) * (current.z + next.z),
+            (current.z - next.z) * (current.x + next.x),
+            (current.x - next.x) * (current.y + next.y),
+        );
+    }
+    // drop the axis the normal is closest to, swapping the others keeps the polygon
+    // counter clockwise
+    let abs = normal.abs();
+    let axis = if abs.x >= abs.y && abs.x >= abs.z {
+        0
+    } else if abs.y >= abs.z {
+        1
+    } else {
+        2
+    };
+    let (mut a, mut b) = [(1, 2), (2, 0), (0, 1)][axis];
+    if normal[axis] < 0.0 {
+        std::mem::swap(&mut a, &mut b);
+    }
+    let projected: Vec<Vec2> = points.iter().map(|p| Vec2::new(p[a], p[b])).collect();
+    let cross = |o: Vec2, p: Vec2, q: Vec2| (p - o).perp_dot(q - o);
+
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let ear = (0..count).find(|&i| {
+            let [previous, current, next] =
+                [(i + count - 1) % count, i, (i + 1) % count].map(|i| remaining[i]);
+            let (p, c, n) = (projected[previous], projected[current], projected[next]);
+            cross(p, c, n) > 0.0
+                && remaining.iter().all(|&other| {
+                    let o = projected[other];
+                    [previous, current, next].contains(&other)
+                        || o == p
+                        || o == c
+                        || o == n
+                        || cross(p, c, o) < 0.0
+                        || cross(c, n, o) < 0.0
+                        || cross(n, p, o) < 0.0
+                })
+        });
+        // self intersecting or degenerate polygons are fanned out
+        let Some(ear) = ear else {
+            break;
+        };
+        triangles.push([
+            remaining[(ear + count - 1) % count],
+            remaining[ear],
+            remaining[(ear + 1) % count],
+        ]);
+        remaining.remove(ear);
+    }
+    for i in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    triangles
+}
+
+struct GroupBuilder {
+    group: ObjGroup,
+    // position, texture coordinate and normal index of every vertex in the group
+    vertices: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+    has_normals: bool,
+}
+
+pub fn parse_obj(source: &str, path: &Path) -> Result<ObjData, ImportError> {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut colors: Vec<Option<[f32; 4]>> = Vec::new();
+    let mut tex_coords: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut material_libraries: Vec<String> = Vec::new();
+    let mut groups: Vec<GroupBuilder> = Vec::new();
+    let mut group_indices: HashMap<(String, Option<String>), usize> = HashMap::new();
+    let mut group_name = "default".to_string();
+    let mut material: Option<String> = None;
+    let mut smooth = false;
+
+    for (line, statement) in get_statements(source) {
+        let parser = StatementParser { path, line };
+        let mut tokens = statement.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+        match keyword {
+            "v" => {
+                let values = parser.parse_floats(&arguments, 3, [0.0, 0.0, 0.0, 1.0, 1.0, 1.0])?;
+                positions.push(Vec3::new(values[0], values[1], values[2]));
+                // some exporters append a color, otherwise the fourth value is an unused weight
+                colors.push((arguments.len() == 6).then(|| [values[3], values[4], values[5], 1.0]));
+            }
+            "vt" => {
+                let [u, v, _] = parser.parse_floats(&arguments, 1, [0.0; 3])?;
+                tex_coords.push([u, 1.0 - v]);
+            }
+            "vn" => normals.push(parser.parse_floats(&arguments, 3, [0.0; 3])?),
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(parser.error(format!(
+                        "a face needs at least 3 vertices, found {}",
+                        arguments.len()
+                    )));
+                }
+                let mut corners = Vec::with_capacity(arguments.len());
+                for argument in &arguments {
+                    let mut parts = argument.split('/');
+                    let position = parser.parse_index(
+                        parts.next().unwrap_or_default(),
+                        positions.len(),
+                        "position",
+                    )?;
+                    let tex_coord = match parts.next() {
+                        Some(part) if !part.is_empty() => Some(parser.parse_index(
+                            part,
+                            tex_coords.len(),
+                            "texture coordinate",
+                        )?),
+                        _ => None,
+                    };
+                    let normal = match parts.next() {
+                        Some(part) if !part.is_empty() => {
+                            Some(parser.parse_index(part, normals.len(), "normal")?)
+                        }
+                        _ => None,
+                    };
+                    corners.push((position, tex_coord, normal));
+                }
+
+                let key = (group_name.clone(), material.clone());
+                let group_index = *group_indices.entry(key).or_insert_with(|| {
+                    groups.push(GroupBuilder {
+                        group: ObjGroup {
+                            name: group_name.clone(),
+                            material: material.clone(),
+                            positions: Vec::new(),
+                            normals: Vec::new(),
+                            tex_coords: Vec::new(),
+                            colors: Vec::new(),
+                            indices: Vec::new(),
+                            smooth: false,
+                        },
+                        vertices: HashMap::new(),
+                        has_normals: true,
+                    });
+                    groups.len() - 1
+                });
+                let builder = &mut groups[group_index];
+                builder.group.smooth |= smooth;
+                let indices: Vec<u32> = corners
+                    .iter()
+                    .map(|&(position, tex_coord, normal)| {
+                        builder.has_normals &= normal.is_some();
+                        let group = &mut builder.group;
+                        *builder
+                            .vertices
+                            .entry((position, tex_coord, normal))
+                            .or_insert_with(|| {
+                                group.positions.push(positions[position].to_array());
+                                group.colors.push(colors[position].unwrap_or([1.0; 4]));
+                                group
+                                    .tex_coords
+                                    .push(tex_coord.map_or([0.0; 2], |i| tex_coords[i]));
+                                group.normals.push(normal.map_or([0.0; 3], |i| normals[i]));
+                                group.positions.len() as u32 - 1
+                            })
+                    })
+                    .collect();
+                let points: Vec<Vec3> = corners.iter().map(|c| positions[c.0]).collect();
+                for triangle in triangulate(&points) {
+                    builder
+                        .group
+                        .indices
+                        .extend(triangle.map(|corner| indices[corner]));
+                }
+            }
+            "g" | "o" => {
+                group_name = if arguments.is_empty() {
+                    "default".to_string()
+                } else {
+                    arguments.join(" ")
+                };
+            }
+            "usemtl" => {
+                if arguments.is_empty() {
+                    return Err(parser.error("usemtl needs a material name".to_string()));
+                }
+                material = Some(arguments.join(" "));
+            }
+            "mtllib" => material_libraries.extend(arguments.iter().map(|a| a.to_string())),
+            "s" => smooth = !matches!(arguments.first(), None | Some(&"off") | Some(&"0")),
+            // lines, points, curves and surfaces aren't supported
+            _ => {}
+        }
+    }
+
+    let groups = groups
+        .into_iter()
+        .map(|mut builder| {
+            if !builder.has_normals {
+                builder.group.normals.clear();
+            }
+            if builder.group.colors.iter().all(|color| *color == [1.0; 4]) {
+                builder.group.colors.clear();
+            }
+            builder.group
+        })
+        .collect();
+    Ok(ObjData {
+        groups,
+        material_libraries,
+    })
+}
+
+// texture statements can start with options like -bm 0.5 or -s 1 1 1 before the file name
+fn get_texture_file(arguments: &[&str]) -> Option<String> {
+    let mut index = 0;
+    while index < arguments.len() && arguments[index].starts_with('-') {
+        index += 1;
+        while index < arguments.len()
+            && (arguments[index].parse::<f32>().is_ok()
+                || ["on", "off", "r", "g", "b", "m", "l", "z"].contains(&arguments[index]))
+        {
+            index += 1;
+        }
+    }
+    (index < arguments.len()).then(|| arguments[index..].join(" "))
+}
+
+pub fn parse_mtl(source: &str, path: &Path) -> Result<Vec<ObjMaterial>, ImportError> {
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+    for (line, statement) in get_statements(source) {
+        let parser = StatementParser { path, line };
+        let mut tokens = statement.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+        if keyword == "newmtl" {
+            if arguments.is_empty() {
+                return Err(parser.error("newmtl needs a material name".to_string()));
+            }
+            materials.push(ObjMaterial::new(&arguments.join(" ")));
+            continue;
+        }
+        let Some(material) = materials.last_mut() else {
+            return Err(parser.error(format!("'{}' before the first newmtl", keyword)));
+        };
+        match keyword {
+            "Kd" => {
+                // a single value is used for every channel
+                let color = if arguments.len() == 1 {
+                    Vec3::splat(parser.parse_floats(&arguments, 1, [0.0])?[0])
+                } else {
+                    Vec3::from(parser.parse_floats(&arguments, 3, [0.0; 3])?)
+                };
+                material.base_color = color.extend(material.base_color.w);
+            }
+            "d" => material.base_color.w = parser.parse_floats(&arguments, 1, [1.0])?[0],
+            "Tr" => material.base_color.w = 1.0 - parser.parse_floats(&arguments, 1, [0.0])?[0],
+            "Ke" => material.emissive = Vec3::from(parser.parse_floats(&arguments, 3, [0.0; 3])?),
+            // the specular exponent like Blender exports it, Pr overrides it when both are set
+            "Ns" => {
+                let exponent = parser.parse_floats(&arguments, 1, [0.0])?[0];
+                material.roughness = 1.0 - (exponent.clamp(0.0, 1000.0) / 1000.0).sqrt();
+            }
+            "Pr" => material.roughness = parser.parse_floats(&arguments, 1, [1.0])?[0],
+            "Pm" => material.metallic = parser.parse_floats(&arguments, 1, [0.0])?[0],
+            "map_Kd" => material.base_color_texture = get_texture_file(&arguments),
+            "map_Bump" | "map_bump" | "bump" | "norm" => {
+                material.normal_texture = get_texture_file(&arguments)
+            }
+            "map_Ke" => material.emissive_texture = get_texture_file(&arguments),
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+fn read_file(path: &Path) -> Result<String, ImportError> {
+    std::fs::read_to_string(path).map_err(|error| ImportError::Io(path.to_path_buf(), error))
+}
+
+// textures are registered under their path, so materials sharing one load it once
+fn load_material_texture(
+    engine: &mut Engine,
+    directory: &Path,
+    file: &Option<String>,
+    srgb: bool,
+) -> Option<String> {
+    let path = directory.join(file.as_ref()?);
+    let id = path.to_string_lossy().to_string();
+    if engine.get_texture(&id).is_some() {
+        return Some(id);
+    }
+    let result = if srgb {
+        engine.load_texture(&id, &path)
+    } else {
+        engine.load_linear_texture(&id, &path)
+    };
+    match result {
+        Ok(()) => Some(id),
+        Err(error) => {
+            println!("failed to load texture {}: {}", id, error);
+            None
+        }
+    }
+}
+
+// Loads an OBJ file and the MTL files it references into pbr materials and one MeshInstance
+// per group and material. Mesh and material ids start with the path of the file to keep them
+// apart from other imports
+pub fn load_obj<P: AsRef<Path>>(
+    engine: &mut Engine,
+    path: P,
+    settings: &ImportSettings,
+) -> Result<Node, ImportError> {
+    let path = path.as_ref();
+    let data = parse_obj(&read_file(path)?, path)?;
+    let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let file_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let id_prefix = get_id_prefix(engine, path);
+
+    let mut materials: Vec<(ObjMaterial, PathBuf)> = Vec::new();
+    for library in &data.material_libraries {
+        let library_path = directory.join(library);
+        // a missing library only costs the materials, the geometry is still usable
+        let source = match read_file(&library_path) {
+            Ok(source) => source,
+            Err(error) => {
+                println!("{}", error);
+                continue;
+            }
+        };
+        let library_directory = library_path.parent().map(Path::to_path_buf);
+        for material in parse_mtl(&source, &library_path)? {
+            materials.push((material, library_directory.clone().unwrap_or_default()));
+        }
+    }
+
+    let mut material_ids: HashMap<Option<String>, (String, bool)> = HashMap::new();
+    let mut root = Node::new(&file_name);
+    if settings.y_up_to_z_up {
+        // turns the Y up of the file into Z up, +Y becomes +Z and +Z becomes -Y
+        root.transform.set_rotation(Vec3::new(90.0, 0.0, 0.0));
+    }
+    for group in &data.groups {
+        if group.indices.is_empty() {
+            continue;
+        }
+        let (material_id, has_normal_texture) = match material_ids.get(&group.material) {
+            Some(material) => material.clone(),
+            None => {
+                let material = group
+                    .material
+                    .as_ref()
+                    .and_then(|name| materials.iter().find(|(m, _)| &m.name == name));
+                if material.is_none() {
+                    if let Some(name) = &group.material {
+                        println!("material {} of {} not found", name, path.display());
+                    }
+                }
+                let material_id = format!(
+                    "{} {}",
+                    id_prefix,
+                    group.material.as_deref().unwrap_or("default")
+                );
+                let mut builder = Material::new_pbr(&material_id);
+                let mut has_normal_texture = false;
+                if let Some((material, directory)) = material {
+                    builder
+                        .new_base_color(material.base_color)
+                        .new_emissive(material.emissive)
+                        .new_metallic(material.metallic)
+                        .new_roughness(material.roughness);
+                    let textures = [
+                        (&material.base_color_texture, true),
+                        (&material.normal_texture, false),
+                        (&material.emissive_texture, true),
+                    ]
+                    .map(|(file, srgb)| load_material_texture(engine, directory, file, srgb));
+                    if let Some(id) = &textures[0] {
+                        builder.new_base_color_texture(id);
+                    }
+                    if let Some(id) = &textures[1] {
+                        builder.new_normal_texture(id);
+                        has_normal_texture = true;
+                    }
+                    if let Some(id) = &textures[2] {
+                        builder.new_emissive_texture(id);
+                        // the emissive map is multiplied with the factor
+                        if material.emissive == Vec3::ZERO {
+                            builder.new_emissive(Vec3::ONE);
+                        }
+                    }
+                }
+                let built = builder.build(&engine.get_renderer_server().device);
+                engine.set_material(built);
+                material_ids.insert(
+                    group.material.clone(),
+                    (material_id.clone(), has_normal_texture),
+                );
+                (material_id, has_normal_texture)
+            }
+        };
+
+        let name = match &group.material {
+            Some(material) if data.groups.iter().filter(|g| g.name == group.name).count() > 1 => {
+                format!("{} {}", group.name, material)
+            }
+            _ => group.name.clone(),
+        };
+        let mut builder = VertexDataBuilder::new();
+        builder
+            .set_vertex_positions(&group.positions)
+            .set_vertex_tex_coords(&group.tex_coords)
+            .set_vertex_colors(&group.colors)
+            .set_indicies(&group.indices)
+            .set_generate_tangents(has_normal_texture);
+        if group.normals.is_empty() {
+            builder.set_generate_normals(if group.smooth {
+                NormalMode::Smooth
+            } else {
+                NormalMode::Flat
+            });
+        } else {
+            builder.set_vertex_normals(&group.normals);
+        }
+        let mut instance = MeshInstance::new(&name);
+        instance.mesh = Some(builder.build(&format!("{} {}", id_prefix, name), engine));
+        instance.pipeline_id = PBR_PIPELINE.to_string();
+        instance.material_id = material_id;
+        root.add_node(Box::new(instance));
+    }
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_error_line(error: ImportError) -> (usize, String) {
+        match error {
+            ImportError::Parse { line, message, .. } => (line, message),
+            error => panic!("expected a parse error, got {}", error),
+        }
+    }
+
+    // every triangle has to keep the winding of the polygon and together they cover its area
+    fn check_triangulation(points: &[Vec3]) {
+        let triangles = triangulate(points);
+        assert_eq!(triangles.len(), points.len() - 2);
+        let polygon_area: f32 = (0..points.len())
+            .map(|i| {
+                points[i]
+                    .truncate()
+                    .perp_dot(points[(i + 1) % points.len()].truncate())
+            })
+            .sum::<f32>()
+            * 0.5;
+        let mut area = 0.0;
+        for [a, b, c] in triangles {
+            let triangle_area = (points[b] - points[a]).cross(points[c] - points[a]).z * 0.5;
+            assert!(triangle_area > 0.0, "{:?} is flipped", [a, b, c]);
+            area += triangle_area;
+        }
+        assert!((area - polygon_area).abs() < 1e-5);
+    }
+
+    #[test]
+    fn statements_join_continued_lines() {
+        let statements = get_statements("v 1 2 \\\n  3\n# comment\nf 1 2 3 # trailing\n");
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[0].0, 1);
+        assert_eq!(
+            statements[0].1.split_whitespace().collect::<Vec<_>>(),
+            ["v", "1", "2", "3"]
+        );
+        assert_eq!(statements[1], (3, String::new()));
+        assert_eq!(statements[2].0, 4);
+        assert_eq!(statements[2].1.trim(), "f 1 2 3");
+    }
+
+    #[test]
+    fn triangulation_handles_concave_polygons() {
+        // L shape
+        check_triangulation(&[
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(2.0, 1.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(1.0, 2.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+        ]);
+        // arrow pointing right with the reflex vertex first
+        check_triangulation(&[
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(3.0, 1.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+        ]);
+    }
+
+    #[test]
+    fn triangulation_keeps_the_winding_of_tilted_polygons() {
+        // the L shape moved onto the XZ plane, which turns it to face -Y
+        let points = [
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [2.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 2.0],
+            [0.0, 2.0],
+        ]
+        .map(|[x, y]| Vec3::new(x, 0.0, y));
+        for [a, b, c] in triangulate(&points) {
+            let normal = (points[b] - points[a]).cross(points[c] - points[a]);
+            assert!(normal.y < 0.0);
+        }
+    }
+
+    #[test]
+    fn obj_faces_are_grouped_by_name_and_material() {
+        let source = "mtllib scene.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0 0.5 0.5 0.5
+vt 0 0
+vt 1 1
+vn 0 0 1
+g floor
+usemtl stone
+f 1/1/1 2/2/1 3/2/1 4/1/1
+g wall
+s 1
+f -4 -3 -2
+";
+        let data = parse_obj(source, Path::new("scene.obj")).unwrap();
+        assert_eq!(data.material_libraries, ["scene.mtl"]);
+        assert_eq!(data.groups.len(), 2);
+
+        let floor = &data.groups[0];
+        assert_eq!(floor.name, "floor");
+        assert_eq!(floor.material.as_deref(), Some("stone"));
+        assert_eq!(floor.positions.len(), 4);
+        assert_eq!(floor.indices.len(), 6);
+        assert_eq!(floor.normals, vec![[0.0, 0.0, 1.0]; 4]);
+        // v is flipped so 0 is the top of the image
+        assert_eq!(floor.tex_coords[0], [0.0, 1.0]);
+        assert_eq!(floor.tex_coords[1], [1.0, 0.0]);
+        assert_eq!(floor.colors[3], [0.5, 0.5, 0.5, 1.0]);
+        assert!(!floor.smooth);
+
+        // the wall keeps the material and has neither normals nor colors
+        let wall = &data.groups[1];
+        assert_eq!(wall.name, "wall");
+        assert_eq!(wall.material.as_deref(), Some("stone"));
+        assert_eq!(wall.indices, [0, 1, 2]);
+        assert!(wall.normals.is_empty());
+        assert!(wall.colors.is_empty());
+        assert!(wall.smooth);
+    }
+
+    #[test]
+    fn obj_errors_report_the_line() {
+        let path = Path::new("bad.obj");
+        let error = parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 5\n", path)
+            .err()
+            .unwrap();
+        assert_eq!(
+            get_error_line(error),
+            (
+                3,
+                "position index 5 is out of range, 2 defined so far".to_string()
+            )
+        );
+        // continued lines report the line they start on
+        let error = parse_obj("# header\nv 0 \\\n x 0\n", path).err().unwrap();
+        assert_eq!(get_error_line(error), (2, "invalid number 'x'".to_string()));
+        let error = parse_obj("\n\nvn 0 1\n", path).err().unwrap();
+        assert_eq!(
+            get_error_line(error),
+            (3, "expected at least 3 values, found 2".to_string())
+        );
+        assert_eq!(
+            format!("{}", parse_obj("f 1 2\n", path).err().unwrap()),
+            "bad.obj:1: a face needs at least 3 vertices, found 2"
+        );
+    }
+
+    #[test]
+    fn mtl_materials_are_converted_to_metallic_roughness() {
+        let source = "newmtl stone
+Kd 0.5 0.25 1
+d 0.5
+Ke 1 0 0
+Pm 0.75
+Pr 0.5
+map_Kd -s 1 1 1 -bm 0.5 textures/stone color.png
+map_Bump normal.png
+
+newmtl glass
+Kd 0.2
+Tr 0.25
+";
+        let materials = parse_mtl(source, Path::new("scene.mtl")).unwrap();
+        assert_eq!(materials.len(), 2);
+        let stone = &materials[0];
+        assert_eq!(stone.name, "stone");
+        assert_eq!(stone.base_color, glam::Vec4::new(0.5, 0.25, 1.0, 0.5));
+        assert_eq!(stone.emissive, Vec3::X);
+        assert_eq!(stone.metallic, 0.75);
+        assert_eq!(stone.roughness, 0.5);
+        assert_eq!(
+            stone.base_color_texture.as_deref(),
+            Some("textures/stone color.png")
+        );
+        assert_eq!(stone.normal_texture.as_deref(), Some("normal.png"));
+        assert_eq!(stone.emissive_texture, None);
+        let glass = &materials[1];
+        assert_eq!(glass.base_color, glam::Vec4::new(0.2, 0.2, 0.2, 0.75));
+        assert_eq!(glass.roughness, 1.0);
+    }
+
+    #[test]
+    fn mtl_errors_report_the_line() {
+        let path = Path::new("bad.mtl");
+        let error = parse_mtl("# header\nKd 1 1 1\n", path).err().unwrap();
+        assert_eq!(
+            get_error_line(error),
+            (2, "'Kd' before the first newmtl".to_string())
+        );
+        let error = parse_mtl("newmtl a\n\nNs high\n", path).err().unwrap();
+        assert_eq!(
+            get_error_line(error),
+            (3, "invalid number 'high'".to_string())
+        );
+    }
+}
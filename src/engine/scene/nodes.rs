@@ -1,4 +1,7 @@
+pub mod camera_3d;
+pub mod light;
 pub mod mesh_instance;
+pub mod multi_mesh_instance;
 pub mod node;
 
 pub trait BaseNode {
@@ -6,7 +9,11 @@ pub trait BaseNode {
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
     fn get_node_name(&self) -> &str;
     fn update(&mut self, delta: f64);
+    // local transform relative to the parent node
     fn get_transformation_matrix(&self) -> &glam::Mat4;
+    // world space transform, updated by the engine every frame
+    fn get_global_transformation_matrix(&self) -> &glam::Mat4;
+    fn set_global_transformation_matrix(&mut self, mx: glam::Mat4);
     fn add_node(&mut self, node: Box<dyn BaseNode + 'static>);
     fn get_node_mut(&mut self, name: &str) -> Option<&mut Box<dyn BaseNode + 'static>>;
     fn get_children(&self) -> &Vec<Box<dyn BaseNode + 'static>>;
@@ -0,0 +1,133 @@
+use crate::engine::scene::nodes::BaseNode;
+use crate::engine::scene::utils::Transform;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    // vertical field of view in degrees
+    Perspective { fov: f32 },
+    // height of the visible area in world units, the width follows the aspect ratio
+    Orthographic { size: f32 },
+}
+
+// The camera looks down its local -Z axis. The engine renders the scene from the first
+// camera in the tree that has current set
+pub struct Camera3D {
+    pub name: String,
+    pub transform: Transform,
+    pub children: Vec<Box<dyn BaseNode>>,
+    pub projection: Projection,
+    pub near: f32,
+    pub far: f32,
+    pub current: bool,
+    transform_mx: glam::Mat4,
+    global_transform_mx: glam::Mat4,
+}
+
+impl Camera3D {
+    pub fn new(name: &str) -> Camera3D {
+        Camera3D {
+            name: name.to_string(),
+            transform: Transform::new(),
+            children: Vec::new(),
+            projection: Projection::Perspective { fov: 45.0 },
+            near: 0.1,
+            far: 100.0,
+            current: true,
+            transform_mx: glam::Mat4::IDENTITY,
+            global_transform_mx: glam::Mat4::IDENTITY,
+        }
+    }
+
+    pub fn get_view_matrix(&self) -> glam::Mat4 {
+        self.global_transform_mx.inverse()
+    }
+
+    // depth is mapped to 0..1 like wgpu expects
+    pub fn get_projection_matrix(&self, aspect_ratio: f32) -> glam::Mat4 {
+        match self.projection {
+            Projection::Perspective { fov } => {
+                glam::Mat4::perspective_rh(fov.to_radians(), aspect_ratio, self.near, self.far)
+            }
+            Projection::Orthographic { size } => {
+                let half_height = size * 0.5;
+                let half_width = half_height * aspect_ratio;
+                glam::Mat4::orthographic_rh(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    self.near,
+                    self.far,
+                )
+            }
+        }
+    }
+
+    pub fn get_view_projection_matrix(&self, aspect_ratio: f32) -> glam::Mat4 {
+        self.get_projection_matrix(aspect_ratio) * self.get_view_matrix()
+    }
+}
+
+impl BaseNode for Camera3D {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+    fn get_node_name(&self) -> &str {
+        &self.name
+    }
+    fn update(&mut self, delta: f64) {
+        if self.transform.get_values_changed() {
+            self.transform_mx = self.transform.generate_transform_matrix();
+            self.transform.set_values_changed(false);
+        }
+        for child in self.children.iter_mut() {
+            child.update(delta);
+        }
+    }
+    fn get_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.transform_mx
+    }
+    fn get_global_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.global_transform_mx
+    }
+    fn set_global_transformation_matrix(&mut self, mx: glam::Mat4) {
+        self.global_transform_mx = mx;
+    }
+    fn add_node(&mut self, node: Box<dyn BaseNode + 'static>) {
+        self.children.push(node);
+    }
+    fn get_node_mut(&mut self, name: &str) -> Option<&mut Box<dyn BaseNode + 'static>> {
+        if let Some(node) = self
+            .children
+            .iter_mut()
+            .find(|node| node.get_node_name() == name)
+        {
+            return Some(node);
+        }
+        println!("node: {} not found", name);
+        None
+    }
+    fn get_children(&self) -> &Vec<Box<dyn BaseNode + 'static>> {
+        &self.children
+    }
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn BaseNode + 'static>> {
+        &mut self.children
+    }
+    fn remove_node(&mut self, name: &str) {
+        if let Some(index) = self
+            .children
+            .iter()
+            .position(|node| node.get_node_name() == name)
+        {
+            self.children.remove(index);
+        } else {
+            println!(
+                "couldn't find a node: {} or it has been already removed",
+                name
+            )
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use crate::engine::scene::nodes::BaseNode;
+use crate::engine::scene::utils::Transform;
+use crate::engine::servers::renderer::{GPULight, GPUShadow, SHADOW_MAP_SIZE};
+
+// Lights shine along their local -Z axis, like cameras look down it. The engine collects
+// every light of the tree into light_buffer each frame
+
+// global light without a position, e.g. the sun
+pub struct DirectionalLight {
+    pub name: String,
+    pub transform: Transform,
+    pub children: Vec<Box<dyn BaseNode>>,
+    pub color: glam::Vec3,
+    pub intensity: f32,
+    pub cast_shadows: bool,
+    // the camera frustum is split into up to MAX_SHADOW_CASCADES shadow maps until shadow_distance
+    pub shadow_cascade_count: u32,
+    pub shadow_distance: f32,
+    transform_mx: glam::Mat4,
+    global_transform_mx: glam::Mat4,
+}
+
+impl DirectionalLight {
+    pub fn new(name: &str) -> DirectionalLight {
+        DirectionalLight {
+            name: name.to_string(),
+            transform: Transform::new(),
+            children: Vec::new(),
+            color: glam::Vec3::ONE,
+            intensity: 1.0,
+            cast_shadows: false,
+            shadow_cascade_count: 4,
+            shadow_distance: 50.0,
+            transform_mx: glam::Mat4::IDENTITY,
+            global_transform_mx: glam::Mat4::IDENTITY,
+        }
+    }
+
+    pub fn get_direction(&self) -> glam::Vec3 {
+        get_forward(&self.global_transform_mx)
+    }
+
+    pub fn get_gpu_light(&self) -> GPULight {
+        GPULight::new_directional(self.get_direction(), self.color, self.intensity)
+    }
+}
+
+impl BaseNode for DirectionalLight {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+    fn get_node_name(&self) -> &str {
+        &self.name
+    }
+    fn update(&mut self, delta: f64) {
+        if self.transform.get_values_changed() {
+            self.transform_mx = self.transform.generate_transform_matrix();
+            self.transform.set_values_changed(false);
+        }
+        for child in self.children.iter_mut() {
+            child.update(delta);
+        }
+    }
+    fn get_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.transform_mx
+    }
+    fn get_global_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.global_transform_mx
+    }
+    fn set_global_transformation_matrix(&mut self, mx: glam::Mat4) {
+        self.global_transform_mx = mx;
+    }
+    fn add_node(&mut self, node: Box<dyn BaseNode + 'static>) {
+        self.children.push(node);
+    }
+    fn get_node_mut(&mut self, name: &str) -> Option<&mut Box<dyn BaseNode + 'static>> {
+        if let Some(node) = self
+            .children
+            .iter_mut()
+            .find(|node| node.get_node_name() == name)
+        {
+            return Some(node);
+        }
+        println!("node: {} not found", name);
+        None
+    }
+    fn get_children(&self) -> &Vec<Box<dyn BaseNode + 'static>> {
+        &self.children
+    }
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn BaseNode + 'static>> {
+        &mut self.children
+    }
+    fn remove_node(&mut self, name: &str) {
+        if let Some(index) = self
+            .children
+            .iter()
+            .position(|node| node.get_node_name() == name)
+        {
+            self.children.remove(index);
+        } else {
+            println!(
+                "couldn't find a node: {} or it has been already removed",
+                name
+            )
+        }
+    }
+}
+
+// light shining in every direction that fades out until range
+pub struct PointLight {
+    pub name: String,
+    pub transform: Transform,
+    pub children: Vec<Box<dyn BaseNode>>,
+    pub color: glam::Vec3,
+    pub intensity: f32,
+    pub range: f32,
+    transform_mx: glam::Mat4,
+    global_transform_mx: glam::Mat4,
+}
+
+impl PointLight {
+    pub fn new(name: &str) -> PointLight {
+        PointLight {
+            name: name.to_string(),
+            transform: Transform::new(),
+            children: Vec::new(),
+            color: glam::Vec3::ONE,
+            intensity: 1.0,
+            range: 10.0,
+            transform_mx: glam::Mat4::IDENTITY,
+            global_transform_mx: glam::Mat4::IDENTITY,
+        }
+    }
+
+    pub fn get_position(&self) -> glam::Vec3 {
+        self.global_transform_mx.w_axis.truncate()
+    }
+
+    pub fn get_gpu_light(&self) -> GPULight {
+        GPULight::new_point(self.get_position(), self.color, self.intensity, self.range)
+    }
+}
+
+impl BaseNode for PointLight {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+    fn get_node_name(&self) -> &str {
+        &self.name
+    }
+    fn update(&mut self, delta: f64) {
+        if self.transform.get_values_changed() {
+            self.transform_mx = self.transform.generate_transform_matrix();
+            self.transform.set_values_changed(false);
+        }
+        for child in self.children.iter_mut() {
+            child.update(delta);
+        }
+    }
+    fn get_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.transform_mx
+    }
+    fn get_global_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.global_transform_mx
+    }
+    fn set_global_transformation_matrix(&mut self, mx: glam::Mat4) {
+        self.global_transform_mx = mx;
+    }
+    fn add_node(&mut self, node: Box<dyn BaseNode + 'static>) {
+        self.children.push(node);
+    }
+    fn get_node_mut(&mut self, name: &str) -> Option<&mut Box<dyn BaseNode + 'static>> {
+        if let Some(node) = self
+            .children
+            .iter_mut()
+            .find(|node| node.get_node_name() == name)
+        {
+            return Some(node);
+        }
+        println!("node: {} not found", name);
+        None
+    }
+    fn get_children(&self) -> &Vec<Box<dyn BaseNode + 'static>> {
+        &self.children
+    }
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn BaseNode + 'static>> {
+        &mut self.children
+    }
+    fn remove_node(&mut self, name: &str) {
+        if let Some(index) = self
+            .children
+            .iter()
+            .position(|node| node.get_node_name() == name)
+        {
+            self.children.remove(index);
+        } else {
+            println!(
+                "couldn't find a node: {} or it has been already removed",
+                name
+            )
+        }
+    }
+}
+
+// point light limited to a cone, the angles are measured from the cone axis in degrees
+pub struct SpotLight {
+    pub name: String,
+    pub transform: Transform,
+    pub children: Vec<Box<dyn BaseNode>>,
+    pub color: glam::Vec3,
+    pub intensity: f32,
+    pub range: f32,
+    // full intensity inside the inner angle, fading out until the outer angle
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub cast_shadows: bool,
+    transform_mx: glam::Mat4,
+    global_transform_mx: glam::Mat4,
+}
+
+impl SpotLight {
+    pub fn new(name: &str) -> SpotLight {
+        SpotLight {
+            name: name.to_string(),
+            transform: Transform::new(),
+            children: Vec::new(),
+            color: glam::Vec3::ONE,
+            intensity: 1.0,
+            range: 10.0,
+            inner_angle: 30.0,
+            outer_angle: 45.0,
+            cast_shadows: false,
+            transform_mx: glam::Mat4::IDENTITY,
+            global_transform_mx: glam::Mat4::IDENTITY,
+        }
+    }
+
+    pub fn get_position(&self) -> glam::Vec3 {
+        self.global_transform_mx.w_axis.truncate()
+    }
+
+    pub fn get_direction(&self) -> glam::Vec3 {
+        get_forward(&self.global_transform_mx)
+    }
+
+    pub fn get_gpu_light(&self) -> GPULight {
+        GPULight::new_spot(
+            self.get_position(),
+            self.get_direction(),
+            self.color,
+            self.intensity,
+            self.range,
+            (self.inner_angle, self.outer_angle),
+        )
+    }
+
+    // perspective covering the outer cone until the range
+    pub fn get_shadow(&self) -> GPUShadow {
+        let position = self.get_position();
+        let direction = self.get_direction();
+        let fov = (self.outer_angle * 2.0).clamp(1.0, 170.0).to_radians();
+        let view_mx =
+            glam::Mat4::look_at_rh(position, position + direction, get_up_vector(direction));
+        let proj_mx =
+            glam::Mat4::perspective_rh(fov, 1.0, (self.range * 0.01).max(0.05), self.range);
+        // offset per unit of distance to the light, roughly one and a half shadow map texels
+        let normal_offset = 3.0 * (fov * 0.5).tan() / SHADOW_MAP_SIZE as f32;
+        GPUShadow::new(&(proj_mx * view_mx), 0.0, normal_offset)
+    }
+}
+
+impl BaseNode for SpotLight {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+    fn get_node_name(&self) -> &str {
+        &self.name
+    }
+    fn update(&mut self, delta: f64) {
+        if self.transform.get_values_changed() {
+            self.transform_mx = self.transform.generate_transform_matrix();
+            self.transform.set_values_changed(false);
+        }
+        for child in self.children.iter_mut() {
+            child.update(delta);
+        }
+    }
+    fn get_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.transform_mx
+    }
+    fn get_global_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.global_transform_mx
+    }
+    fn set_global_transformation_matrix(&mut self, mx: glam::Mat4) {
+        self.global_transform_mx = mx;
+    }
+    fn add_node(&mut self, node: Box<dyn BaseNode + 'static>) {
+        self.children.push(node);
+    }
+    fn get_node_mut(&mut self, name: &str) -> Option<&mut Box<dyn BaseNode + 'static>> {
+        if let Some(node) = self
+            .children
+            .iter_mut()
+            .find(|node| node.get_node_name() == name)
+        {
+            return Some(node);
+        }
+        println!("node: {} not found", name);
+        None
+    }
+    fn get_children(&self) -> &Vec<Box<dyn BaseNode + 'static>> {
+        &self.children
+    }
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn BaseNode + 'static>> {
+        &mut self.children
+    }
+    fn remove_node(&mut self, name: &str) {
+        if let Some(index) = self
+            .children
+            .iter()
+            .position(|node| node.get_node_name() == name)
+        {
+            self.children.remove(index);
+        } else {
+            println!(
+                "couldn't find a node: {} or it has been already removed",
+                name
+            )
+        }
+    }
+}
+
+fn get_forward(global_transform_mx: &glam::Mat4) -> glam::Vec3 {
+    global_transform_mx
+        .transform_vector3(glam::Vec3::NEG_Z)
+        .normalize_or_zero()
+}
+
+fn get_up_vector(direction: glam::Vec3) -> glam::Vec3 {
+    if direction.cross(glam::Vec3::Z).length_squared() < 0.0001 {
+        glam::Vec3::Y
+    } else {
+        glam::Vec3::Z
+    }
+}
+
+// Splits the camera frustum between its near plane and max_distance into cascades, closer
+// cascades cover a smaller area so the shadow resolution follows the perspective.
+// Every cascade is an orthographic projection around the bounding sphere of its slice
+pub fn get_shadow_cascades(
+    direction: glam::Vec3,
+    cascade_count: u32,
+    max_distance: f32,
+    camera_view_proj_mx: &glam::Mat4,
+    (near, far): (f32, f32),
+) -> Vec<GPUShadow> {
+    let cascade_count = cascade_count.max(1);
+    let max_distance = max_distance.clamp(near, far);
+    // frustum edges from the near to the far plane
+    let inverse_mx = camera_view_proj_mx.inverse();
+    let mut edges: Vec<(glam::Vec3, glam::Vec3)> = Vec::new();
+    for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+        edges.push((
+            inverse_mx.project_point3(glam::Vec3::new(x, y, 0.0)),
+            inverse_mx.project_point3(glam::Vec3::new(x, y, 1.0)),
+        ));
+    }
+    let up = get_up_vector(direction);
+    // light space without translation, cascade centers are snapped to its texel grid so
+    // the shadows don't shimmer when the camera moves
+    let light_mx = glam::Mat4::look_at_rh(glam::Vec3::ZERO, direction, up);
+    let mut cascades: Vec<GPUShadow> = Vec::new();
+    let mut split_start = near;
+    for i in 1..=cascade_count {
+        let ratio = i as f32 / cascade_count as f32;
+        let uniform_split = near + (max_distance - near) * ratio;
+        let log_split = near * (max_distance / near).powf(ratio);
+        let split_end = uniform_split + (log_split - uniform_split) * 0.75;
+
+        let mut corners: Vec<glam::Vec3> = Vec::new();
+        for (near_corner, far_corner) in edges.iter() {
+            for distance in [split_start, split_end] {
+                let t = (distance - near) / (far - near);
+                corners.push(near_corner.lerp(*far_corner, t));
+            }
+        }
+        let center = corners.iter().sum::<glam::Vec3>() / corners.len() as f32;
+        let radius = corners
+            .iter()
+            .map(|corner| corner.distance(center))
+            .fold(0.0, f32::max);
+        let radius = (radius * 16.0).ceil() / 16.0;
+        let texel_size = radius * 2.0 / SHADOW_MAP_SIZE as f32;
+        let mut light_center = light_mx.transform_point3(center);
+        light_center.x = (light_center.x / texel_size).floor() * texel_size;
+        light_center.y = (light_center.y / texel_size).floor() * texel_size;
+        let center = light_mx.inverse().transform_point3(light_center);
+
+        // casters outside the slice but between it and the light still have to be rendered
+        let caster_distance = radius * 2.0;
+        let eye = center - direction * (radius + caster_distance);
+        let view_mx = glam::Mat4::look_at_rh(eye, center, up);
+        let proj_mx = glam::Mat4::orthographic_rh(
+            -radius,
+            radius,
+            -radius,
+            radius,
+            0.0,
+            radius * 2.0 + caster_distance,
+        );
+        cascades.push(GPUShadow::new(
+            &(proj_mx * view_mx),
+            split_end,
+            texel_size * 1.5,
+        ));
+        split_start = split_end;
+    }
+    cascades
+}
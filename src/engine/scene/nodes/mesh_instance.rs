@@ -1,5 +1,5 @@
 use crate::engine::scene::nodes::BaseNode;
-use crate::engine::scene::utils::Transform;
+use crate::engine::scene::utils::{Aabb, BoundingSphere, Transform};
 pub mod mesh;
 use mesh::Mesh;
 pub struct MeshInstance {
@@ -8,7 +8,17 @@ pub struct MeshInstance {
     pub children: Vec<Box<dyn BaseNode>>,
     pub mesh: Option<Mesh>,
     pub pipeline_id: String,
+    // when set, the material's pipeline is used instead of pipeline_id
+    pub material_id: String,
+    // whether the instance is drawn into shadow maps and darkened by them
+    pub cast_shadows: bool,
+    pub receive_shadows: bool,
     transform_mx: glam::Mat4,
+    global_transform_mx: glam::Mat4,
+    // world space bounds of the mesh and the local bounds they were computed from
+    world_aabb: Option<Aabb>,
+    world_bounding_sphere: Option<BoundingSphere>,
+    bounds_source: Option<(Aabb, BoundingSphere)>,
 }
 
 impl MeshInstance {
@@ -19,9 +29,39 @@ impl MeshInstance {
             children: Vec::new(),
             mesh: None,
             pipeline_id: String::new(),
+            material_id: String::new(),
+            cast_shadows: true,
+            receive_shadows: true,
             transform_mx: glam::Mat4::IDENTITY,
+            global_transform_mx: glam::Mat4::IDENTITY,
+            world_aabb: None,
+            world_bounding_sphere: None,
+            bounds_source: None,
         }
     }
+
+    // World space bounds of the mesh, they follow the global transform the engine sets every
+    // frame and are None without a mesh or before the first frame
+    pub fn get_world_aabb(&self) -> Option<&Aabb> {
+        self.world_aabb.as_ref()
+    }
+    pub fn get_world_bounding_sphere(&self) -> Option<&BoundingSphere> {
+        self.world_bounding_sphere.as_ref()
+    }
+
+    fn update_world_bounds(&mut self, transform_changed: bool) {
+        let local_bounds = self
+            .mesh
+            .as_ref()
+            .map(|mesh| (*mesh.get_aabb(), *mesh.get_bounding_sphere()));
+        if !transform_changed && local_bounds == self.bounds_source {
+            return;
+        }
+        self.bounds_source = local_bounds;
+        self.world_aabb = local_bounds.map(|(aabb, _)| aabb.transform(&self.global_transform_mx));
+        self.world_bounding_sphere =
+            local_bounds.map(|(_, sphere)| sphere.transform(&self.global_transform_mx));
+    }
 }
 
 impl BaseNode for MeshInstance {
@@ -37,18 +77,24 @@ impl BaseNode for MeshInstance {
     fn update(&mut self, delta: f64) {
         if self.transform.get_values_changed() {
             self.transform_mx = self.transform.generate_transform_matrix();
-            for i in 0..self.children.len() {
-                self.children[i].update(delta);
-                self.transform_mx = self.children[i]
-                    .get_transformation_matrix()
-                    .mul_mat4(&self.transform_mx);
-            }
             self.transform.set_values_changed(false);
         }
+        for child in self.children.iter_mut() {
+            child.update(delta);
+        }
     }
     fn get_transformation_matrix(&self) -> &glam::Mat4 {
         &self.transform_mx
     }
+    fn get_global_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.global_transform_mx
+    }
+    fn set_global_transformation_matrix(&mut self, mx: glam::Mat4) {
+        // the mesh can be swapped at any time, so its bounds are compared as well
+        let transform_changed = mx != self.global_transform_mx || self.bounds_source.is_none();
+        self.global_transform_mx = mx;
+        self.update_world_bounds(transform_changed);
+    }
     fn add_node(&mut self, node: Box<dyn BaseNode + 'static>) {
         self.children.push(node);
     }
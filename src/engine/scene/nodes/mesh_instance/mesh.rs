@@ -1,22 +1,262 @@
+pub mod generation;
+pub mod optimization;
+pub mod primitives;
+
 use bytemuck::{Pod, Zeroable};
 
+use crate::engine::scene::utils::{Aabb, BoundingSphere};
+use crate::engine::servers::renderer::resources::VertexBufferLayout;
 use crate::engine::Engine;
+use generation::{generate_normals, generate_tangents, NormalMode};
+use optimization::{generate_lods, optimize_vertex_cache, optimize_vertex_fetch, weld_vertices};
 #[repr(C)]
-#[derive(Clone, Copy, Pod, Zeroable)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
 pub struct Vertex {
     pos: [f32; 3],
+    normal: [f32; 3],
+    tex_coord: [f32; 2],
+    tex_coord1: [f32; 2],
+    // xyz is the tangent and w the sign of the bitangent
+    tangent: [f32; 4],
+    color: [f32; 4],
+    joints: [u16; 4],
+    weights: [f32; 4],
+}
+
+impl Default for Vertex {
+    fn default() -> Self {
+        Vertex {
+            pos: [0.0; 3],
+            normal: [0.0; 3],
+            tex_coord: [0.0; 2],
+            tex_coord1: [0.0; 2],
+            tangent: [0.0; 4],
+            color: [1.0; 4],
+            joints: [0; 4],
+            weights: [0.0; 4],
+        }
+    }
+}
+
+impl Vertex {
+    pub fn get_position(&self) -> glam::Vec3 {
+        glam::Vec3::from(self.pos)
+    }
+    pub fn get_normal(&self) -> glam::Vec3 {
+        glam::Vec3::from(self.normal)
+    }
+    pub fn get_tex_coord(&self) -> glam::Vec2 {
+        glam::Vec2::from(self.tex_coord)
+    }
+    pub fn get_tex_coord1(&self) -> glam::Vec2 {
+        glam::Vec2::from(self.tex_coord1)
+    }
+    pub fn get_tangent(&self) -> glam::Vec4 {
+        glam::Vec4::from(self.tangent)
+    }
+    pub fn get_color(&self) -> glam::Vec4 {
+        glam::Vec4::from(self.color)
+    }
+    pub fn get_joints(&self) -> [u16; 4] {
+        self.joints
+    }
+    pub fn get_weights(&self) -> glam::Vec4 {
+        glam::Vec4::from(self.weights)
+    }
+    // layout of the engine's pipelines, every attribute interleaved in one buffer
+    pub fn get_vertex_buffer_layout() -> VertexBufferLayout {
+        VertexLayout::default()
+            .get_vertex_buffer_layouts()
+            .remove(0)
+    }
+}
+
+// every attribute a vertex can have, the discriminant is its shader location
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VertexAttribute {
+    Position = 0,
+    Normal = 1,
+    TexCoord0 = 2,
+    TexCoord1 = 3,
+    Tangent = 4,
+    Color = 5,
+    Joints = 6,
+    Weights = 7,
+}
+
+impl VertexAttribute {
+    pub const ALL: [VertexAttribute; 8] = [
+        VertexAttribute::Position,
+        VertexAttribute::Normal,
+        VertexAttribute::TexCoord0,
+        VertexAttribute::TexCoord1,
+        VertexAttribute::Tangent,
+        VertexAttribute::Color,
+        VertexAttribute::Joints,
+        VertexAttribute::Weights,
+    ];
+    pub fn get_shader_location(&self) -> u32 {
+        *self as u32
+    }
+    pub fn get_format(&self) -> wgpu::VertexFormat {
+        match self {
+            VertexAttribute::Position | VertexAttribute::Normal => wgpu::VertexFormat::Float32x3,
+            VertexAttribute::TexCoord0 | VertexAttribute::TexCoord1 => {
+                wgpu::VertexFormat::Float32x2
+            }
+            VertexAttribute::Tangent | VertexAttribute::Color | VertexAttribute::Weights => {
+                wgpu::VertexFormat::Float32x4
+            }
+            VertexAttribute::Joints => wgpu::VertexFormat::Uint16x4,
+        }
+    }
+    pub fn get_size(&self) -> u64 {
+        self.get_format().size()
+    }
+    fn get_bytes<'a>(&self, vertex: &'a Vertex) -> &'a [u8] {
+        match self {
+            VertexAttribute::Position => bytemuck::bytes_of(&vertex.pos),
+            VertexAttribute::Normal => bytemuck::bytes_of(&vertex.normal),
+            VertexAttribute::TexCoord0 => bytemuck::bytes_of(&vertex.tex_coord),
+            VertexAttribute::TexCoord1 => bytemuck::bytes_of(&vertex.tex_coord1),
+            VertexAttribute::Tangent => bytemuck::bytes_of(&vertex.tangent),
+            VertexAttribute::Color => bytemuck::bytes_of(&vertex.color),
+            VertexAttribute::Joints => bytemuck::bytes_of(&vertex.joints),
+            VertexAttribute::Weights => bytemuck::bytes_of(&vertex.weights),
+        }
+    }
+}
+
+// which attributes of a mesh are uploaded and whether they share one buffer (interleaved)
+// or get a vertex buffer each, in the order of their shader locations
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexLayout {
+    attributes: Vec<VertexAttribute>,
+    interleaved: bool,
+}
+
+impl Default for VertexLayout {
+    fn default() -> Self {
+        VertexLayout::new(&VertexAttribute::ALL, true)
+    }
+}
+
+impl VertexLayout {
+    pub fn new(attributes: &[VertexAttribute], interleaved: bool) -> VertexLayout {
+        let mut attributes = attributes.to_vec();
+        attributes.sort_by_key(|a| a.get_shader_location());
+        attributes.dedup();
+        VertexLayout {
+            attributes,
+            interleaved,
+        }
+    }
+    pub fn get_attributes(&self) -> &[VertexAttribute] {
+        &self.attributes
+    }
+    pub fn is_interleaved(&self) -> bool {
+        self.interleaved
+    }
+    // number of vertex buffers a mesh with this layout binds, starting from slot 0
+    pub fn get_stream_count(&self) -> u32 {
+        if self.interleaved {
+            1
+        } else {
+            self.attributes.len() as u32
+        }
+    }
+    // stride of the first vertex buffer when the layout has positions, they always come
+    // first in it since they use location 0
+    pub fn get_position_stride(&self) -> Option<u64> {
+        if self.attributes.first() != Some(&VertexAttribute::Position) {
+            return None;
+        }
+        Some(self.get_streams()[0].iter().map(|a| a.get_size()).sum())
+    }
+    fn get_streams(&self) -> Vec<&[VertexAttribute]> {
+        if self.interleaved {
+            vec![&self.attributes]
+        } else {
+            self.attributes.chunks(1).collect()
+        }
+    }
+    // one layout per stream, ready for RenderPipelineBuilder::new_vertex_buffer
+    pub fn get_vertex_buffer_layouts(&self) -> Vec<VertexBufferLayout> {
+        self.get_streams()
+            .into_iter()
+            .map(|stream| {
+                let mut layout = VertexBufferLayout::new();
+                let mut offset = 0;
+                for attribute in stream {
+                    layout.new_attribute(wgpu::VertexAttribute {
+                        format: attribute.get_format(),
+                        offset,
+                        shader_location: attribute.get_shader_location(),
+                    });
+                    offset += attribute.get_size();
+                }
+                layout
+                    .new_array_stride(offset)
+                    .new_step_mode(wgpu::VertexStepMode::Vertex);
+                layout
+            })
+            .collect()
+    }
+    // vertex buffer contents of every stream
+    pub fn pack(&self, vertices: &[Vertex]) -> Vec<Vec<u8>> {
+        self.get_streams()
+            .into_iter()
+            .map(|stream| {
+                let mut data = Vec::new();
+                for vertex in vertices {
+                    for attribute in stream {
+                        data.extend_from_slice(attribute.get_bytes(vertex));
+                    }
+                }
+                data
+            })
+            .collect()
+    }
+}
+
+pub fn get_vertex_buffer_name(mesh_id: &str, stream: u32) -> String {
+    if stream == 0 {
+        format!("{} vertex_buffer", mesh_id)
+    } else {
+        format!("{} vertex_buffer {}", mesh_id, stream)
+    }
 }
 
 pub struct VertexDataBuilder {
     pos: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    tex_coords: Vec<[f32; 2]>,
+    tex_coords1: Vec<[f32; 2]>,
+    tangents: Vec<[f32; 4]>,
+    colors: Vec<[f32; 4]>,
+    joints: Vec<[u16; 4]>,
+    weights: Vec<[f32; 4]>,
     indicies: Vec<u32>,
+    vertex_layout: VertexLayout,
+    normal_mode: Option<NormalMode>,
+    generate_tangents: bool,
 }
 
 impl VertexDataBuilder {
     pub fn new() -> VertexDataBuilder {
         VertexDataBuilder {
             pos: Vec::new(),
+            normals: Vec::new(),
+            tex_coords: Vec::new(),
+            tex_coords1: Vec::new(),
+            tangents: Vec::new(),
+            colors: Vec::new(),
+            joints: Vec::new(),
+            weights: Vec::new(),
             indicies: Vec::new(),
+            vertex_layout: VertexLayout::default(),
+            normal_mode: None,
+            generate_tangents: false,
         }
     }
     pub fn set_vertex_positions(&mut self, pos: &[[f32; 3]]) -> &mut Self {
@@ -24,39 +264,122 @@ impl VertexDataBuilder {
         self
     }
 
+    // vertices without a normal get a zero normal
+    pub fn set_vertex_normals(&mut self, normals: &[[f32; 3]]) -> &mut Self {
+        self.normals = normals.to_vec();
+        self
+    }
+
+    // vertices without a texture coordinate get (0, 0)
+    pub fn set_vertex_tex_coords(&mut self, tex_coords: &[[f32; 2]]) -> &mut Self {
+        self.tex_coords = tex_coords.to_vec();
+        self
+    }
+
+    // second uv set, usually for lightmaps
+    pub fn set_vertex_tex_coords1(&mut self, tex_coords: &[[f32; 2]]) -> &mut Self {
+        self.tex_coords1 = tex_coords.to_vec();
+        self
+    }
+
+    // xyz tangent with the bitangent sign in w, vertices without one get a zero tangent
+    pub fn set_vertex_tangents(&mut self, tangents: &[[f32; 4]]) -> &mut Self {
+        self.tangents = tangents.to_vec();
+        self
+    }
+
+    // vertices without a color are white
+    pub fn set_vertex_colors(&mut self, colors: &[[f32; 4]]) -> &mut Self {
+        self.colors = colors.to_vec();
+        self
+    }
+
+    // indices of the four joints that influence each vertex
+    pub fn set_vertex_joints(&mut self, joints: &[[u16; 4]]) -> &mut Self {
+        self.joints = joints.to_vec();
+        self
+    }
+
+    pub fn set_vertex_weights(&mut self, weights: &[[f32; 4]]) -> &mut Self {
+        self.weights = weights.to_vec();
+        self
+    }
+
     pub fn set_indicies(&mut self, indicies: &[u32]) -> &mut Self {
         self.indicies = indicies.to_vec();
         self
     }
 
-    pub fn build(&self, mesh_id: &str, engine: &mut Engine) -> Mesh {
+    // replaces the normals with ones generated from the triangles, vertices on hard edges are split
+    pub fn set_generate_normals(&mut self, mode: NormalMode) -> &mut Self {
+        self.normal_mode = Some(mode);
+        self
+    }
+
+    // generates tangents for normal mapping after the normals, needs texture coordinates
+    pub fn set_generate_tangents(&mut self, generate: bool) -> &mut Self {
+        self.generate_tangents = generate;
+        self
+    }
+
+    // the engine's pipelines expect the default layout, custom pipelines can use less
+    // attributes or a buffer per attribute
+    pub fn set_vertex_layout(&mut self, vertex_layout: VertexLayout) -> &mut Self {
+        self.vertex_layout = vertex_layout;
+        self
+    }
+
+    // vertices and indices after the generation steps, for meshes that aren't owned by the engine
+    pub fn build_vertex_data(&self) -> (Vec<Vertex>, Vec<u32>) {
         let mut vertex_data: Vec<Vertex> = Vec::new();
-        let mut indicies: Vec<u32> = Vec::new();
         for i in 0..self.pos.len() {
             // vertex position is always used so we will use it as the vertex_count
-            vertex_data.push(Vertex { pos: self.pos[i] });
+            let default = Vertex::default();
+            vertex_data.push(Vertex {
+                pos: self.pos[i],
+                normal: self.normals.get(i).copied().unwrap_or(default.normal),
+                tex_coord: self.tex_coords.get(i).copied().unwrap_or(default.tex_coord),
+                tex_coord1: self
+                    .tex_coords1
+                    .get(i)
+                    .copied()
+                    .unwrap_or(default.tex_coord1),
+                tangent: self.tangents.get(i).copied().unwrap_or(default.tangent),
+                color: self.colors.get(i).copied().unwrap_or(default.color),
+                joints: self.joints.get(i).copied().unwrap_or(default.joints),
+                weights: self.weights.get(i).copied().unwrap_or(default.weights),
+            });
         }
-        for i in 0..self.indicies.len() {
-            indicies.push(self.indicies[i]);
+        let mut indicies = self.indicies.clone();
+        if let Some(mode) = self.normal_mode {
+            (vertex_data, indicies) = generate_normals(&vertex_data, &indicies, mode);
         }
-        if vertex_data.len() > 0 {
-            engine.set_vertex_buffer(mesh_id, bytemuck::cast_slice(&vertex_data));
-        }
-        if indicies.len() > 0 {
-            engine.set_index_buffer(mesh_id, bytemuck::cast_slice(&indicies));
-        }
-        Mesh {
-            id: mesh_id.to_string(),
-            vertex_data: vertex_data,
-            index_data: indicies,
+        if self.generate_tangents {
+            (vertex_data, indicies) = generate_tangents(&vertex_data, &indicies);
         }
+        (vertex_data, indicies)
+    }
+
+    pub fn build(&self, mesh_id: &str, engine: &mut Engine) -> Mesh {
+        let (vertex_data, indicies) = self.build_vertex_data();
+        let mut mesh = Mesh::new(mesh_id);
+        mesh.vertex_layout = self.vertex_layout.clone();
+        mesh.set_vertex_data(&vertex_data);
+        mesh.set_index_data(&indicies);
+        mesh.upload(engine);
+        mesh
     }
 }
 
+#[derive(Clone)]
 pub struct Mesh {
     id: String,
     vertex_data: Vec<Vertex>,
     index_data: Vec<u32>,
+    vertex_layout: VertexLayout,
+    // local space bounds of the vertex positions, updated with the vertex data
+    aabb: Aabb,
+    bounding_sphere: BoundingSphere,
 }
 
 impl Mesh {
@@ -65,6 +388,9 @@ impl Mesh {
             id: id.to_string(),
             vertex_data: Vec::new(),
             index_data: Vec::new(),
+            vertex_layout: VertexLayout::default(),
+            aabb: Aabb::from_points(&[]),
+            bounding_sphere: BoundingSphere::from_points(&[]),
         }
     }
     pub fn get_mesh_id(&self) -> &str {
@@ -78,9 +404,74 @@ impl Mesh {
     }
     pub fn set_vertex_data(&mut self, vertex_data: &[Vertex]) {
         self.vertex_data = vertex_data.to_vec();
+        let positions: Vec<glam::Vec3> = vertex_data.iter().map(|v| v.get_position()).collect();
+        self.aabb = Aabb::from_points(&positions);
+        self.bounding_sphere = BoundingSphere::from_points(&positions);
+    }
+    pub fn get_vertex_layout(&self) -> &VertexLayout {
+        &self.vertex_layout
+    }
+    pub fn get_aabb(&self) -> &Aabb {
+        &self.aabb
+    }
+    pub fn get_bounding_sphere(&self) -> &BoundingSphere {
+        &self.bounding_sphere
     }
 
+    pub fn get_index_data(&self) -> &[u32] {
+        &self.index_data
+    }
     pub fn set_index_data(&mut self, index_data: &[u32]) {
         self.index_data = index_data.to_vec();
     }
+
+    // Welds vertices whose attributes are within weld_epsilon of each other, 0 only welds exact
+    // duplicates, and reorders the triangles and vertices for the GPU caches. Upload the mesh
+    // afterwards to update its buffers
+    pub fn optimize(&mut self, weld_epsilon: f32) {
+        let (vertex_data, index_data) =
+            weld_vertices(&self.vertex_data, &self.index_data, weld_epsilon);
+        let index_data = optimize_vertex_cache(&index_data, vertex_data.len());
+        let (vertex_data, index_data) = optimize_vertex_fetch(&vertex_data, &index_data);
+        self.set_vertex_data(&vertex_data);
+        self.set_index_data(&index_data);
+    }
+
+    // Simplified versions of the mesh aiming for the given triangle counts, from the
+    // most to the least detailed. Each one is uploaded with its own buffers as "<id> lod <n>"
+    // starting at 1, levels that couldn't be simplified any further are left out
+    pub fn create_lods(&self, target_triangle_counts: &[usize], engine: &mut Engine) -> Vec<Mesh> {
+        generate_lods(&self.vertex_data, &self.index_data, target_triangle_counts)
+            .iter()
+            .enumerate()
+            .map(|(level, index_data)| {
+                let index_data = optimize_vertex_cache(index_data, self.vertex_data.len());
+                let (vertex_data, index_data) =
+                    optimize_vertex_fetch(&self.vertex_data, &index_data);
+                let mut lod = Mesh::new(&format!("{} lod {}", self.id, level + 1));
+                lod.vertex_layout = self.vertex_layout.clone();
+                lod.set_vertex_data(&vertex_data);
+                lod.set_index_data(&index_data);
+                lod.upload(engine);
+                lod
+            })
+            .collect()
+    }
+
+    // writes the vertex and index data into the engine's buffers of this mesh
+    pub fn upload(&self, engine: &mut Engine) {
+        if !self.vertex_data.is_empty() {
+            for (stream, data) in self
+                .vertex_layout
+                .pack(&self.vertex_data)
+                .iter()
+                .enumerate()
+            {
+                engine.set_vertex_stream(&self.id, stream as u32, data);
+            }
+        }
+        if !self.index_data.is_empty() {
+            engine.set_index_buffer(&self.id, bytemuck::cast_slice(&self.index_data));
+        }
+    }
 }
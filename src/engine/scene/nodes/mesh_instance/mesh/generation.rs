@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+
+use super::Vertex;
+use crate::engine::scene::utils::Aabb;
+
+// how generated normals treat the edges between triangles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NormalMode {
+    // every triangle around a position is averaged
+    Smooth,
+    // every triangle keeps its own normal
+    Flat,
+    // triangles meeting at a sharper angle than this many degrees get a hard edge
+    Angle(f32),
+}
+
+fn is_triangle_list(vertices: &[Vertex], indices: &[u32]) -> bool {
+    if !indices.len().is_multiple_of(3) {
+        println!("index count isn't a multiple of 3, skipping normal and tangent generation");
+        return false;
+    }
+    if indices
+        .iter()
+        .any(|index| *index as usize >= vertices.len())
+    {
+        println!("index out of range, skipping normal and tangent generation");
+        return false;
+    }
+    true
+}
+
+// corners of the same vertex that ended up with different values are given their own vertex
+fn split_corners(
+    vertices: &[Vertex],
+    indices: &[u32],
+    corner_values: &[[f32; 4]],
+    set_value: fn(&mut Vertex, [f32; 4]),
+) -> (Vec<Vertex>, Vec<u32>) {
+    let mut new_vertices: Vec<Vertex> = Vec::with_capacity(vertices.len());
+    let mut new_indices: Vec<u32> = Vec::with_capacity(indices.len());
+    let mut corner_vertices: HashMap<(u32, [u32; 4]), u32> = HashMap::new();
+    for (index, value) in indices.iter().zip(corner_values) {
+        // adding zero turns -0.0 into 0.0 so both land on the same vertex
+        let key = (*index, value.map(|value| (value + 0.0).to_bits()));
+        let new_index = *corner_vertices.entry(key).or_insert_with(|| {
+            let mut vertex = vertices[*index as usize];
+            set_value(&mut vertex, *value);
+            new_vertices.push(vertex);
+            new_vertices.len() as u32 - 1
+        });
+        new_indices.push(new_index);
+    }
+    (new_vertices, new_indices)
+}
+
+// Angle weighted vertex normals. Corners are smoothed by position, so vertices that were
+// only split for their texture coordinates still get the same normal
+pub fn generate_normals(
+    vertices: &[Vertex],
+    indices: &[u32],
+    mode: NormalMode,
+) -> (Vec<Vertex>, Vec<u32>) {
+    if !is_triangle_list(vertices, indices) {
+        return (vertices.to_vec(), indices.to_vec());
+    }
+    // positions closer than a fraction of the mesh size are welded, so slivers like the ones
+    // around the poles of a sphere become degenerate instead of giving wrong normals
+    let aabb = Aabb::from_points(
+        &vertices
+            .iter()
+            .map(|vertex| vertex.get_position())
+            .collect::<Vec<_>>(),
+    );
+    let cell_size = (aabb.get_extents().max_element() * 2e-5).max(f32::MIN_POSITIVE);
+    let get_key = |position: glam::Vec3| (position / cell_size).round().as_ivec3().to_array();
+    let mut welded_positions: HashMap<[i32; 3], glam::Vec3> = HashMap::new();
+    let positions: Vec<glam::Vec3> = indices
+        .iter()
+        .map(|index| {
+            let position = vertices[*index as usize].get_position();
+            *welded_positions
+                .entry(get_key(position))
+                .or_insert(position)
+        })
+        .collect();
+    // unit normal of every triangle, zero for degenerate ones
+    let face_normals: Vec<glam::Vec3> = positions
+        .chunks_exact(3)
+        .map(|p| (p[1] - p[0]).cross(p[2] - p[0]).normalize_or_zero())
+        .collect();
+    let corner_angle = |corner: usize| {
+        let triangle = corner - corner % 3;
+        let position = positions[corner];
+        let next = positions[triangle + (corner + 1) % 3];
+        let previous = positions[triangle + (corner + 2) % 3];
+        (next - position).angle_between(previous - position)
+    };
+    let mut position_corners: HashMap<[i32; 3], Vec<usize>> = HashMap::new();
+    for (corner, position) in positions.iter().enumerate() {
+        position_corners
+            .entry(get_key(*position))
+            .or_default()
+            .push(corner);
+    }
+    let min_cos = match mode {
+        NormalMode::Smooth => f32::MIN,
+        NormalMode::Flat => f32::MAX,
+        NormalMode::Angle(degrees) => degrees.to_radians().cos(),
+    };
+    let corner_normals: Vec<[f32; 4]> = (0..indices.len())
+        .map(|corner| {
+            let face_normal = face_normals[corner / 3];
+            let mut normal = glam::Vec3::ZERO;
+            if mode == NormalMode::Flat {
+                normal = face_normal;
+            } else {
+                for other in &position_corners[&get_key(positions[corner])] {
+                    let other_normal = face_normals[other / 3];
+                    if other_normal != glam::Vec3::ZERO && face_normal.dot(other_normal) >= min_cos
+                    {
+                        normal += other_normal * corner_angle(*other);
+                    }
+                }
+            }
+            // degenerate triangles keep the normal they had
+            let normal = normal
+                .try_normalize()
+                .unwrap_or_else(|| vertices[indices[corner] as usize].get_normal());
+            normal.extend(0.0).to_array()
+        })
+        .collect();
+    split_corners(vertices, indices, &corner_normals, |vertex, normal| {
+        vertex.normal = [normal[0], normal[1], normal[2]];
+    })
+}
+
+struct TangentGeometry<'a> {
+    vertices: &'a [Vertex],
+    indices: &'a [u32],
+    corner_tangents: Vec<[f32; 4]>,
+}
+
+impl TangentGeometry<'_> {
+    fn get_vertex(&self, face: usize, vert: usize) -> &Vertex {
+        &self.vertices[self.indices[face * 3 + vert] as usize]
+    }
+}
+
+impl bevy_mikktspace::Geometry for TangentGeometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.get_vertex(face, vert).pos
+    }
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.get_vertex(face, vert).normal
+    }
+    // texture coordinates start at the top of the image, flipped so the bitangent points up
+    // the image like in the tangents glTF files carry
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        let [u, v] = self.get_vertex(face, vert).tex_coord;
+        [u, 1.0 - v]
+    }
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.corner_tangents[face * 3 + vert] = tangent;
+    }
+}
+
+// MikkTSpace tangents from the positions, normals and texture coordinates of the vertices,
+// the bitangent is cross(normal, tangent.xyz) * tangent.w
+pub fn generate_tangents(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    if !is_triangle_list(vertices, indices) {
+        return (vertices.to_vec(), indices.to_vec());
+    }
+    let mut geometry = TangentGeometry {
+        vertices,
+        indices,
+        corner_tangents: indices
+            .iter()
+            .map(|index| vertices[*index as usize].tangent)
+            .collect(),
+    };
+    if !bevy_mikktspace::generate_tangents(&mut geometry) {
+        println!("failed to generate tangents");
+        return (vertices.to_vec(), indices.to_vec());
+    }
+    split_corners(
+        vertices,
+        indices,
+        &geometry.corner_tangents,
+        |vertex, tangent| vertex.tangent = tangent,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Vec2, Vec3};
+
+    fn get_vertex(position: Vec3, normal: Vec3, tex_coord: Vec2) -> Vertex {
+        Vertex {
+            pos: position.to_array(),
+            normal: normal.to_array(),
+            tex_coord: tex_coord.to_array(),
+            ..Default::default()
+        }
+    }
+
+    // grid of (subdivisions + 1)^2 vertices facing +z with u along +x and v = 0 at +y
+    fn get_plane(subdivisions: u32) -> (Vec<Vertex>, Vec<u32>) {
+        let row_size = subdivisions + 1;
+        let mut vertices = Vec::new();
+        for row in 0..row_size {
+            for column in 0..row_size {
+                let tex_coord = Vec2::new(column as f32, row as f32) / subdivisions as f32;
+                let position = Vec3::new(tex_coord.x - 0.5, 0.5 - tex_coord.y, 0.0);
+                vertices.push(get_vertex(position, Vec3::Z, tex_coord));
+            }
+        }
+        let mut indices = Vec::new();
+        for row in 0..subdivisions {
+            for column in 0..subdivisions {
+                let top_left = row * row_size + column;
+                let bottom_left = top_left + row_size;
+                indices.extend([top_left, bottom_left, top_left + 1]);
+                indices.extend([top_left + 1, bottom_left, bottom_left + 1]);
+            }
+        }
+        (vertices, indices)
+    }
+
+    // unit cube centered on the origin, every face has its own four vertices with zero normals
+    fn get_cube() -> (Vec<Vertex>, Vec<u32>) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for (normal, up) in [
+            (Vec3::X, Vec3::Z),
+            (Vec3::NEG_X, Vec3::Z),
+            (Vec3::Y, Vec3::Z),
+            (Vec3::NEG_Y, Vec3::Z),
+            (Vec3::Z, Vec3::Y),
+            (Vec3::NEG_Z, Vec3::NEG_Y),
+        ] {
+            let right = up.cross(normal);
+            let first = vertices.len() as u32;
+            for (x, y) in [(-1.0, 1.0), (1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)] {
+                let position = (normal + right * x + up * y) * 0.5;
+                let tex_coord = Vec2::new(x + 1.0, 1.0 - y) * 0.5;
+                vertices.push(get_vertex(position, Vec3::ZERO, tex_coord));
+            }
+            indices.extend([first, first + 2, first + 1, first + 1, first + 2, first + 3]);
+        }
+        (vertices, indices)
+    }
+
+    fn assert_normals(vertices: &[Vertex], expected: impl Fn(&Vertex) -> Vec3) {
+        for vertex in vertices {
+            let normal = vertex.get_normal();
+            assert!(
+                normal.abs_diff_eq(expected(vertex), 1e-5),
+                "normal {normal} at {}",
+                vertex.get_position()
+            );
+        }
+    }
+
+    #[test]
+    fn flat_normals_of_a_cube_are_the_face_normals() {
+        let (vertices, indices) = get_cube();
+        let (new_vertices, new_indices) = generate_normals(&vertices, &indices, NormalMode::Flat);
+        // both triangles of a face share its vertices
+        assert_eq!(new_vertices.len(), 24);
+        assert_eq!(new_indices.len(), indices.len());
+        for triangle in new_indices.chunks_exact(3) {
+            let p: Vec<Vec3> = triangle
+                .iter()
+                .map(|index| new_vertices[*index as usize].get_position())
+                .collect();
+            let face_normal = (p[1] - p[0]).cross(p[2] - p[0]).normalize();
+            // every face of a cube is axis aligned and faces outwards
+            assert_eq!(face_normal.abs().max_element(), 1.0);
+            assert!(face_normal.dot(p[0]) > 0.0);
+            for index in triangle {
+                assert!(new_vertices[*index as usize]
+                    .get_normal()
+                    .abs_diff_eq(face_normal, 1e-6));
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_normals_of_a_cube_point_away_from_the_center() {
+        let (vertices, indices) = get_cube();
+        let (new_vertices, _) = generate_normals(&vertices, &indices, NormalMode::Smooth);
+        // corners split for their texture coordinates still share a normal
+        assert_eq!(new_vertices.len(), vertices.len());
+        assert_normals(&new_vertices, |vertex| vertex.get_position().normalize());
+    }
+
+    #[test]
+    fn angle_threshold_keeps_edges_sharper_than_it() {
+        let (vertices, indices) = get_cube();
+        let (flat_vertices, flat_indices) = generate_normals(&vertices, &indices, NormalMode::Flat);
+        // the faces of a cube meet at 90 degrees
+        let (hard_vertices, hard_indices) =
+            generate_normals(&vertices, &indices, NormalMode::Angle(60.0));
+        assert_eq!(hard_indices, flat_indices);
+        assert_eq!(hard_vertices, flat_vertices);
+        let (soft_vertices, _) = generate_normals(&vertices, &indices, NormalMode::Angle(120.0));
+        assert_normals(&soft_vertices, |vertex| vertex.get_position().normalize());
+    }
+
+    #[test]
+    fn invalid_index_lists_are_left_alone() {
+        let (vertices, indices) = get_cube();
+        let (new_vertices, new_indices) =
+            generate_normals(&vertices, &indices[..4], NormalMode::Flat);
+        assert_eq!(new_vertices, vertices);
+        assert_eq!(new_indices, &indices[..4]);
+        let (_, new_indices) = generate_tangents(&vertices, &[0, 1, vertices.len() as u32]);
+        assert_eq!(new_indices, [0, 1, vertices.len() as u32]);
+    }
+
+    #[test]
+    fn tangents_of_a_plane_follow_the_texture_coordinates() {
+        let (vertices, indices) = get_plane(2);
+        let (new_vertices, new_indices) = generate_tangents(&vertices, &indices);
+        assert_eq!(new_vertices.len(), vertices.len());
+        assert_eq!(new_indices.len(), indices.len());
+        for vertex in &new_vertices {
+            // u goes along +X and v = 0 is at +Y, so the bitangent is +Y
+            let tangent = vertex.get_tangent();
+            assert!(tangent.abs_diff_eq(glam::Vec4::new(1.0, 0.0, 0.0, 1.0), 1e-5));
+            let bitangent = vertex.get_normal().cross(tangent.truncate()) * tangent.w;
+            assert!(bitangent.abs_diff_eq(Vec3::Y, 1e-5));
+        }
+    }
+
+    #[test]
+    fn tangents_are_unit_length_and_orthogonal_to_the_normals() {
+        let (vertices, indices) = get_cube();
+        let (vertices, indices) = generate_normals(&vertices, &indices, NormalMode::Flat);
+        let (new_vertices, _) = generate_tangents(&vertices, &indices);
+        for vertex in &new_vertices {
+            let tangent = vertex.get_tangent();
+            assert!((tangent.truncate().length() - 1.0).abs() < 1e-5);
+            assert!(tangent.truncate().dot(vertex.get_normal()).abs() < 1e-5);
+            assert_eq!(tangent.w.abs(), 1.0);
+        }
+    }
+}
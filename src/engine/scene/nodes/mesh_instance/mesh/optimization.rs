@@ -0,0 +1,685 @@
+use std::collections::{HashMap, HashSet};
+
+use super::Vertex;
+use crate::engine::scene::utils::Aabb;
+
+// Same key for values that round to the same multiple of epsilon, an epsilon of 0 only
+// matches the exact same value. -0.0 and 0.0 always get the same key. Values on either side
+// of a rounding boundary get different keys even when they are closer than epsilon
+pub fn get_weld_key(value: f32, epsilon: f32) -> i64 {
+    if epsilon > 0.0 {
+        (value / epsilon).round() as i64
+    } else {
+        // adding 0 turns -0.0 into 0.0
+        (value + 0.0).to_bits() as i64
+    }
+}
+
+fn get_vertex_weld_key(vertex: &Vertex, epsilon: f32) -> Vec<i64> {
+    let floats = [
+        vertex.get_position().to_array().as_slice(),
+        &vertex.get_normal().to_array(),
+        &vertex.get_tex_coord().to_array(),
+        &vertex.get_tex_coord1().to_array(),
+        &vertex.get_tangent().to_array(),
+        &vertex.get_color().to_array(),
+        &vertex.get_weights().to_array(),
+    ]
+    .concat();
+    floats
+        .iter()
+        .map(|value| get_weld_key(*value, epsilon))
+        .chain(vertex.get_joints().map(i64::from))
+        .collect()
+}
+
+// Merges vertices whose attributes all round to the same multiples of epsilon, which importers
+// and generators often leave behind. The first vertex of every group is kept as it is.
+// Vertices no triangle uses are dropped
+pub fn weld_vertices(
+    vertices: &[Vertex],
+    indices: &[u32],
+    epsilon: f32,
+) -> (Vec<Vertex>, Vec<u32>) {
+    let mut new_vertices: Vec<Vertex> = Vec::with_capacity(vertices.len());
+    let mut welded: HashMap<Vec<i64>, u32> = HashMap::new();
+    let mut new_indices: Vec<u32> = Vec::with_capacity(indices.len());
+    for index in indices {
+        let Some(vertex) = vertices.get(*index as usize) else {
+            println!("index out of range, skipping vertex welding");
+            return (vertices.to_vec(), indices.to_vec());
+        };
+        let new_index = *welded
+            .entry(get_vertex_weld_key(vertex, epsilon))
+            .or_insert_with(|| {
+                new_vertices.push(*vertex);
+                new_vertices.len() as u32 - 1
+            });
+        new_indices.push(new_index);
+    }
+    (new_vertices, new_indices)
+}
+
+// Average number of vertices the GPU has to transform per triangle with a FIFO cache of the
+// given size, 0.5 is about the best a regular grid can get and 3 means nothing is reused
+pub fn get_average_cache_miss_ratio(
+    indices: &[u32],
+    vertex_count: usize,
+    cache_size: usize,
+) -> f32 {
+    if indices.len() < 3 {
+        return 0.0;
+    }
+    let mut cache: std::collections::VecDeque<u32> = std::collections::VecDeque::new();
+    let mut in_cache = vec![false; vertex_count];
+    let mut misses = 0;
+    for index in indices {
+        if in_cache.get(*index as usize).copied().unwrap_or(true) {
+            continue;
+        }
+        misses += 1;
+        cache.push_back(*index);
+        in_cache[*index as usize] = true;
+        if cache.len() > cache_size {
+            if let Some(evicted) = cache.pop_front() {
+                in_cache[evicted as usize] = false;
+            }
+        }
+    }
+    misses as f32 / (indices.len() / 3) as f32
+}
+
+const CACHE_SIZE: usize = 32;
+
+// how much a vertex is worth drawing next, from its position in the simulated cache and how
+// many of its triangles are left
+fn get_vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.0;
+    }
+    let cache_score = match cache_position {
+        // the triangle that was just drawn gets a fixed score so it doesn't win every time
+        Some(position) if position < 3 => 0.75,
+        Some(position) => {
+            let falloff = 1.0 - (position - 3) as f32 / (CACHE_SIZE - 3) as f32;
+            falloff.powf(1.5)
+        }
+        None => 0.0,
+    };
+    // vertices with few triangles left are finished first so they don't linger
+    cache_score + 2.0 * (remaining_triangles as f32).powf(-0.5)
+}
+
+// Reorders the triangles so vertices are reused while they're still in the post-transform
+// cache, using Tom Forsyth's linear-speed algorithm. The vertices stay where they are
+pub fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    if !indices.len().is_multiple_of(3)
+        || indices.iter().any(|index| *index as usize >= vertex_count)
+    {
+        println!("indices aren't a valid triangle list, skipping vertex cache optimization");
+        return indices.to_vec();
+    }
+    let triangle_count = indices.len() / 3;
+    let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+    for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+        for index in corners {
+            vertex_triangles[*index as usize].push(triangle);
+        }
+    }
+    let mut cache_positions: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vertex_scores: Vec<f32> = vertex_triangles
+        .iter()
+        .map(|triangles| get_vertex_score(None, triangles.len()))
+        .collect();
+    let mut triangle_scores: Vec<f32> = indices
+        .chunks_exact(3)
+        .map(|corners| corners.iter().map(|i| vertex_scores[*i as usize]).sum())
+        .collect();
+    let mut emitted = vec![false; triangle_count];
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+    let mut new_indices: Vec<u32> = Vec::with_capacity(indices.len());
+    // used when nothing in the cache has triangles left
+    let mut next_unemitted = 0;
+
+    let mut best_triangle = triangle_scores
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(triangle, _)| triangle);
+    while let Some(triangle) = best_triangle {
+        emitted[triangle] = true;
+        let corners = &indices[triangle * 3..triangle * 3 + 3];
+        new_indices.extend_from_slice(corners);
+        for index in corners {
+            let triangles = &mut vertex_triangles[*index as usize];
+            triangles.retain(|t| *t != triangle);
+        }
+        // the drawn vertices move to the front of the cache, the oldest ones fall out
+        cache.retain(|index| !corners.contains(index));
+        cache.splice(0..0, corners.iter().copied());
+        let evicted: Vec<u32> = cache.drain(CACHE_SIZE.min(cache.len())..).collect();
+        for index in &evicted {
+            cache_positions[*index as usize] = None;
+        }
+        for (position, index) in cache.iter().enumerate() {
+            cache_positions[*index as usize] = Some(position);
+        }
+
+        let mut touched: Vec<usize> = Vec::new();
+        for index in cache.iter().chain(&evicted) {
+            let vertex = *index as usize;
+            vertex_scores[vertex] =
+                get_vertex_score(cache_positions[vertex], vertex_triangles[vertex].len());
+            touched.extend(vertex_triangles[vertex].iter().copied());
+        }
+        // sorted so ties always go the same way
+        touched.sort_unstable();
+        touched.dedup();
+        best_triangle = None;
+        let mut best_score = f32::MIN;
+        for touched_triangle in touched {
+            let score = indices[touched_triangle * 3..touched_triangle * 3 + 3]
+                .iter()
+                .map(|i| vertex_scores[*i as usize])
+                .sum();
+            triangle_scores[touched_triangle] = score;
+            if score > best_score {
+                best_score = score;
+                best_triangle = Some(touched_triangle);
+            }
+        }
+        if best_triangle.is_none() {
+            while next_unemitted < triangle_count && emitted[next_unemitted] {
+                next_unemitted += 1;
+            }
+            best_triangle = (next_unemitted < triangle_count).then_some(next_unemitted);
+        }
+    }
+    new_indices
+}
+
+// Reorders the vertices by when the indices first use them, so the vertex fetch reads memory
+// mostly in order. Run it after optimize_vertex_cache. Vertices no triangle uses are dropped
+pub fn optimize_vertex_fetch(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    if indices
+        .iter()
+        .any(|index| *index as usize >= vertices.len())
+    {
+        println!("index out of range, skipping vertex fetch optimization");
+        return (vertices.to_vec(), indices.to_vec());
+    }
+    let mut remap: Vec<Option<u32>> = vec![None; vertices.len()];
+    let mut new_vertices: Vec<Vertex> = Vec::with_capacity(vertices.len());
+    let new_indices = indices
+        .iter()
+        .map(|index| {
+            *remap[*index as usize].get_or_insert_with(|| {
+                new_vertices.push(vertices[*index as usize]);
+                new_vertices.len() as u32 - 1
+            })
+        })
+        .collect();
+    (new_vertices, new_indices)
+}
+
+// symmetric 4x4 matrix of the summed squared distances to a set of planes
+#[derive(Clone, Copy, Default)]
+struct Quadric {
+    values: [f64; 10],
+}
+
+impl Quadric {
+    fn from_plane(normal: glam::DVec3, distance: f64, weight: f64) -> Quadric {
+        let (a, b, c, d) = (normal.x, normal.y, normal.z, distance);
+        Quadric {
+            values: [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|value| value * weight),
+        }
+    }
+    fn add(&mut self, other: &Quadric) {
+        for (value, other) in self.values.iter_mut().zip(other.values) {
+            *value += other;
+        }
+    }
+    fn get_error(&self, point: glam::DVec3) -> f64 {
+        let [aa, ab, ac, ad, bb, bc, bd, cc, cd, dd] = self.values;
+        let (x, y, z) = (point.x, point.y, point.z);
+        (aa * x * x + 2.0 * ab * x * y + 2.0 * ac * x * z + 2.0 * ad * x)
+            + (bb * y * y + 2.0 * bc * y * z + 2.0 * bd * y)
+            + (cc * z * z + 2.0 * cd * z)
+            + dd
+    }
+}
+
+// borders are kept in place by planes through them that stand on the triangle, weighted so
+// they outweigh the triangles around them
+const BORDER_WEIGHT: f64 = 10.0;
+
+// Reduces the triangle count towards the target by collapsing the edges that change the surface
+// the least, measured with quadric error metrics. Vertices only move onto other vertices, so
+// the returned indices use the same vertex data. Open borders and texture seams are kept
+// in shape. The result can stay above the target when no collapse is left that keeps the mesh
+// intact
+pub fn simplify(vertices: &[Vertex], indices: &[u32], target_triangle_count: usize) -> Vec<u32> {
+    if !indices.len().is_multiple_of(3)
+        || indices
+            .iter()
+            .any(|index| *index as usize >= vertices.len())
+    {
+        println!("indices aren't a valid triangle list, skipping simplification");
+        return indices.to_vec();
+    }
+    let positions: Vec<glam::DVec3> = vertices
+        .iter()
+        .map(|vertex| vertex.get_position().as_dvec3())
+        .collect();
+    // vertices split for their normals or texture coordinates collapse together, every vertex
+    // points at the first one with its position. Positions are welded like for generated
+    // normals, so seams that don't line up exactly aren't taken for borders
+    let aabb = Aabb::from_points(
+        &vertices
+            .iter()
+            .map(|vertex| vertex.get_position())
+            .collect::<Vec<_>>(),
+    );
+    let cell_size = (aabb.get_extents().max_element() * 2e-5).max(f32::MIN_POSITIVE);
+    let mut first_vertices: HashMap<[i64; 3], u32> = HashMap::new();
+    let position_ids: Vec<u32> = vertices
+        .iter()
+        .enumerate()
+        .map(|(index, vertex)| {
+            let key = vertex
+                .get_position()
+                .to_array()
+                .map(|value| get_weld_key(value, cell_size));
+            *first_vertices.entry(key).or_insert(index as u32)
+        })
+        .collect();
+    let mut position_vertices: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (index, position_id) in position_ids.iter().enumerate() {
+        position_vertices
+            .entry(*position_id)
+            .or_default()
+            .push(index as u32);
+    }
+
+    let mut quadrics = vec![Quadric::default(); vertices.len()];
+    let mut edge_counts: HashMap<(u32, u32), u32> = HashMap::new();
+    for corners in indices.chunks_exact(3) {
+        let [p0, p1, p2] = [0, 1, 2].map(|i| positions[corners[i] as usize]);
+        let cross = (p1 - p0).cross(p2 - p0);
+        let area = cross.length() * 0.5;
+        let Some(normal) = cross.try_normalize() else {
+            continue;
+        };
+        let quadric = Quadric::from_plane(normal, -normal.dot(p0), area);
+        for i in 0..3 {
+            quadrics[position_ids[corners[i] as usize] as usize].add(&quadric);
+            let a = position_ids[corners[i] as usize];
+            let b = position_ids[corners[(i + 1) % 3] as usize];
+            *edge_counts.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+    }
+    // edges only one triangle uses are on a border
+    for corners in indices.chunks_exact(3) {
+        let [p0, p1, p2] = [0, 1, 2].map(|i| positions[corners[i] as usize]);
+        let Some(normal) = (p1 - p0).cross(p2 - p0).try_normalize() else {
+            continue;
+        };
+        for i in 0..3 {
+            let a = position_ids[corners[i] as usize];
+            let b = position_ids[corners[(i + 1) % 3] as usize];
+            if edge_counts.get(&(a.min(b), a.max(b))) != Some(&1) {
+                continue;
+            }
+            let edge = positions[b as usize] - positions[a as usize];
+            let Some(border_normal) = edge.cross(normal).try_normalize() else {
+                continue;
+            };
+            let quadric = Quadric::from_plane(
+                border_normal,
+                -border_normal.dot(positions[a as usize]),
+                edge.length_squared() * BORDER_WEIGHT,
+            );
+            quadrics[a as usize].add(&quadric);
+            quadrics[b as usize].add(&quadric);
+        }
+    }
+    let border_edges: HashSet<(u32, u32)> = edge_counts
+        .iter()
+        .filter(|(_, count)| **count == 1)
+        .map(|(edge, _)| *edge)
+        .collect();
+    let border_positions: HashSet<u32> = border_edges.iter().flat_map(|(a, b)| [*a, *b]).collect();
+
+    let mut indices = indices.to_vec();
+    loop {
+        let triangle_count = indices.len() / 3;
+        if triangle_count <= target_triangle_count {
+            break;
+        }
+        // vertices connected by an edge, used to find where seam vertices collapse to
+        let mut vertex_neighbours: HashMap<u32, HashSet<u32>> = HashMap::new();
+        let mut position_triangles: HashMap<u32, Vec<usize>> = HashMap::new();
+        let mut position_neighbours: HashMap<u32, HashSet<u32>> = HashMap::new();
+        let mut edges: HashSet<(u32, u32)> = HashSet::new();
+        for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+            for i in 0..3 {
+                let (a, b) = (corners[i], corners[(i + 1) % 3]);
+                vertex_neighbours.entry(a).or_default().insert(b);
+                vertex_neighbours.entry(b).or_default().insert(a);
+                let (pa, pb) = (position_ids[a as usize], position_ids[b as usize]);
+                edges.insert((pa.min(pb), pa.max(pb)));
+                position_neighbours.entry(pa).or_default().insert(pb);
+                position_neighbours.entry(pb).or_default().insert(pa);
+                let triangles = position_triangles.entry(pa).or_default();
+                if triangles.last() != Some(&triangle) {
+                    triangles.push(triangle);
+                }
+            }
+        }
+
+        // every edge can collapse either way, the cheaper direction is kept
+        let mut collapses: Vec<(f64, u32, u32)> = Vec::new();
+        for (a, b) in edges {
+            let mut candidates = Vec::with_capacity(2);
+            for (from, to) in [(a, b), (b, a)] {
+                // border vertices may only slide along the border
+                if border_positions.contains(&from) && !border_edges.contains(&(a, b)) {
+                    continue;
+                }
+                let mut quadric = quadrics[from as usize];
+                quadric.add(&quadrics[to as usize]);
+                candidates.push((quadric.get_error(positions[to as usize]), from, to));
+            }
+            if let Some(candidate) = candidates.into_iter().min_by(|x, y| x.0.total_cmp(&y.0)) {
+                collapses.push(candidate);
+            }
+        }
+        // ties are broken by the vertices so the result is the same on every run
+        collapses.sort_by(|x, y| x.0.total_cmp(&y.0).then((x.1, x.2).cmp(&(y.1, y.2))));
+
+        let mut remap: Vec<u32> = (0..vertices.len() as u32).collect();
+        // positions around a collapse can't change again in the same pass, otherwise the
+        // flip test would see outdated triangles
+        let mut locked: HashSet<u32> = HashSet::new();
+        // each collapse removes about two triangles
+        let mut removed_triangles = 0;
+        for (_, from, to) in collapses {
+            if triangle_count - removed_triangles <= target_triangle_count {
+                break;
+            }
+            if locked.contains(&from) || locked.contains(&to) {
+                continue;
+            }
+            // every vertex on the collapsing position needs a vertex on the target position it's
+            // connected to, so texture seams collapse along themselves
+            let Some(vertex_targets) = position_vertices[&from]
+                .iter()
+                .filter(|vertex| vertex_neighbours.contains_key(vertex))
+                .map(|vertex| {
+                    let target = vertex_neighbours[vertex]
+                        .iter()
+                        .filter(|neighbour| position_ids[**neighbour as usize] == to)
+                        .min()?;
+                    Some((*vertex, *target))
+                })
+                .collect::<Option<Vec<(u32, u32)>>>()
+            else {
+                continue;
+            };
+            // the positions both ends are connected to have to be the ones of the triangles on
+            // the edge, otherwise the collapse would fold the surface onto itself
+            let edge_triangles = position_triangles[&from]
+                .iter()
+                .filter(|triangle| {
+                    indices[*triangle * 3..*triangle * 3 + 3]
+                        .iter()
+                        .any(|index| position_ids[*index as usize] == to)
+                })
+                .count();
+            let shared_neighbours = position_neighbours[&from]
+                .intersection(&position_neighbours[&to])
+                .count();
+            if shared_neighbours != edge_triangles {
+                continue;
+            }
+            // triangles that keep their area mustn't flip over
+            let target_position = positions[to as usize];
+            let flips = position_triangles[&from].iter().any(|triangle| {
+                let corners = [0, 1, 2].map(|i| indices[triangle * 3 + i]);
+                let ids = corners.map(|index| position_ids[index as usize]);
+                if ids.contains(&to) {
+                    return false;
+                }
+                let before = corners.map(|index| positions[index as usize]);
+                let after = ids.map(|id| {
+                    if id == from {
+                        target_position
+                    } else {
+                        positions[id as usize]
+                    }
+                });
+                let normal_before = (before[1] - before[0]).cross(before[2] - before[0]);
+                let normal_after = (after[1] - after[0]).cross(after[2] - after[0]);
+                normal_before.dot(normal_after) <= 0.0
+                    || normal_before
+                        .normalize_or_zero()
+                        .dot(normal_after.normalize_or_zero())
+                        < 0.25
+            });
+            if flips {
+                continue;
+            }
+            for (vertex, target) in vertex_targets {
+                remap[vertex as usize] = target;
+            }
+            let from_quadric = quadrics[from as usize];
+            quadrics[to as usize].add(&from_quadric);
+            for triangle in &position_triangles[&from] {
+                for index in &indices[triangle * 3..triangle * 3 + 3] {
+                    locked.insert(position_ids[*index as usize]);
+                }
+            }
+            removed_triangles += edge_triangles;
+        }
+        if removed_triangles == 0 {
+            break;
+        }
+        // collapsed vertices now share a position with another corner of their triangles
+        indices = indices
+            .chunks_exact(3)
+            .map(|corners| [0, 1, 2].map(|i| remap[corners[i] as usize]))
+            .filter(|corners| {
+                let ids = corners.map(|index| position_ids[index as usize]);
+                ids[0] != ids[1] && ids[1] != ids[2] && ids[2] != ids[0]
+            })
+            .flatten()
+            .collect();
+        // the collapsed positions are gone, their vertices point at the targets from now on
+        for vertices in position_vertices.values_mut() {
+            vertices.retain(|vertex| remap[*vertex as usize] == *vertex);
+        }
+    }
+    indices
+}
+
+// Indices of every level of detail, each simplified from the one before it. Levels that
+// couldn't get below the previous one's triangle count are left out
+pub fn generate_lods(
+    vertices: &[Vertex],
+    indices: &[u32],
+    target_triangle_counts: &[usize],
+) -> Vec<Vec<u32>> {
+    let mut lods: Vec<Vec<u32>> = Vec::new();
+    for target in target_triangle_counts {
+        let previous = lods.last().map(Vec::as_slice).unwrap_or(indices);
+        let lod = simplify(vertices, previous, *target);
+        if lod.len() < previous.len() {
+            lods.push(lod);
+        }
+    }
+    lods
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::primitives::{create_plane, create_uv_sphere};
+    use super::*;
+    use glam::{Vec2, Vec3};
+
+    fn get_vertex(position: [f32; 3]) -> Vertex {
+        Vertex {
+            pos: position,
+            normal: [0.0, 0.0, 1.0],
+            ..Default::default()
+        }
+    }
+
+    // 16 x 16 cells, 512 triangles
+    fn get_plane() -> (Vec<Vertex>, Vec<u32>) {
+        create_plane(Vec2::ONE, 15).build_vertex_data()
+    }
+
+    fn get_triangle_count(indices: &[u32]) -> usize {
+        indices.len() / 3
+    }
+
+    #[test]
+    fn welding_a_duplicated_quad_leaves_four_vertices() {
+        // two triangles that don't share vertices, one corner has -0.0 and one is a tiny bit off
+        let vertices = [
+            get_vertex([0.0, 0.0, 0.0]),
+            get_vertex([1.0, 0.0, 0.0]),
+            get_vertex([1.0, 1.0, 0.0]),
+            get_vertex([-0.0, 0.0, 0.0]),
+            get_vertex([1.0, 1.0 + 1e-7, 0.0]),
+            get_vertex([0.0, 1.0, 0.0]),
+        ];
+        let indices = [0, 1, 2, 3, 4, 5];
+        let (welded, welded_indices) = weld_vertices(&vertices, &indices, 1e-5);
+        assert_eq!(welded.len(), 4);
+        assert_eq!(welded_indices, vec![0, 1, 2, 0, 2, 3]);
+        // an epsilon of 0 still welds -0.0, but not the corner that is off
+        let (welded, _) = weld_vertices(&vertices, &indices, 0.0);
+        assert_eq!(welded.len(), 5);
+    }
+
+    #[test]
+    fn welding_keeps_vertices_with_different_attributes() {
+        let mut vertices = [
+            get_vertex([0.0; 3]),
+            get_vertex([1.0, 0.0, 0.0]),
+            get_vertex([0.0, 1.0, 0.0]),
+        ]
+        .repeat(2);
+        vertices[3].tex_coord = [0.5, 0.0];
+        let (welded, _) = weld_vertices(&vertices, &[0, 1, 2, 3, 4, 5], 1e-5);
+        assert_eq!(welded.len(), 4);
+    }
+
+    #[test]
+    fn cache_optimization_does_not_increase_cache_misses() {
+        let (vertices, indices) = get_plane();
+        // scatter the triangles so the original order doesn't reuse anything
+        let triangle_count = get_triangle_count(&indices);
+        let shuffled: Vec<u32> = (0..triangle_count)
+            .flat_map(|i| {
+                let triangle = (i * 7919) % triangle_count;
+                indices[triangle * 3..triangle * 3 + 3].to_vec()
+            })
+            .collect();
+        for indices in [indices, shuffled] {
+            let optimized = optimize_vertex_cache(&indices, vertices.len());
+            assert_eq!(optimized.len(), indices.len());
+            let before = get_average_cache_miss_ratio(&indices, vertices.len(), CACHE_SIZE);
+            let after = get_average_cache_miss_ratio(&optimized, vertices.len(), CACHE_SIZE);
+            assert!(after <= before, "{after} > {before}");
+        }
+    }
+
+    #[test]
+    fn fetch_optimization_orders_vertices_by_first_use() {
+        let (vertices, indices) = get_plane();
+        let reversed: Vec<u32> = indices.iter().rev().copied().collect();
+        let (new_vertices, new_indices) = optimize_vertex_fetch(&vertices, &reversed);
+        assert_eq!(new_vertices.len(), vertices.len());
+        let mut next = 0;
+        for (index, new_index) in reversed.iter().zip(&new_indices) {
+            assert_eq!(new_vertices[*new_index as usize], vertices[*index as usize]);
+            assert!(*new_index <= next);
+            if *new_index == next {
+                next += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn simplification_reaches_the_target_and_keeps_the_border() {
+        let (vertices, indices) = get_plane();
+        let target = 100;
+        let simplified = simplify(&vertices, &indices, target);
+        assert!(get_triangle_count(&simplified) <= target);
+
+        let positions: Vec<Vec3> = vertices.iter().map(Vertex::get_position).collect();
+        // the plane is flat, so the area only stays the same when the outline does
+        let area: f32 = simplified
+            .chunks_exact(3)
+            .map(|corners| {
+                let [p0, p1, p2] = [0, 1, 2].map(|i| positions[corners[i] as usize]);
+                (p1 - p0).cross(p2 - p0).z * 0.5
+            })
+            .sum();
+        assert!((area - 1.0).abs() < 1e-4, "area is {area}");
+        for corner in [
+            Vec3::new(-0.5, -0.5, 0.0),
+            Vec3::new(0.5, -0.5, 0.0),
+            Vec3::new(-0.5, 0.5, 0.0),
+            Vec3::new(0.5, 0.5, 0.0),
+        ] {
+            assert!(simplified
+                .iter()
+                .any(|index| positions[*index as usize] == corner));
+        }
+        // vertices on the border only slide along it
+        let mut edge_counts: HashMap<(u32, u32), u32> = HashMap::new();
+        for corners in simplified.chunks_exact(3) {
+            for i in 0..3 {
+                let (a, b) = (corners[i], corners[(i + 1) % 3]);
+                *edge_counts.entry((a.min(b), a.max(b))).or_default() += 1;
+            }
+        }
+        let is_on_border = |position: Vec3| position.x.abs() == 0.5 || position.y.abs() == 0.5;
+        for ((a, b), count) in edge_counts {
+            if count == 1 {
+                assert!(is_on_border(positions[a as usize]) && is_on_border(positions[b as usize]));
+            }
+        }
+    }
+
+    #[test]
+    fn lods_have_fewer_triangles_than_the_level_before() {
+        let (vertices, indices) = create_uv_sphere(1.0, 16, 32).build_vertex_data();
+        let targets = [400, 200, 100, 50];
+        let lods = generate_lods(&vertices, &indices, &targets);
+        assert_eq!(lods.len(), targets.len());
+        let mut previous = get_triangle_count(&indices);
+        for (lod, target) in lods.iter().zip(targets) {
+            let triangle_count = get_triangle_count(lod);
+            assert!(triangle_count < previous);
+            assert!(triangle_count <= target, "{triangle_count} > {target}");
+            previous = triangle_count;
+        }
+    }
+}
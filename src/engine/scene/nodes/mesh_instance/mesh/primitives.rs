@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use glam::{Vec2, Vec3};
+
+use super::VertexDataBuilder;
+
+// Every primitive is centered on the origin with Z up and comes with normals and texture
+// coordinates, v = 0 is the top of the image. The builders can still generate tangents or
+// change the vertex layout before they are built into a mesh
+
+#[derive(Default)]
+struct PrimitiveData {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    tex_coords: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl PrimitiveData {
+    fn push_vertex(&mut self, position: Vec3, normal: Vec3, tex_coord: Vec2) -> u32 {
+        self.positions.push(position.to_array());
+        self.normals.push(normal.to_array());
+        self.tex_coords.push(tex_coord.to_array());
+        self.positions.len() as u32 - 1
+    }
+    // (rows + 1) * (columns + 1) vertices, rows go down the surface and columns to the right
+    // of it when looking at its front
+    fn push_grid(
+        &mut self,
+        rows: u32,
+        columns: u32,
+        vertex: impl Fn(u32, u32) -> (Vec3, Vec3, Vec2),
+    ) {
+        let first = self.positions.len() as u32;
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let (position, normal, tex_coord) = vertex(column, row);
+                self.push_vertex(position, normal, tex_coord);
+            }
+        }
+        for row in 0..rows {
+            for column in 0..columns {
+                let top = first + row * (columns + 1) + column;
+                let bottom = top + columns + 1;
+                self.indices.extend_from_slice(&[
+                    top,
+                    bottom,
+                    top + 1,
+                    top + 1,
+                    bottom,
+                    bottom + 1,
+                ]);
+            }
+        }
+    }
+    // disc facing up or down, textured with the circle inscribed in the image
+    fn push_cap(&mut self, radius: f32, z: f32, segments: u32, facing_up: bool) {
+        let normal = if facing_up { Vec3::Z } else { Vec3::NEG_Z };
+        let center = self.push_vertex(Vec3::new(0.0, 0.0, z), normal, Vec2::splat(0.5));
+        for segment in 0..=segments {
+            let (sin, cos) = (segment as f32 / segments as f32 * 2.0 * PI).sin_cos();
+            let flip = if facing_up { 1.0 } else { -1.0 };
+            self.push_vertex(
+                Vec3::new(cos * radius, sin * radius, z),
+                normal,
+                Vec2::new(0.5 + cos * 0.5, 0.5 - sin * flip * 0.5),
+            );
+        }
+        for segment in 0..segments {
+            let current = center + 1 + segment;
+            if facing_up {
+                self.indices
+                    .extend_from_slice(&[center, current, current + 1]);
+            } else {
+                self.indices
+                    .extend_from_slice(&[center, current + 1, current]);
+            }
+        }
+    }
+    fn into_builder(mut self) -> VertexDataBuilder {
+        // the pole rows of spheres collapse into slivers that would only waste draw time
+        let positions = &self.positions;
+        self.indices = self
+            .indices
+            .chunks_exact(3)
+            .filter(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(positions[triangle[i] as usize]));
+                let longest_edge = (b - a)
+                    .length_squared()
+                    .max((c - b).length_squared())
+                    .max((a - c).length_squared());
+                (b - a).cross(c - a).length() > longest_edge * 1e-6
+            })
+            .flatten()
+            .copied()
+            .collect();
+        let mut builder = VertexDataBuilder::new();
+        builder
+            .set_vertex_positions(&self.positions)
+            .set_vertex_normals(&self.normals)
+            .set_vertex_tex_coords(&self.tex_coords)
+            .set_indicies(&self.indices);
+        builder
+    }
+}
+
+// every face has its own four vertices and the whole image
+pub fn create_cube(size: Vec3) -> VertexDataBuilder {
+    let half_size = size * 0.5;
+    let mut data = PrimitiveData::default();
+    for (normal, up) in [
+        (Vec3::X, Vec3::Z),
+        (Vec3::NEG_X, Vec3::Z),
+        (Vec3::Y, Vec3::Z),
+        (Vec3::NEG_Y, Vec3::Z),
+        (Vec3::Z, Vec3::Y),
+        (Vec3::NEG_Z, Vec3::NEG_Y),
+    ] {
+        let right = up.cross(normal);
+        data.push_grid(1, 1, |column, row| {
+            let x = column as f32 * 2.0 - 1.0;
+            let y = 1.0 - row as f32 * 2.0;
+            (
+                (normal + right * x + up * y) * half_size,
+                normal,
+                Vec2::new(column as f32, row as f32),
+            )
+        });
+    }
+    data.into_builder()
+}
+
+// rings go from the top pole to the bottom one, segments around the Z axis
+pub fn create_uv_sphere(radius: f32, rings: u32, segments: u32) -> VertexDataBuilder {
+    let rings = rings.max(2);
+    let segments = segments.max(3);
+    let mut data = PrimitiveData::default();
+    data.push_grid(rings, segments, |column, row| {
+        let u = column as f32 / segments as f32;
+        let v = row as f32 / rings as f32;
+        let (polar_sin, polar_cos) = (v * PI).sin_cos();
+        let (azimuth_sin, azimuth_cos) = (u * 2.0 * PI).sin_cos();
+        let normal = Vec3::new(polar_sin * azimuth_cos, polar_sin * azimuth_sin, polar_cos);
+        (normal * radius, normal, Vec2::new(u, v))
+    });
+    data.into_builder()
+}
+
+// Subdivided icosahedron, evenly spread triangles without the crowded poles of a uv sphere.
+// Vertices on the texture seam and the poles are duplicated so the mapping stays continuous
+pub fn create_icosphere(radius: f32, subdivisions: u32) -> VertexDataBuilder {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let mut points: Vec<Vec3> = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+    .iter()
+    .map(|point| Vec3::from(*point).normalize())
+    .collect();
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+    for _ in 0..subdivisions {
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut get_midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                points.push((points[a as usize] + points[b as usize]).normalize());
+                points.len() as u32 - 1
+            })
+        };
+        triangles = triangles
+            .iter()
+            .flat_map(|[a, b, c]| {
+                let ab = get_midpoint(*a, *b);
+                let bc = get_midpoint(*b, *c);
+                let ca = get_midpoint(*c, *a);
+                [[*a, ab, ca], [*b, bc, ab], [*c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut data = PrimitiveData::default();
+    // point and u bits of every vertex that has been added
+    let mut vertices: HashMap<(u32, u32), u32> = HashMap::new();
+    for triangle in triangles {
+        let mut tex_coords = triangle.map(|index| {
+            let point = points[index as usize];
+            Vec2::new(
+                0.5 + point.y.atan2(point.x) / (2.0 * PI),
+                point.z.clamp(-1.0, 1.0).acos() / PI,
+            )
+        });
+        // triangles crossing the seam continue past u = 1
+        let min_u = tex_coords.iter().map(|uv| uv.x).fold(f32::MAX, f32::min);
+        let max_u = tex_coords.iter().map(|uv| uv.x).fold(f32::MIN, f32::max);
+        if max_u - min_u > 0.5 {
+            for tex_coord in tex_coords.iter_mut().filter(|uv| uv.x < 0.5) {
+                tex_coord.x += 1.0;
+            }
+        }
+        // poles take the u of the triangle they're part of
+        for corner in 0..3 {
+            let point = points[triangle[corner] as usize];
+            if point.x.abs() < 1e-6 && point.y.abs() < 1e-6 {
+                tex_coords[corner].x =
+                    (tex_coords[(corner + 1) % 3].x + tex_coords[(corner + 2) % 3].x) * 0.5;
+            }
+        }
+        for (index, tex_coord) in triangle.iter().zip(tex_coords) {
+            let vertex = *vertices
+                .entry((*index, tex_coord.x.to_bits()))
+                .or_insert_with(|| {
+                    let point = points[*index as usize];
+                    data.push_vertex(point * radius, point, tex_coord)
+                });
+            data.indices.push(vertex);
+        }
+    }
+    data.into_builder()
+}
+
+// Plane on the XY axes facing up, the subdivisions are extra cuts along each side
+pub fn create_plane(size: Vec2, subdivisions: u32) -> VertexDataBuilder {
+    let cells = subdivisions + 1;
+    let mut data = PrimitiveData::default();
+    data.push_grid(cells, cells, |column, row| {
+        let u = column as f32 / cells as f32;
+        let v = row as f32 / cells as f32;
+        (
+            Vec3::new((u - 0.5) * size.x, (0.5 - v) * size.y, 0.0),
+            Vec3::Z,
+            Vec2::new(u, v),
+        )
+    });
+    data.into_builder()
+}
+
+// capped cylinder along the Z axis
+pub fn create_cylinder(radius: f32, height: f32, segments: u32) -> VertexDataBuilder {
+    let segments = segments.max(3);
+    let half_height = height * 0.5;
+    let mut data = PrimitiveData::default();
+    data.push_grid(1, segments, |column, row| {
+        let u = column as f32 / segments as f32;
+        let (sin, cos) = (u * 2.0 * PI).sin_cos();
+        let normal = Vec3::new(cos, sin, 0.0);
+        let z = half_height - row as f32 * height;
+        (
+            normal * radius + Vec3::Z * z,
+            normal,
+            Vec2::new(u, row as f32),
+        )
+    });
+    data.push_cap(radius, half_height, segments, true);
+    data.push_cap(radius, -half_height, segments, false);
+    data.into_builder()
+}
+
+// cone along the Z axis with the tip at the top and a capped base
+pub fn create_cone(radius: f32, height: f32, segments: u32) -> VertexDataBuilder {
+    let segments = segments.max(3);
+    let half_height = height * 0.5;
+    let get_normal = |u: f32| {
+        let (sin, cos) = (u * 2.0 * PI).sin_cos();
+        Vec3::new(cos * height, sin * height, radius).normalize()
+    };
+    let mut data = PrimitiveData::default();
+    // the tip is split per segment so each one gets the normal in the middle of its face
+    for segment in 0..segments {
+        let u = (segment as f32 + 0.5) / segments as f32;
+        data.push_vertex(Vec3::Z * half_height, get_normal(u), Vec2::new(u, 0.0));
+    }
+    for segment in 0..=segments {
+        let u = segment as f32 / segments as f32;
+        let (sin, cos) = (u * 2.0 * PI).sin_cos();
+        data.push_vertex(
+            Vec3::new(cos * radius, sin * radius, -half_height),
+            get_normal(u),
+            Vec2::new(u, 1.0),
+        );
+    }
+    for segment in 0..segments {
+        let base = segments + segment;
+        data.indices.extend_from_slice(&[segment, base, base + 1]);
+    }
+    data.push_cap(radius, -half_height, segments, false);
+    data.into_builder()
+}
+
+// Cylinder with half spheres on both ends along the Z axis, the height includes the ends.
+// rings is the ring count of each half sphere
+pub fn create_capsule(radius: f32, height: f32, rings: u32, segments: u32) -> VertexDataBuilder {
+    let rings = rings.max(1);
+    let segments = segments.max(3);
+    let height = height.max(radius * 2.0);
+    let half_cylinder = height * 0.5 - radius;
+    let mut data = PrimitiveData::default();
+    // the row between both equators is the cylinder
+    data.push_grid(rings * 2 + 1, segments, |column, row| {
+        let u = column as f32 / segments as f32;
+        let (polar, z_offset) = if row <= rings {
+            (row as f32 / rings as f32 * PI * 0.5, half_cylinder)
+        } else {
+            (
+                (row - rings - 1) as f32 / rings as f32 * PI * 0.5 + PI * 0.5,
+                -half_cylinder,
+            )
+        };
+        let (polar_sin, polar_cos) = polar.sin_cos();
+        let (azimuth_sin, azimuth_cos) = (u * 2.0 * PI).sin_cos();
+        let normal = Vec3::new(polar_sin * azimuth_cos, polar_sin * azimuth_sin, polar_cos);
+        let position = normal * radius + Vec3::Z * z_offset;
+        (position, normal, Vec2::new(u, 0.5 - position.z / height))
+    });
+    data.into_builder()
+}
+
+// torus around the Z axis, major segments go around the axis and minor ones around the tube
+pub fn create_torus(
+    major_radius: f32,
+    minor_radius: f32,
+    major_segments: u32,
+    minor_segments: u32,
+) -> VertexDataBuilder {
+    let major_segments = major_segments.max(3);
+    let minor_segments = minor_segments.max(3);
+    let mut data = PrimitiveData::default();
+    // rows start on the outer equator and go down around the tube
+    data.push_grid(minor_segments, major_segments, |column, row| {
+        let u = column as f32 / major_segments as f32;
+        let v = row as f32 / minor_segments as f32;
+        let (major_sin, major_cos) = (u * 2.0 * PI).sin_cos();
+        let (minor_sin, minor_cos) = (-v * 2.0 * PI).sin_cos();
+        let normal = Vec3::new(minor_cos * major_cos, minor_cos * major_sin, minor_sin);
+        let center = Vec3::new(major_cos, major_sin, 0.0) * major_radius;
+        (center + normal * minor_radius, normal, Vec2::new(u, v))
+    });
+    data.into_builder()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_primitives() -> Vec<(&'static str, VertexDataBuilder)> {
+        vec![
+            ("cube", create_cube(Vec3::new(1.0, 2.0, 3.0))),
+            ("uv sphere", create_uv_sphere(1.5, 8, 12)),
+            ("icosphere", create_icosphere(1.5, 2)),
+            ("plane", create_plane(Vec2::new(2.0, 3.0), 4)),
+            ("cylinder", create_cylinder(0.5, 2.0, 12)),
+            ("cone", create_cone(0.5, 2.0, 12)),
+            ("capsule", create_capsule(0.5, 2.0, 4, 12)),
+            ("torus", create_torus(1.0, 0.25, 16, 8)),
+            // too few segments are raised to the minimum
+            ("small uv sphere", create_uv_sphere(1.0, 0, 0)),
+            ("small cylinder", create_cylinder(1.0, 1.0, 0)),
+            ("small torus", create_torus(1.0, 0.5, 0, 0)),
+        ]
+    }
+
+    #[test]
+    fn indices_are_in_range_triangles() {
+        for (name, builder) in get_primitives() {
+            let (vertices, indices) = builder.build_vertex_data();
+            assert!(!indices.is_empty(), "{name} has no triangles");
+            assert!(
+                indices.len().is_multiple_of(3),
+                "{name} isn't a triangle list"
+            );
+            assert!(
+                indices
+                    .iter()
+                    .all(|index| (*index as usize) < vertices.len()),
+                "{name} has an index out of range"
+            );
+        }
+    }
+
+    #[test]
+    fn normals_are_unit_length() {
+        for (name, builder) in get_primitives() {
+            let (vertices, _) = builder.build_vertex_data();
+            for vertex in vertices {
+                let length = vertex.get_normal().length();
+                assert!(
+                    (length - 1.0).abs() < 1e-5,
+                    "{name} normal has length {length}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn triangles_face_their_normals() {
+        for (name, builder) in get_primitives() {
+            let (vertices, indices) = builder.build_vertex_data();
+            for triangle in indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize]);
+                let face_normal = (b.get_position() - a.get_position())
+                    .cross(c.get_position() - a.get_position());
+                // slivers at the poles are degenerate
+                if face_normal.length() < 1e-6 {
+                    continue;
+                }
+                let normal = a.get_normal() + b.get_normal() + c.get_normal();
+                assert!(
+                    face_normal.dot(normal) > 0.0,
+                    "{name} has a flipped triangle"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::engine::scene::nodes::mesh_instance::mesh::Mesh;
+use crate::engine::scene::nodes::BaseNode;
+use crate::engine::scene::utils::Transform;
+
+// Draws the same mesh once per instance transform with a single instanced draw call.
+// Instance transforms are relative to the node's own transform
+pub struct MultiMeshInstance {
+    pub name: String,
+    pub transform: Transform,
+    pub children: Vec<Box<dyn BaseNode>>,
+    pub mesh: Option<Mesh>,
+    pub pipeline_id: String,
+    // when set, the material's pipeline is used instead of pipeline_id
+    pub material_id: String,
+    // whether the instance is drawn into shadow maps and darkened by them
+    pub cast_shadows: bool,
+    pub receive_shadows: bool,
+    pub instance_transforms: Vec<Transform>,
+    transform_mx: glam::Mat4,
+    global_transform_mx: glam::Mat4,
+    instance_mxs: Vec<glam::Mat4>,
+}
+
+impl MultiMeshInstance {
+    pub fn new(name: &str) -> MultiMeshInstance {
+        MultiMeshInstance {
+            name: name.to_string(),
+            transform: Transform::new(),
+            children: Vec::new(),
+            mesh: None,
+            pipeline_id: String::new(),
+            material_id: String::new(),
+            cast_shadows: true,
+            receive_shadows: true,
+            instance_transforms: Vec::new(),
+            transform_mx: glam::Mat4::IDENTITY,
+            global_transform_mx: glam::Mat4::IDENTITY,
+            instance_mxs: Vec::new(),
+        }
+    }
+    pub fn add_instance(&mut self, transform: Transform) {
+        self.instance_transforms.push(transform);
+    }
+    pub fn clear_instances(&mut self) {
+        self.instance_transforms.clear();
+    }
+    pub fn get_instance_count(&self) -> usize {
+        self.instance_transforms.len()
+    }
+    // local instance matrices, regenerated during update()
+    pub fn get_instance_matrices(&self) -> &[glam::Mat4] {
+        &self.instance_mxs
+    }
+}
+
+impl BaseNode for MultiMeshInstance {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+    fn get_node_name(&self) -> &str {
+        &self.name
+    }
+    fn update(&mut self, delta: f64) {
+        if self.transform.get_values_changed() {
+            self.transform_mx = self.transform.generate_transform_matrix();
+            self.transform.set_values_changed(false);
+        }
+        let instance_count = self.instance_transforms.len();
+        let mut rebuild_all = false;
+        if self.instance_mxs.len() != instance_count {
+            self.instance_mxs
+                .resize(instance_count, glam::Mat4::IDENTITY);
+            rebuild_all = true;
+        }
+        for (transform, mx) in self
+            .instance_transforms
+            .iter_mut()
+            .zip(self.instance_mxs.iter_mut())
+        {
+            if rebuild_all || transform.get_values_changed() {
+                *mx = transform.generate_transform_matrix();
+                transform.set_values_changed(false);
+            }
+        }
+        for child in self.children.iter_mut() {
+            child.update(delta);
+        }
+    }
+    fn get_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.transform_mx
+    }
+    fn get_global_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.global_transform_mx
+    }
+    fn set_global_transformation_matrix(&mut self, mx: glam::Mat4) {
+        self.global_transform_mx = mx;
+    }
+    fn add_node(&mut self, node: Box<dyn BaseNode + 'static>) {
+        self.children.push(node);
+    }
+    fn get_node_mut(&mut self, name: &str) -> Option<&mut Box<dyn BaseNode + 'static>> {
+        if let Some(node) = self
+            .children
+            .iter_mut()
+            .find(|node| node.get_node_name() == name)
+        {
+            return Some(node);
+        }
+        println!("node: {} not found", name);
+        None
+    }
+    fn get_children(&self) -> &Vec<Box<dyn BaseNode + 'static>> {
+        &self.children
+    }
+    fn get_children_mut(&mut self) -> &mut Vec<Box<dyn BaseNode + 'static>> {
+        &mut self.children
+    }
+    fn remove_node(&mut self, name: &str) {
+        if let Some(index) = self
+            .children
+            .iter()
+            .position(|node| node.get_node_name() == name)
+        {
+            self.children.remove(index);
+        } else {
+            println!(
+                "couldn't find a node: {} or it has been already removed",
+                name
+            )
+        }
+    }
+}
@@ -9,6 +9,7 @@ pub struct Node {
     pub children: Vec<Box<dyn BaseNode>>,
     pub parent: Option<Rc<dyn BaseNode>>,
     transform_mx: glam::Mat4,
+    global_transform_mx: glam::Mat4,
 }
 
 impl Node {
@@ -19,6 +20,7 @@ impl Node {
             children: Vec::new(),
             parent: None,
             transform_mx: glam::Mat4::IDENTITY,
+            global_transform_mx: glam::Mat4::IDENTITY,
         }
     }
 }
@@ -36,18 +38,21 @@ impl BaseNode for Node {
     fn update(&mut self, delta: f64) {
         if self.transform.get_values_changed() {
             self.transform_mx = self.transform.generate_transform_matrix();
-            for i in 0..self.children.len() {
-                self.children[i].update(delta);
-                self.transform_mx = self.children[i]
-                    .get_transformation_matrix()
-                    .mul_mat4(&self.transform_mx);
-            }
             self.transform.set_values_changed(false);
         }
+        for child in self.children.iter_mut() {
+            child.update(delta);
+        }
     }
     fn get_transformation_matrix(&self) -> &glam::Mat4 {
         &self.transform_mx
     }
+    fn get_global_transformation_matrix(&self) -> &glam::Mat4 {
+        &self.global_transform_mx
+    }
+    fn set_global_transformation_matrix(&mut self, mx: glam::Mat4) {
+        self.global_transform_mx = mx;
+    }
     fn add_node(&mut self, node: Box<dyn BaseNode + 'static>) {
         self.children.push(node);
     }
@@ -15,32 +15,43 @@ impl Transform {
         }
     }
     pub fn get_translation(&self) -> &glam::Vec3 {
-        return &self.translation;
+        &self.translation
     }
     pub fn set_translation(&mut self, input: glam::Vec3) {
         self.translation = input;
         self.values_changed = true;
     }
     pub fn get_scale(&self) -> &glam::Vec3 {
-        return &self.scale;
+        &self.scale
     }
     pub fn set_scale(&mut self, input: glam::Vec3) {
         self.scale = input;
         self.values_changed = true;
     }
     pub fn get_rotation(&self) -> &glam::Vec3 {
-        return &self.rotation;
+        &self.rotation
     }
     pub fn set_rotation(&mut self, input: glam::Vec3) {
         self.rotation = input;
         self.values_changed = true;
     }
     pub fn get_values_changed(&self) -> bool {
-        return self.values_changed;
+        self.values_changed
     }
     pub fn set_values_changed(&mut self, input: bool) {
         self.values_changed = input;
     }
+    // rotates so that the local -Z axis points from the translation towards the target
+    pub fn look_at(&mut self, target: glam::Vec3, up: glam::Vec3) {
+        let view = glam::Mat4::look_at_rh(self.translation, target, up);
+        let rotation = glam::Quat::from_mat4(&view.inverse());
+        let (x, y, z) = rotation.to_euler(glam::EulerRot::XYZ);
+        self.set_rotation(glam::Vec3::new(
+            x.to_degrees(),
+            y.to_degrees(),
+            z.to_degrees(),
+        ));
+    }
     pub fn generate_transform_matrix(&self) -> glam::Mat4 {
         let rot_quat = glam::Quat::from_euler(
             glam::EulerRot::XYZ,
@@ -48,6 +59,302 @@ impl Transform {
             self.rotation.y.to_radians(),
             self.rotation.z.to_radians(),
         );
-        return glam::Mat4::from_scale_rotation_translation(self.scale, rot_quat, self.translation);
+        glam::Mat4::from_scale_rotation_translation(self.scale, rot_quat, self.translation)
+    }
+}
+
+// Axis aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: glam::Vec3,
+    pub max: glam::Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: glam::Vec3, max: glam::Vec3) -> Aabb {
+        Aabb { min, max }
+    }
+    // an empty slice gives a box of zero size at the origin
+    pub fn from_points(points: &[glam::Vec3]) -> Aabb {
+        if points.is_empty() {
+            return Aabb::new(glam::Vec3::ZERO, glam::Vec3::ZERO);
+        }
+        let mut aabb = Aabb::new(glam::Vec3::splat(f32::MAX), glam::Vec3::splat(f32::MIN));
+        for point in points {
+            aabb.min = aabb.min.min(*point);
+            aabb.max = aabb.max.max(*point);
+        }
+        aabb
+    }
+    pub fn get_center(&self) -> glam::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+    // half of the size along every axis
+    pub fn get_extents(&self) -> glam::Vec3 {
+        (self.max - self.min) * 0.5
+    }
+    pub fn contains_point(&self, point: glam::Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
+    }
+    // box around the transformed box, projects the extents onto the world axes instead of
+    // transforming all eight corners
+    pub fn transform(&self, mx: &glam::Mat4) -> Aabb {
+        let center = mx.transform_point3(self.get_center());
+        let extents = self.get_extents();
+        let abs_mx = glam::Mat3::from_cols(
+            mx.x_axis.truncate().abs(),
+            mx.y_axis.truncate().abs(),
+            mx.z_axis.truncate().abs(),
+        );
+        let world_extents = abs_mx * extents;
+        Aabb::new(center - world_extents, center + world_extents)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: glam::Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: glam::Vec3, radius: f32) -> BoundingSphere {
+        BoundingSphere { center, radius }
+    }
+    // centered on the bounding box of the points, which is close to the smallest sphere for most meshes
+    pub fn from_points(points: &[glam::Vec3]) -> BoundingSphere {
+        let center = Aabb::from_points(points).get_center();
+        let radius = points
+            .iter()
+            .map(|point| point.distance_squared(center))
+            .fold(0.0, f32::max)
+            .sqrt();
+        BoundingSphere::new(center, radius)
+    }
+    pub fn contains_point(&self, point: glam::Vec3) -> bool {
+        point.distance_squared(self.center) <= self.radius * self.radius
+    }
+    pub fn intersects(&self, other: &BoundingSphere) -> bool {
+        let radius = self.radius + other.radius;
+        self.center.distance_squared(other.center) <= radius * radius
+    }
+    // the radius grows with the largest scale so that non uniform scaling stays covered
+    pub fn transform(&self, mx: &glam::Mat4) -> BoundingSphere {
+        let scale = mx
+            .x_axis
+            .truncate()
+            .length()
+            .max(mx.y_axis.truncate().length())
+            .max(mx.z_axis.truncate().length());
+        BoundingSphere::new(mx.transform_point3(self.center), self.radius * scale)
+    }
+}
+
+// Planes of the volume a view projection matrix maps into clip space with 0..1 depth,
+// their normals point inwards
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    pub planes: [glam::Vec4; 6],
+}
+
+impl Frustum {
+    pub fn from_view_projection(view_proj_mx: &glam::Mat4) -> Frustum {
+        let row_x = view_proj_mx.row(0);
+        let row_y = view_proj_mx.row(1);
+        let row_z = view_proj_mx.row(2);
+        let row_w = view_proj_mx.row(3);
+        let planes = [
+            row_w + row_x,
+            row_w - row_x,
+            row_w + row_y,
+            row_w - row_y,
+            row_z,
+            row_w - row_z,
+        ]
+        .map(|plane| plane / plane.truncate().length().max(f32::EPSILON));
+        Frustum { planes }
+    }
+    // conservative, boxes near the corners of the frustum can pass while being outside
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let center = aabb.get_center();
+        let extents = aabb.get_extents();
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            normal.dot(center) + plane.w >= -extents.dot(normal.abs())
+        })
+    }
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(sphere.center) + plane.w >= -sphere.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Mat4, Vec3};
+
+    fn get_unit_cube_corners() -> Vec<Vec3> {
+        (0..8)
+            .map(|i| Vec3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2 & 1) as f32) - 0.5)
+            .collect()
+    }
+
+    #[test]
+    fn aabb_of_a_unit_cube() {
+        let aabb = Aabb::from_points(&get_unit_cube_corners());
+        assert_eq!(aabb, Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5)));
+        assert_eq!(aabb.get_center(), Vec3::ZERO);
+        assert_eq!(aabb.get_extents(), Vec3::splat(0.5));
+        assert!(aabb.contains_point(Vec3::new(0.5, 0.0, -0.5)));
+        assert!(!aabb.contains_point(Vec3::new(0.6, 0.0, 0.0)));
+        assert_eq!(Aabb::from_points(&[]), Aabb::new(Vec3::ZERO, Vec3::ZERO));
+    }
+
+    #[test]
+    fn aabb_intersections() {
+        let aabb = Aabb::new(Vec3::ZERO, Vec3::ONE);
+        assert!(aabb.intersects(&Aabb::new(Vec3::splat(0.5), Vec3::splat(2.0))));
+        // touching faces count
+        assert!(aabb.intersects(&Aabb::new(
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(2.0, 1.0, 1.0)
+        )));
+        assert!(!aabb.intersects(&Aabb::new(
+            Vec3::new(1.1, 0.0, 0.0),
+            Vec3::new(2.0, 1.0, 1.0)
+        )));
+    }
+
+    #[test]
+    fn transformed_aabb_contains_the_transformed_corners() {
+        let corners = get_unit_cube_corners();
+        let aabb = Aabb::from_points(&corners);
+        let mx = Mat4::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 0.5),
+            glam::Quat::from_rotation_z(std::f32::consts::FRAC_PI_4),
+            Vec3::new(1.0, 2.0, 3.0),
+        );
+        let transformed = aabb.transform(&mx);
+        let expected = Aabb::from_points(
+            &corners
+                .iter()
+                .map(|corner| mx.transform_point3(*corner))
+                .collect::<Vec<_>>(),
+        );
+        assert!(transformed.min.abs_diff_eq(expected.min, 1e-5));
+        assert!(transformed.max.abs_diff_eq(expected.max, 1e-5));
+        // translations only move the box
+        let moved = aabb.transform(&Mat4::from_translation(Vec3::X));
+        assert_eq!(
+            moved,
+            Aabb::new(Vec3::new(0.5, -0.5, -0.5), Vec3::new(1.5, 0.5, 0.5))
+        );
+    }
+
+    #[test]
+    fn bounding_sphere_of_a_unit_cube() {
+        let sphere = BoundingSphere::from_points(&get_unit_cube_corners());
+        assert_eq!(sphere.center, Vec3::ZERO);
+        assert!((sphere.radius - 0.75f32.sqrt()).abs() < 1e-6);
+        for corner in get_unit_cube_corners() {
+            assert!(sphere.contains_point(corner * 0.999));
+        }
+        assert!(sphere.intersects(&BoundingSphere::new(Vec3::new(1.0, 0.0, 0.0), 0.2)));
+        assert!(!sphere.intersects(&BoundingSphere::new(Vec3::new(2.0, 0.0, 0.0), 0.2)));
+    }
+
+    #[test]
+    fn transformed_bounding_sphere_grows_with_the_largest_scale() {
+        let sphere = BoundingSphere::new(Vec3::X, 1.0);
+        let mx = Mat4::from_scale_rotation_translation(
+            Vec3::new(1.0, 3.0, 2.0),
+            glam::Quat::IDENTITY,
+            Vec3::new(0.0, 0.0, 5.0),
+        );
+        let transformed = sphere.transform(&mx);
+        assert_eq!(transformed.center, Vec3::new(1.0, 0.0, 5.0));
+        assert_eq!(transformed.radius, 3.0);
+    }
+
+    // camera at the origin looking along +y with z up, sees 1..10 units ahead inside a 90 degree cone
+    fn get_test_frustum() -> Frustum {
+        let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 10.0);
+        let view = Mat4::look_at_rh(Vec3::ZERO, Vec3::Y, Vec3::Z);
+        Frustum::from_view_projection(&(projection * view))
+    }
+
+    fn get_box(center: Vec3, extent: f32) -> Aabb {
+        Aabb::new(center - Vec3::splat(extent), center + Vec3::splat(extent))
+    }
+
+    #[test]
+    fn frustum_planes_are_normalized() {
+        for plane in get_test_frustum().planes {
+            assert!((plane.truncate().length() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn frustum_accepts_volumes_inside() {
+        let frustum = get_test_frustum();
+        assert!(frustum.intersects_aabb(&get_box(Vec3::new(0.0, 5.0, 0.0), 0.5)));
+        assert!(frustum.intersects_sphere(&BoundingSphere::new(Vec3::new(1.0, 5.0, -1.0), 0.5)));
+    }
+
+    #[test]
+    fn frustum_rejects_volumes_outside() {
+        let frustum = get_test_frustum();
+        // behind the camera, to the side and above
+        for center in [
+            Vec3::new(0.0, -5.0, 0.0),
+            Vec3::new(20.0, 5.0, 0.0),
+            Vec3::new(0.0, 5.0, 20.0),
+        ] {
+            assert!(!frustum.intersects_aabb(&get_box(center, 0.5)));
+            assert!(!frustum.intersects_sphere(&BoundingSphere::new(center, 0.5)));
+        }
+    }
+
+    #[test]
+    fn frustum_accepts_volumes_straddling_a_plane() {
+        let frustum = get_test_frustum();
+        // centered on the right plane x = y
+        let center = Vec3::new(5.0, 5.0, 0.0);
+        assert!(frustum.intersects_aabb(&get_box(center, 1.0)));
+        assert!(frustum.intersects_sphere(&BoundingSphere::new(center, 1.0)));
+        // center outside of the right plane, but the volume reaches over it
+        let center = Vec3::new(5.5, 5.0, 0.0);
+        assert!(frustum.intersects_aabb(&get_box(center, 1.0)));
+        assert!(frustum.intersects_sphere(&BoundingSphere::new(center, 1.0)));
+    }
+
+    #[test]
+    fn frustum_clips_against_near_and_far_planes() {
+        let frustum = get_test_frustum();
+        let sphere = |y: f32| BoundingSphere::new(Vec3::new(0.0, y, 0.0), 0.2);
+        assert!(!frustum.intersects_aabb(&get_box(Vec3::new(0.0, 0.7, 0.0), 0.2)));
+        assert!(!frustum.intersects_sphere(&sphere(0.7)));
+        assert!(frustum.intersects_aabb(&get_box(Vec3::new(0.0, 1.1, 0.0), 0.2)));
+        assert!(frustum.intersects_sphere(&sphere(1.1)));
+        assert!(frustum.intersects_aabb(&get_box(Vec3::new(0.0, 9.9, 0.0), 0.2)));
+        assert!(frustum.intersects_sphere(&sphere(9.9)));
+        assert!(!frustum.intersects_aabb(&get_box(Vec3::new(0.0, 10.3, 0.0), 0.2)));
+        assert!(!frustum.intersects_sphere(&sphere(10.3)));
+    }
+
+    #[test]
+    fn frustum_uses_zero_to_one_depth() {
+        // the identity keeps clip space as it is, so only 0 <= z <= 1 is inside
+        let frustum = Frustum::from_view_projection(&Mat4::IDENTITY);
+        assert!(frustum.intersects_aabb(&get_box(Vec3::new(0.0, 0.0, 0.5), 0.1)));
+        assert!(!frustum.intersects_aabb(&get_box(Vec3::new(0.0, 0.0, -0.5), 0.1)));
+        assert!(!frustum.intersects_aabb(&get_box(Vec3::new(0.0, 0.0, 1.5), 0.1)));
+        assert!(!frustum.intersects_sphere(&BoundingSphere::new(Vec3::new(0.0, 0.0, -0.5), 0.1)));
+        assert!(frustum.intersects_sphere(&BoundingSphere::new(Vec3::new(0.0, 0.0, 0.05), 0.1)));
     }
 }
@@ -1,9 +1,18 @@
-use std::mem;
+use std::{cell::Cell, mem};
 
 use winit::window::Window;
 
+pub mod environment;
+pub mod material;
+pub mod post_process;
+pub mod render_graph;
 pub mod resources;
 
+// HDR render graph texture the engine's main pass draws the scene into, pipelines drawn by
+// mesh instances have to target SCENE_COLOR_FORMAT
+pub const SCENE_COLOR: &str = "scene_color";
+pub const SCENE_COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
 #[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
 pub enum BindingGroupType {
     Global = 0,
@@ -50,7 +59,32 @@ pub struct RendererServer {
     pub queue: wgpu::Queue,
     //swapchain
     swapchain: Swapchain,
-    pub surface: wgpu::Surface,
+    // None when the renderer runs headless
+    pub surface: Option<wgpu::Surface>,
+    // set by get_new_frame(), lets the engine tell whether the application rendered on its own
+    frame_acquired: Cell<bool>,
+}
+
+// Color target handed out by get_new_frame(), either the window's swapchain image
+// or the offscreen texture of a headless renderer
+pub enum Frame<'a> {
+    Surface(wgpu::SurfaceTexture),
+    Offscreen(&'a wgpu::Texture),
+}
+
+impl Frame<'_> {
+    pub fn get_texture(&self) -> &wgpu::Texture {
+        match self {
+            Frame::Surface(surface_texture) => &surface_texture.texture,
+            Frame::Offscreen(texture) => texture,
+        }
+    }
+
+    pub fn present(self) {
+        if let Frame::Surface(surface_texture) = self {
+            surface_texture.present();
+        }
+    }
 }
 
 use bytemuck::{Pod, Zeroable};
@@ -59,12 +93,57 @@ impl RendererServer {
         //Instance and device init
         let instance = wgpu::Instance::default();
         let surface = unsafe { instance.create_surface(&window) }.unwrap();
+        let (adapter, device, queue) = Self::request_device(&instance, Some(&surface), false).await;
+        let swapchain = Swapchain::new()
+            .new_resolution((window.inner_size().width, window.inner_size().height))
+            .build(&adapter, &device, Some(&surface));
+        RendererServer {
+            instance,
+            adapter,
+            device,
+            queue,
+            surface: Some(surface),
+            swapchain,
+            frame_acquired: Cell::new(false),
+        }
+    }
 
+    // Creates a renderer without a window, frames are rendered into an offscreen texture.
+    // The WGPU_BACKEND environment variable can restrict the backends, e.g. to pick lavapipe through vulkan
+    pub async fn new_headless(
+        resolution: (u32, u32),
+        force_fallback_adapter: bool,
+    ) -> RendererServer {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all),
+            ..Default::default()
+        });
+        let (adapter, device, queue) =
+            Self::request_device(&instance, None, force_fallback_adapter).await;
+        let swapchain = Swapchain::new()
+            .new_resolution(resolution)
+            .build(&adapter, &device, None);
+        RendererServer {
+            instance,
+            adapter,
+            device,
+            queue,
+            surface: None,
+            swapchain,
+            frame_acquired: Cell::new(false),
+        }
+    }
+
+    async fn request_device(
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface>,
+        force_fallback_adapter: bool,
+    ) -> (wgpu::Adapter, wgpu::Device, wgpu::Queue) {
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
                 power_preference: wgpu::PowerPreference::default(),
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
+                force_fallback_adapter,
+                compatible_surface,
             })
             .await
             .expect("Failed to find an appropriate adapter");
@@ -80,54 +159,203 @@ impl RendererServer {
             )
             .await
             .expect("Failed to create device");
-        let swapchain = Swapchain::new()
-            .new_resolution((window.inner_size().width, window.inner_size().height))
-            .build(&adapter, &device, &surface);
-        RendererServer {
-            instance,
-            adapter,
-            device,
-            queue,
-            surface,
-            swapchain,
-        }
+        (adapter, device, queue)
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
     }
 
     pub fn get_swapchain(&self) -> &Swapchain {
-        return &self.swapchain;
+        &self.swapchain
     }
 
+    // Reconfigures the surface and recreates every size dependent target.
+    // Zero sized (minimized) windows keep the old targets until they get a valid size again
     pub fn update_swapchain(&mut self, resolution: (u32, u32)) {
-        Swapchain::new().new_resolution(resolution);
+        if resolution.0 == 0 || resolution.1 == 0 {
+            self.swapchain.minimized = true;
+            return;
+        }
+        self.swapchain = Swapchain::new()
+            .new_resolution(resolution)
+            .new_present_mode(self.swapchain.get_present_mode())
+            .build(&self.adapter, &self.device, self.surface.as_ref());
     }
 
-    pub fn get_new_frame(&self) -> (wgpu::SurfaceTexture, wgpu::TextureView, &wgpu::TextureView) {
-        let frame = self
-            .surface
-            .get_current_texture()
-            .expect("Failed to acquire next swap chain texture");
+    // Falls back to Fifo (vsync) when the surface doesn't support the requested mode.
+    // Minimized windows only remember the mode, it's applied once they get a valid size again
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) {
+        let resolution = self.swapchain.get_resolution();
+        if self.swapchain.minimized || resolution.0 == 0 || resolution.1 == 0 {
+            self.swapchain.config.present_mode = mode;
+            return;
+        }
+        self.swapchain = Swapchain::new()
+            .new_resolution(self.swapchain.get_resolution())
+            .new_present_mode(mode)
+            .build(&self.adapter, &self.device, self.surface.as_ref());
+    }
+
+    // Returns None when there is nothing to render to, e.g. while the window is minimized
+    pub fn get_new_frame(&self) -> Option<(Frame<'_>, wgpu::TextureView, &wgpu::TextureView)> {
+        if self.swapchain.minimized {
+            return None;
+        }
+        let frame = if let Some(surface) = self.surface.as_ref() {
+            let surface_texture = match surface.get_current_texture() {
+                Ok(surface_texture) => surface_texture,
+                Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                    surface.configure(&self.device, &self.swapchain.config);
+                    // skip the frame if the surface is still unusable after reconfiguring
+                    surface.get_current_texture().ok()?
+                }
+                Err(wgpu::SurfaceError::Timeout) => return None,
+                Err(wgpu::SurfaceError::OutOfMemory) => {
+                    panic!("Ran out of memory while acquiring next swap chain texture")
+                }
+            };
+            Frame::Surface(surface_texture)
+        } else {
+            Frame::Offscreen(
+                self.swapchain
+                    .color_texture
+                    .as_ref()
+                    .expect("Headless swapchain is missing its color texture"),
+            )
+        };
         let frame_view = frame
-            .texture
+            .get_texture()
             .create_view(&wgpu::TextureViewDescriptor::default());
-        (frame, frame_view, &self.get_swapchain().depth_view)
+        self.frame_acquired.set(true);
+        Some((frame, frame_view, &self.get_swapchain().depth_view))
     }
 
-    pub fn get_uniform_aligned_buffer_size(&self, value: wgpu::BufferAddress) -> u64 {
-        let uniform_alignment = {
-            let alignment =
-                self.device.limits().min_uniform_buffer_offset_alignment as wgpu::BufferAddress;
-            wgpu::util::align_to(value, alignment)
+    // Whether a frame was acquired since the last call
+    pub fn take_frame_acquired(&self) -> bool {
+        self.frame_acquired.replace(false)
+    }
+
+    // Copies an Rgba8/Bgra8 texture into a mappable buffer and returns its content as an RGBA8 image,
+    // None for other formats
+    pub fn read_texture(&self, texture: &wgpu::Texture) -> Option<image::RgbaImage> {
+        let swap_red_blue = match texture.format() {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => {
+                println!(
+                    "Reading back textures of format {:?} is not supported",
+                    format
+                );
+                return None;
+            }
         };
-        uniform_alignment
+        let (width, height) = (texture.width(), texture.height());
+        let unpadded_bytes_per_row = width * 4;
+        let padded_bytes_per_row =
+            wgpu::util::align_to(unpadded_bytes_per_row, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback_buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("readback_buffer"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = readback_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).unwrap();
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .unwrap()
+            .expect("Failed to map the readback buffer");
+
+        let mut pixels: Vec<u8> = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let padded_data = buffer_slice.get_mapped_range();
+            for row in padded_data.chunks(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        readback_buffer.unmap();
+        if swap_red_blue {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        image::RgbaImage::from_raw(width, height, pixels)
+    }
+
+    // Copies a buffer into a mappable buffer and returns its content, the buffer needs COPY_SRC usage
+    pub fn read_buffer(&self, buffer: &wgpu::Buffer) -> Vec<u8> {
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback_buffer"),
+            size: buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("readback_buffer"),
+            });
+        encoder.copy_buffer_to_buffer(buffer, 0, &readback_buffer, 0, buffer.size());
+        self.queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = readback_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).unwrap();
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .unwrap()
+            .expect("Failed to map the readback buffer");
+
+        let data = buffer_slice.get_mapped_range().to_vec();
+        readback_buffer.unmap();
+        data
+    }
+
+    // Returns the content of the offscreen color target, only available when running headless
+    pub fn read_frame(&self) -> Option<image::RgbaImage> {
+        self.swapchain
+            .color_texture
+            .as_ref()
+            .and_then(|texture| self.read_texture(texture))
+    }
+
+    pub fn get_uniform_aligned_buffer_size(&self, value: wgpu::BufferAddress) -> u64 {
+        let alignment =
+            self.device.limits().min_uniform_buffer_offset_alignment as wgpu::BufferAddress;
+        wgpu::util::align_to(value, alignment)
     }
 
     pub fn get_storage_aligned_buffer_size(&self, value: wgpu::BufferAddress) -> u64 {
-        let storage_alignment = {
-            let alignment =
-                self.device.limits().min_storage_buffer_offset_alignment as wgpu::BufferAddress;
-            wgpu::util::align_to(value, alignment)
-        };
-        storage_alignment
+        let alignment =
+            self.device.limits().min_storage_buffer_offset_alignment as wgpu::BufferAddress;
+        wgpu::util::align_to(value, alignment)
     }
 }
 
@@ -149,18 +377,49 @@ impl SwapchainBuilder {
         &mut self,
         adapter: &wgpu::Adapter,
         device: &wgpu::Device,
-        surface: &wgpu::Surface,
+        surface: Option<&wgpu::Surface>,
     ) -> Swapchain {
-        let swapchain_capabilities = surface.get_capabilities(adapter);
-        let swapchain_format = swapchain_capabilities.formats[0];
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: swapchain_format,
-            width: self.resolution.0,
-            height: self.resolution.1,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: swapchain_capabilities.alpha_modes[0],
-            view_formats: vec![],
+        let config = if let Some(surface) = surface {
+            let swapchain_capabilities = surface.get_capabilities(adapter);
+            let mut usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
+            // needed to read back frames for screenshots, GL surfaces can't be used as a copy source
+            if matches!(
+                adapter.get_info().backend,
+                wgpu::Backend::Vulkan | wgpu::Backend::Metal | wgpu::Backend::Dx12
+            ) {
+                usage |= wgpu::TextureUsages::COPY_SRC;
+            }
+            wgpu::SurfaceConfiguration {
+                usage,
+                format: swapchain_capabilities.formats[0],
+                width: self.resolution.0,
+                height: self.resolution.1,
+                present_mode: if swapchain_capabilities
+                    .present_modes
+                    .contains(&self.present_mode)
+                {
+                    self.present_mode
+                } else {
+                    println!(
+                        "Present mode {:?} is not supported by the surface, falling back to Fifo",
+                        self.present_mode
+                    );
+                    wgpu::PresentMode::Fifo
+                },
+                alpha_mode: swapchain_capabilities.alpha_modes[0],
+                view_formats: vec![],
+            }
+        } else {
+            // headless renderers have no surface to query, so pick a format every adapter can render to
+            wgpu::SurfaceConfiguration {
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                width: self.resolution.0,
+                height: self.resolution.1,
+                present_mode: self.present_mode,
+                alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+                view_formats: vec![],
+            }
         };
         let depth_format = wgpu::TextureFormat::Depth32Float;
         let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -180,11 +439,31 @@ impl SwapchainBuilder {
 
         let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        surface.configure(&device, &config);
+        let color_texture = if let Some(surface) = surface {
+            surface.configure(device, &config);
+            None
+        } else {
+            Some(device.create_texture(&wgpu::TextureDescriptor {
+                size: wgpu::Extent3d {
+                    width: config.width,
+                    height: config.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: config.format,
+                usage: config.usage,
+                label: Some("Swapchain offscreen color texture"),
+                view_formats: &[],
+            }))
+        };
         Swapchain {
-            config: config,
-            depth_view: depth_view,
-            depth_format: depth_format,
+            config,
+            depth_view,
+            depth_format,
+            color_texture,
+            minimized: false,
         }
     }
 }
@@ -192,6 +471,9 @@ pub struct Swapchain {
     config: wgpu::SurfaceConfiguration,
     depth_view: wgpu::TextureView,
     depth_format: wgpu::TextureFormat,
+    // offscreen render target used in place of the surface when running headless
+    color_texture: Option<wgpu::Texture>,
+    minimized: bool,
 }
 
 impl Swapchain {
@@ -216,6 +498,14 @@ impl Swapchain {
     pub fn get_depth_format(&self) -> wgpu::TextureFormat {
         self.depth_format
     }
+
+    pub fn get_present_mode(&self) -> wgpu::PresentMode {
+        self.config.present_mode
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
 }
 
 #[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
@@ -225,6 +515,8 @@ pub struct RenderPipelineSettings {
     pub depth_testing: bool,
     pub depth_write_enabled: bool,
     pub depth_compare: wgpu::CompareFunction,
+    // has to match the depth attachment of the passes the pipeline is used in
+    pub depth_format: wgpu::TextureFormat,
 }
 
 impl Default for RenderPipelineSettings {
@@ -235,24 +527,221 @@ impl Default for RenderPipelineSettings {
             depth_testing: false,
             depth_write_enabled: true,
             depth_compare: wgpu::CompareFunction::LessEqual,
+            depth_format: wgpu::TextureFormat::Depth32Float,
         }
     }
 }
 
+// capacity of the engine's mesh_buffer
+pub const MAX_MESH_INSTANCES: u64 = 10000;
+// size of the mesh_buffer window a single draw can see through its dynamic offset
+pub const MAX_INSTANCES_PER_DRAW: u64 = 1024;
+
+// the size has to divide the storage buffer offset alignment so that draws start on a slot
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 pub struct GPUMesh {
     pub model_mx: [f32; 16],
+    // inverse transpose of the model matrix, stored as three padded columns like a WGSL mat3x3
+    pub normal_mx: [f32; 12],
+    // x is 1 when the instance receives shadows, yzw are unused
+    pub flags: [u32; 4],
 }
 
 impl GPUMesh {
     pub fn new() -> GPUMesh {
-        let model_mx = glam::Mat4::IDENTITY;
+        GPUMesh::from_matrix(&glam::Mat4::IDENTITY)
+    }
+    pub fn from_matrix(model_mx: &glam::Mat4) -> GPUMesh {
+        let normal_mx = glam::Mat3::from_mat4(*model_mx).inverse().transpose();
+        let mut normal_columns = [0.0; 12];
+        for (i, column) in [normal_mx.x_axis, normal_mx.y_axis, normal_mx.z_axis]
+            .iter()
+            .enumerate()
+        {
+            normal_columns[i * 4..i * 4 + 3].copy_from_slice(&column.to_array());
+        }
         GPUMesh {
-            model_mx: model_mx.as_ref().clone(),
+            model_mx: model_mx.to_cols_array(),
+            normal_mx: normal_columns,
+            flags: [1, 0, 0, 0],
+        }
+    }
+    pub fn set_receive_shadows(&mut self, receive_shadows: bool) {
+        self.flags[0] = receive_shadows as u32;
+    }
+    pub fn get_size() -> u64 {
+        mem::size_of::<Self>() as u64
+    }
+}
+
+// per frame camera data, written by the engine from the current Camera3D
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct GPUCamera {
+    pub view_proj_mx: [f32; 16],
+    pub view_mx: [f32; 16],
+    pub proj_mx: [f32; 16],
+    // w is unused
+    pub position: [f32; 4],
+}
+
+impl GPUCamera {
+    pub fn new() -> GPUCamera {
+        GPUCamera {
+            view_proj_mx: glam::Mat4::IDENTITY.to_cols_array(),
+            view_mx: glam::Mat4::IDENTITY.to_cols_array(),
+            proj_mx: glam::Mat4::IDENTITY.to_cols_array(),
+            position: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+    pub fn from_matrices(view_mx: &glam::Mat4, proj_mx: &glam::Mat4) -> GPUCamera {
+        GPUCamera {
+            view_proj_mx: (*proj_mx * *view_mx).to_cols_array(),
+            view_mx: view_mx.to_cols_array(),
+            proj_mx: proj_mx.to_cols_array(),
+            position: view_mx.inverse().w_axis.to_array(),
+        }
+    }
+    pub fn get_size() -> u64 {
+        mem::size_of::<Self>() as u64
+    }
+}
+
+pub const MAX_LIGHTS: u64 = 256;
+// id of the built-in pipeline that shades meshes with the scene's lights
+pub const LIT_PIPELINE: &str = "lit";
+// id of the built-in metallic-roughness pipeline
+pub const PBR_PIPELINE: &str = "pbr";
+// id of the built-in pipeline that draws the environment behind the scene
+pub const SKYBOX_PIPELINE: &str = "skybox";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightType {
+    Directional = 0,
+    Point = 1,
+    Spot = 2,
+}
+
+// one light inside light_buffer, the buffer starts with a 16 byte header holding the light count
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct GPULight {
+    // w is the LightType
+    pub position: [f32; 4],
+    // w is the range
+    pub direction: [f32; 4],
+    // w is the intensity
+    pub color: [f32; 4],
+    // cosines of the inner and outer spot angles, the first shadow map layer (-1 without shadows)
+    // and the amount of layers the light uses
+    pub spot: [f32; 4],
+}
+
+impl GPULight {
+    pub fn new_directional(direction: glam::Vec3, color: glam::Vec3, intensity: f32) -> GPULight {
+        GPULight {
+            position: [0.0, 0.0, 0.0, LightType::Directional as u32 as f32],
+            direction: direction.extend(0.0).to_array(),
+            color: color.extend(intensity).to_array(),
+            spot: [0.0, 0.0, -1.0, 0.0],
+        }
+    }
+    pub fn new_point(
+        position: glam::Vec3,
+        color: glam::Vec3,
+        intensity: f32,
+        range: f32,
+    ) -> GPULight {
+        GPULight {
+            position: position.extend(LightType::Point as u32 as f32).to_array(),
+            direction: [0.0, 0.0, 0.0, range],
+            color: color.extend(intensity).to_array(),
+            spot: [0.0, 0.0, -1.0, 0.0],
+        }
+    }
+    // angles are in degrees
+    pub fn new_spot(
+        position: glam::Vec3,
+        direction: glam::Vec3,
+        color: glam::Vec3,
+        intensity: f32,
+        range: f32,
+        (inner_angle, outer_angle): (f32, f32),
+    ) -> GPULight {
+        GPULight {
+            position: position.extend(LightType::Spot as u32 as f32).to_array(),
+            direction: direction.extend(range).to_array(),
+            color: color.extend(intensity).to_array(),
+            spot: [
+                inner_angle.to_radians().cos(),
+                outer_angle.to_radians().cos(),
+                -1.0,
+                0.0,
+            ],
+        }
+    }
+    // shadow map layers are assigned by the engine while it collects the lights
+    pub fn set_shadow_layers(&mut self, first_layer: u32, layer_count: u32) {
+        self.spot[2] = first_layer as f32;
+        self.spot[3] = layer_count as f32;
+    }
+    pub fn get_size() -> u64 {
+        mem::size_of::<Self>() as u64
+    }
+}
+
+// layers of the engine's shadow map array, each shadow casting spot light uses one
+// and directional lights one per cascade
+pub const MAX_SHADOW_MAPS: u64 = 8;
+pub const MAX_SHADOW_CASCADES: u32 = 4;
+pub const SHADOW_MAP_SIZE: u32 = 1024;
+// id of the built-in depth only pipeline the shadow maps are rendered with
+pub const SHADOW_PIPELINE: &str = "shadow";
+
+// light space data of one shadow map layer inside shadow_buffer
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct GPUShadow {
+    pub view_proj_mx: [f32; 16],
+    // x is the view space distance where the cascade ends, y is the normal offset
+    // in world units used against acne, zw are unused
+    pub params: [f32; 4],
+}
+
+impl GPUShadow {
+    pub fn new(view_proj_mx: &glam::Mat4, split_distance: f32, normal_offset: f32) -> GPUShadow {
+        GPUShadow {
+            view_proj_mx: view_proj_mx.to_cols_array(),
+            params: [split_distance, normal_offset, 0.0, 0.0],
+        }
+    }
+    pub fn get_size() -> u64 {
+        mem::size_of::<Self>() as u64
+    }
+}
+
+// image based lighting settings inside environment_buffer
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct GPUEnvironment {
+    pub intensity: f32,
+    pub prefiltered_mip_count: f32,
+    // 0 until an environment has been loaded, shaders fall back to a constant ambient term
+    pub enabled: u32,
+    pub padding: u32,
+}
+
+impl GPUEnvironment {
+    pub fn new() -> GPUEnvironment {
+        GPUEnvironment {
+            intensity: 1.0,
+            prefiltered_mip_count: 1.0,
+            enabled: 0,
+            padding: 0,
         }
     }
     pub fn get_size() -> u64 {
-        return mem::size_of::<Self>() as u64;
+        mem::size_of::<Self>() as u64
     }
 }
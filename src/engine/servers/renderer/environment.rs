@@ -0,0 +1,344 @@
+use std::mem;
+
+use bytemuck::{Pod, Zeroable};
+
+use super::resources::{
+    Buffer, CommandBuffer, RenderPassBuilder, RenderPipeline, Sampler, Texture,
+};
+
+// engine ids of the image based lighting resources
+pub const ENVIRONMENT_TEXTURE: &str = "environment_map";
+pub const IRRADIANCE_TEXTURE: &str = "irradiance_map";
+pub const PREFILTERED_TEXTURE: &str = "prefiltered_map";
+pub const BRDF_LUT_TEXTURE: &str = "brdf_lut";
+pub const ENVIRONMENT_SAMPLER: &str = "environment_sampler";
+
+pub const ENVIRONMENT_SIZE: u32 = 256;
+pub const IRRADIANCE_SIZE: u32 = 32;
+pub const PREFILTERED_SIZE: u32 = 128;
+// roughness goes from 0 at mip 0 to 1 at the last mip
+pub const PREFILTERED_MIP_COUNT: u32 = 5;
+pub const BRDF_LUT_SIZE: u32 = 128;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+const PREFILTER_SAMPLE_COUNT: u32 = 64;
+const BRDF_LUT_SAMPLE_COUNT: u32 = 256;
+
+// values of one draw, every draw reads its own slot through the dynamic offset
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct BakeParams {
+    face: u32,
+    roughness: f32,
+    source_resolution: f32,
+    sample_count: u32,
+}
+
+// Cube maps and lookup table the pbr shader and the skybox read, baked from an
+// equirectangular HDR image with a few render passes
+pub struct EnvironmentMaps {
+    pub environment: Texture,
+    pub irradiance: Texture,
+    pub prefiltered: Texture,
+    pub brdf_lut: Texture,
+    pub sampler: Sampler,
+}
+
+impl EnvironmentMaps {
+    // black 1x1 maps so that pipelines can be bound before an environment is loaded
+    pub fn new_empty(device: &wgpu::Device, queue: &wgpu::Queue) -> EnvironmentMaps {
+        let black_cube = |id: &str| {
+            Texture::new(id)
+                .new_array_layers(6)
+                .new_format(FORMAT)
+                .new_view_dimension(wgpu::TextureViewDimension::Cube)
+                .new_content(&[0; 6 * 8])
+                .build(device, queue)
+        };
+        EnvironmentMaps {
+            environment: black_cube(ENVIRONMENT_TEXTURE),
+            irradiance: black_cube(IRRADIANCE_TEXTURE),
+            prefiltered: black_cube(PREFILTERED_TEXTURE),
+            brdf_lut: Texture::new(BRDF_LUT_TEXTURE)
+                .new_format(FORMAT)
+                .new_content(&[0; 8])
+                .build(device, queue),
+            sampler: new_sampler(device),
+        }
+    }
+
+    pub fn from_equirect(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &image::Rgba32FImage,
+    ) -> EnvironmentMaps {
+        let half_floats: Vec<u16> = image.as_raw().iter().map(|v| f32_to_f16(*v)).collect();
+        let equirect = Texture::new("environment equirect")
+            .new_size(image.dimensions())
+            .new_format(FORMAT)
+            .new_content(bytemuck::cast_slice(&half_floats))
+            .build(device, queue);
+        let environment_mip_count =
+            super::resources::get_mip_level_count((ENVIRONMENT_SIZE, ENVIRONMENT_SIZE));
+        let environment = new_cube(
+            device,
+            queue,
+            ENVIRONMENT_TEXTURE,
+            ENVIRONMENT_SIZE,
+            environment_mip_count,
+        );
+        let irradiance = new_cube(device, queue, IRRADIANCE_TEXTURE, IRRADIANCE_SIZE, 1);
+        let prefiltered = new_cube(
+            device,
+            queue,
+            PREFILTERED_TEXTURE,
+            PREFILTERED_SIZE,
+            PREFILTERED_MIP_COUNT,
+        );
+        let brdf_lut = Texture::new(BRDF_LUT_TEXTURE)
+            .new_size((BRDF_LUT_SIZE, BRDF_LUT_SIZE))
+            .new_format(FORMAT)
+            .new_usage(
+                wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            )
+            .build(device, queue);
+        let sampler = new_sampler(device);
+
+        let mut baker = Baker::new(device, queue);
+        let mut command_buffer = CommandBuffer::new_command_buffer(device, "environment_bake");
+        let source_resolution = ENVIRONMENT_SIZE as f32;
+
+        let mut pipeline = baker.new_pipeline(
+            "equirect_to_cube",
+            include_str!("shaders/equirect_to_cube.wgsl"),
+        );
+        baker.bind(
+            &mut pipeline,
+            &[equirect.get_binding(), sampler.get_binding()],
+        );
+        for face in 0..6 {
+            let target = environment.create_layer_view(face, 0);
+            baker.draw(&mut command_buffer, &pipeline, &target, face, 0.0, 0.0, 0);
+        }
+
+        let mut pipeline = baker.new_pipeline(
+            "downsample_cube",
+            include_str!("shaders/downsample_cube.wgsl"),
+        );
+        for mip_level in 1..environment_mip_count {
+            let source =
+                environment.create_mip_view(mip_level - 1, wgpu::TextureViewDimension::Cube);
+            baker.bind(
+                &mut pipeline,
+                &[
+                    wgpu::BindingResource::TextureView(&source),
+                    sampler.get_binding(),
+                ],
+            );
+            for face in 0..6 {
+                let target = environment.create_layer_view(face, mip_level);
+                baker.draw(&mut command_buffer, &pipeline, &target, face, 0.0, 0.0, 0);
+            }
+        }
+
+        let mut pipeline =
+            baker.new_pipeline("irradiance", include_str!("shaders/irradiance.wgsl"));
+        baker.bind(
+            &mut pipeline,
+            &[environment.get_binding(), sampler.get_binding()],
+        );
+        for face in 0..6 {
+            let target = irradiance.create_layer_view(face, 0);
+            baker.draw(
+                &mut command_buffer,
+                &pipeline,
+                &target,
+                face,
+                0.0,
+                source_resolution,
+                0,
+            );
+        }
+
+        let mut pipeline = baker.new_pipeline("prefilter", include_str!("shaders/prefilter.wgsl"));
+        baker.bind(
+            &mut pipeline,
+            &[environment.get_binding(), sampler.get_binding()],
+        );
+        for mip_level in 0..PREFILTERED_MIP_COUNT {
+            let roughness = mip_level as f32 / (PREFILTERED_MIP_COUNT - 1) as f32;
+            for face in 0..6 {
+                let target = prefiltered.create_layer_view(face, mip_level);
+                baker.draw(
+                    &mut command_buffer,
+                    &pipeline,
+                    &target,
+                    face,
+                    roughness,
+                    source_resolution,
+                    PREFILTER_SAMPLE_COUNT,
+                );
+            }
+        }
+
+        let mut pipeline = baker.new_pipeline("brdf_lut", include_str!("shaders/brdf_lut.wgsl"));
+        baker.bind(&mut pipeline, &[]);
+        baker.draw(
+            &mut command_buffer,
+            &pipeline,
+            brdf_lut.get_view(),
+            0,
+            0.0,
+            0.0,
+            BRDF_LUT_SAMPLE_COUNT,
+        );
+
+        command_buffer.finish_command_buffer(queue);
+        EnvironmentMaps {
+            environment,
+            irradiance,
+            prefiltered,
+            brdf_lut,
+            sampler,
+        }
+    }
+}
+
+fn new_sampler(device: &wgpu::Device) -> Sampler {
+    Sampler::new(ENVIRONMENT_SAMPLER)
+        .new_address_mode(wgpu::AddressMode::ClampToEdge)
+        .new_filter(wgpu::FilterMode::Linear)
+        .new_mipmap_filter(wgpu::FilterMode::Linear)
+        .build(device)
+}
+
+fn new_cube(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    id: &str,
+    size: u32,
+    mip_level_count: u32,
+) -> Texture {
+    Texture::new(id)
+        .new_size((size, size))
+        .new_array_layers(6)
+        .new_format(FORMAT)
+        .new_usage(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT)
+        .new_mip_level_count(mip_level_count)
+        .new_view_dimension(wgpu::TextureViewDimension::Cube)
+        .build(device, queue)
+}
+
+// records fullscreen draws into single cube faces, their parameters are written to
+// consecutive slots of one uniform buffer before the commands are submitted
+struct Baker<'a> {
+    device: &'a wgpu::Device,
+    queue: &'a wgpu::Queue,
+    params_buffer: Buffer,
+    slot_size: u64,
+    next_slot: u64,
+}
+
+impl<'a> Baker<'a> {
+    // one slot per face of every mip that is rendered
+    const MAX_DRAWS: u64 = 128;
+
+    fn new(device: &'a wgpu::Device, queue: &'a wgpu::Queue) -> Baker<'a> {
+        let slot_size = wgpu::util::align_to(
+            mem::size_of::<BakeParams>() as u64,
+            device.limits().min_uniform_buffer_offset_alignment as u64,
+        );
+        let params_buffer = Buffer::new("environment_bake_params")
+            .new_size(slot_size * Self::MAX_DRAWS)
+            .new_usage(wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST)
+            .new_binding_size(mem::size_of::<BakeParams>() as u64)
+            .build(device);
+        Baker {
+            device,
+            queue,
+            params_buffer,
+            slot_size,
+            next_slot: 0,
+        }
+    }
+
+    fn new_pipeline(&self, id: &str, source: &str) -> RenderPipeline {
+        RenderPipeline::new()
+            .new_shader(&format!(
+                "{}{}",
+                include_str!("shaders/cubemap.wgsl"),
+                source
+            ))
+            .new_target(FORMAT.into())
+            .build(id, self.device)
+    }
+
+    // the params buffer is bound first, resources follow it
+    fn bind(&self, pipeline: &mut RenderPipeline, resources: &[wgpu::BindingResource]) {
+        let mut binding_resources = vec![self.params_buffer.get_binding()];
+        binding_resources.extend(resources.iter().cloned());
+        pipeline.bind_resource(self.device, 0, &binding_resources);
+        pipeline.set_resource_ids(0, &["environment_bake_params"]);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw(
+        &mut self,
+        command_buffer: &mut CommandBuffer,
+        pipeline: &RenderPipeline,
+        target: &wgpu::TextureView,
+        face: u32,
+        roughness: f32,
+        source_resolution: f32,
+        sample_count: u32,
+    ) {
+        let offset = self.next_slot * self.slot_size;
+        self.next_slot += 1;
+        self.params_buffer.write(
+            self.queue,
+            offset,
+            bytemuck::bytes_of(&BakeParams {
+                face,
+                roughness,
+                source_resolution,
+                sample_count,
+            }),
+        );
+        let Some(bind_group) = pipeline.get_bind_group(0) else {
+            return;
+        };
+        let mut pass = RenderPassBuilder::new(pipeline.get_id())
+            .color_attachment(target, [0.0, 0.0, 0.0, 1.0])
+            .build(command_buffer);
+        pass.set_pipeline(pipeline.get_native_pipeline());
+        pass.set_bind_group(
+            0,
+            bind_group,
+            &pipeline.get_dynamic_offsets(0, |_| offset as u32),
+        );
+        pass.draw(0..3, 0..1);
+    }
+}
+
+// HDR values are stored as half floats, larger values are clamped to the largest half float
+fn f32_to_f16(value: f32) -> u16 {
+    if value.is_nan() {
+        return 0x7e00;
+    }
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent >= 31 {
+        sign | 0x7bff
+    } else if exponent <= 0 {
+        if exponent < -10 {
+            sign
+        } else {
+            let mantissa = (mantissa | 0x80_0000) >> (14 - exponent);
+            sign | mantissa as u16
+        }
+    } else {
+        sign | ((exponent as u16) << 10) | (mantissa >> 13) as u16
+    }
+}
@@ -0,0 +1,385 @@
+use super::resources::{Buffer, RenderPipeline};
+use super::{LIT_PIPELINE, PBR_PIPELINE};
+
+// Values a material passes to its shader, they are packed in the order they were added
+// so they have to match the field order of the shader's material struct
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MaterialParameter {
+    Float(f32),
+    Int(i32),
+    UInt(u32),
+    Vec2(glam::Vec2),
+    Vec3(glam::Vec3),
+    Vec4(glam::Vec4),
+    Mat4(glam::Mat4),
+}
+
+impl MaterialParameter {
+    // alignment and size follow the WGSL uniform address space layout rules
+    pub fn get_alignment(&self) -> u64 {
+        match self {
+            MaterialParameter::Float(_)
+            | MaterialParameter::Int(_)
+            | MaterialParameter::UInt(_) => 4,
+            MaterialParameter::Vec2(_) => 8,
+            MaterialParameter::Vec3(_)
+            | MaterialParameter::Vec4(_)
+            | MaterialParameter::Mat4(_) => 16,
+        }
+    }
+
+    pub fn get_size(&self) -> u64 {
+        match self {
+            MaterialParameter::Float(_)
+            | MaterialParameter::Int(_)
+            | MaterialParameter::UInt(_) => 4,
+            MaterialParameter::Vec2(_) => 8,
+            MaterialParameter::Vec3(_) => 12,
+            MaterialParameter::Vec4(_) => 16,
+            MaterialParameter::Mat4(_) => 64,
+        }
+    }
+
+    fn write_bytes(&self, data: &mut [u8]) {
+        match self {
+            MaterialParameter::Float(value) => data.copy_from_slice(bytemuck::bytes_of(value)),
+            MaterialParameter::Int(value) => data.copy_from_slice(bytemuck::bytes_of(value)),
+            MaterialParameter::UInt(value) => data.copy_from_slice(bytemuck::bytes_of(value)),
+            MaterialParameter::Vec2(value) => {
+                data.copy_from_slice(bytemuck::cast_slice(&value.to_array()))
+            }
+            MaterialParameter::Vec3(value) => {
+                data.copy_from_slice(bytemuck::cast_slice(&value.to_array()))
+            }
+            MaterialParameter::Vec4(value) => {
+                data.copy_from_slice(bytemuck::cast_slice(&value.to_array()))
+            }
+            MaterialParameter::Mat4(value) => {
+                data.copy_from_slice(bytemuck::cast_slice(&value.to_cols_array()))
+            }
+        }
+    }
+}
+
+// Packs the parameters like a WGSL struct declared with the same fields, the struct size is rounded up to 16
+pub fn pack_material_parameters(parameters: &[(String, MaterialParameter)]) -> Vec<u8> {
+    let mut offset: u64 = 0;
+    let mut offsets: Vec<u64> = Vec::new();
+    for (_, parameter) in parameters {
+        offset = wgpu::util::align_to(offset, parameter.get_alignment());
+        offsets.push(offset);
+        offset += parameter.get_size();
+    }
+    let mut data = vec![0u8; wgpu::util::align_to(offset.max(16), 16) as usize];
+    for ((_, parameter), offset) in parameters.iter().zip(offsets) {
+        let start = offset as usize;
+        parameter.write_bytes(&mut data[start..start + parameter.get_size() as usize]);
+    }
+    data
+}
+
+// parameters of the built-in lit shader in the order it declares them
+pub fn get_lit_parameters(color: glam::Vec4) -> Vec<(String, MaterialParameter)> {
+    vec![
+        ("color".to_string(), MaterialParameter::Vec4(color)),
+        ("ambient".to_string(), MaterialParameter::Float(0.05)),
+        ("specular".to_string(), MaterialParameter::Float(0.5)),
+        ("shininess".to_string(), MaterialParameter::Float(32.0)),
+    ]
+}
+
+// default parameters of the built-in pbr shader in the order it declares them
+pub fn get_pbr_parameters() -> Vec<(String, MaterialParameter)> {
+    vec![
+        (
+            "base_color".to_string(),
+            MaterialParameter::Vec4(glam::Vec4::ONE),
+        ),
+        (
+            "emissive".to_string(),
+            MaterialParameter::Vec3(glam::Vec3::ZERO),
+        ),
+        ("metallic".to_string(), MaterialParameter::Float(0.0)),
+        ("roughness".to_string(), MaterialParameter::Float(1.0)),
+        ("normal_scale".to_string(), MaterialParameter::Float(1.0)),
+        (
+            "occlusion_strength".to_string(),
+            MaterialParameter::Float(1.0),
+        ),
+    ]
+}
+
+// textures the engine binds for maps a pbr material doesn't set, white leaves the
+// factors unchanged and the flat normal keeps the mesh normal
+pub const PBR_DEFAULT_WHITE_TEXTURE: &str = "pbr_default_white";
+pub const PBR_DEFAULT_NORMAL_TEXTURE: &str = "pbr_default_normal";
+pub const PBR_DEFAULT_SAMPLER: &str = "pbr_default_sampler";
+
+// Metallic-roughness material like glTF describes it, every map is multiplied with its factor.
+// Base color and emissive maps are expected in sRGB, the other maps in linear formats
+pub struct PbrMaterialBuilder {
+    id: String,
+    parameters: Vec<(String, MaterialParameter)>,
+    base_color_texture: String,
+    metallic_roughness_texture: String,
+    normal_texture: String,
+    occlusion_texture: String,
+    emissive_texture: String,
+    sampler: String,
+}
+
+impl PbrMaterialBuilder {
+    fn set_parameter(&mut self, name: &str, value: MaterialParameter) {
+        if let Some(parameter) = self.parameters.iter_mut().find(|(n, _)| n == name) {
+            parameter.1 = value;
+        }
+    }
+    pub fn new_base_color(&mut self, base_color: glam::Vec4) -> &mut Self {
+        self.set_parameter("base_color", MaterialParameter::Vec4(base_color));
+        self
+    }
+    pub fn new_emissive(&mut self, emissive: glam::Vec3) -> &mut Self {
+        self.set_parameter("emissive", MaterialParameter::Vec3(emissive));
+        self
+    }
+    pub fn new_metallic(&mut self, metallic: f32) -> &mut Self {
+        self.set_parameter("metallic", MaterialParameter::Float(metallic));
+        self
+    }
+    pub fn new_roughness(&mut self, roughness: f32) -> &mut Self {
+        self.set_parameter("roughness", MaterialParameter::Float(roughness));
+        self
+    }
+    pub fn new_normal_scale(&mut self, normal_scale: f32) -> &mut Self {
+        self.set_parameter("normal_scale", MaterialParameter::Float(normal_scale));
+        self
+    }
+    pub fn new_occlusion_strength(&mut self, occlusion_strength: f32) -> &mut Self {
+        self.set_parameter(
+            "occlusion_strength",
+            MaterialParameter::Float(occlusion_strength),
+        );
+        self
+    }
+    // textures are engine ids, they have to be set before the material
+    pub fn new_base_color_texture(&mut self, texture_id: &str) -> &mut Self {
+        self.base_color_texture = texture_id.to_string();
+        self
+    }
+    // roughness is read from the green and metallic from the blue channel
+    pub fn new_metallic_roughness_texture(&mut self, texture_id: &str) -> &mut Self {
+        self.metallic_roughness_texture = texture_id.to_string();
+        self
+    }
+    pub fn new_normal_texture(&mut self, texture_id: &str) -> &mut Self {
+        self.normal_texture = texture_id.to_string();
+        self
+    }
+    pub fn new_occlusion_texture(&mut self, texture_id: &str) -> &mut Self {
+        self.occlusion_texture = texture_id.to_string();
+        self
+    }
+    pub fn new_emissive_texture(&mut self, texture_id: &str) -> &mut Self {
+        self.emissive_texture = texture_id.to_string();
+        self
+    }
+    pub fn new_sampler(&mut self, sampler_id: &str) -> &mut Self {
+        self.sampler = sampler_id.to_string();
+        self
+    }
+    pub fn build(&mut self, device: &wgpu::Device) -> Material {
+        MaterialBuilder {
+            id: self.id.clone(),
+            pipeline_id: PBR_PIPELINE.to_string(),
+            parameters: self.parameters.clone(),
+            resource_ids: vec![
+                self.base_color_texture.clone(),
+                self.metallic_roughness_texture.clone(),
+                self.normal_texture.clone(),
+                self.occlusion_texture.clone(),
+                self.emissive_texture.clone(),
+                self.sampler.clone(),
+            ],
+        }
+        .build(device)
+    }
+}
+
+pub struct MaterialBuilder {
+    id: String,
+    pipeline_id: String,
+    parameters: Vec<(String, MaterialParameter)>,
+    resource_ids: Vec<String>,
+}
+
+impl MaterialBuilder {
+    pub fn new_pipeline(&mut self, pipeline_id: &str) -> &mut Self {
+        self.pipeline_id = pipeline_id.to_string();
+        self
+    }
+    pub fn new_parameter(&mut self, name: &str, value: MaterialParameter) -> &mut Self {
+        self.parameters.push((name.to_string(), value));
+        self
+    }
+    // textures and samplers are bound by engine id after the parameter buffer, in the order they were added
+    pub fn new_texture(&mut self, texture_id: &str) -> &mut Self {
+        self.resource_ids.push(texture_id.to_string());
+        self
+    }
+    pub fn new_sampler(&mut self, sampler_id: &str) -> &mut Self {
+        self.resource_ids.push(sampler_id.to_string());
+        self
+    }
+    pub fn build(&mut self, device: &wgpu::Device) -> Material {
+        let parameter_buffer = if self.parameters.is_empty() {
+            None
+        } else {
+            Some(
+                Buffer::new(format!("{} material_buffer", self.id).as_str())
+                    .new_content(&pack_material_parameters(&self.parameters))
+                    .new_usage(wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST)
+                    .build(device),
+            )
+        };
+        Material {
+            id: self.id.clone(),
+            pipeline_id: self.pipeline_id.clone(),
+            parameters: self.parameters.clone(),
+            resource_ids: self.resource_ids.clone(),
+            parameter_buffer,
+            bind_group: None,
+        }
+    }
+}
+
+// A pipeline together with the parameters and textures it is drawn with, bound at the Resource
+// group frequency. Any amount of MeshInstances can reference the same material by its id
+pub struct Material {
+    id: String,
+    pipeline_id: String,
+    parameters: Vec<(String, MaterialParameter)>,
+    resource_ids: Vec<String>,
+    parameter_buffer: Option<Buffer>,
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+impl Material {
+    pub fn new(id: &str) -> MaterialBuilder {
+        MaterialBuilder {
+            id: id.to_string(),
+            pipeline_id: String::new(),
+            parameters: Vec::new(),
+            resource_ids: Vec::new(),
+        }
+    }
+
+    // material for the built-in lit pipeline, the color, ambient, specular and shininess
+    // parameters can be changed later with set_parameter
+    pub fn new_lit(id: &str, color: glam::Vec4) -> MaterialBuilder {
+        MaterialBuilder {
+            id: id.to_string(),
+            pipeline_id: LIT_PIPELINE.to_string(),
+            parameters: get_lit_parameters(color),
+            resource_ids: Vec::new(),
+        }
+    }
+
+    // material for the built-in pbr pipeline, white, fully rough and not metallic by default.
+    // Its parameters can be changed later with set_parameter like any other material
+    pub fn new_pbr(id: &str) -> PbrMaterialBuilder {
+        PbrMaterialBuilder {
+            id: id.to_string(),
+            parameters: get_pbr_parameters(),
+            base_color_texture: PBR_DEFAULT_WHITE_TEXTURE.to_string(),
+            metallic_roughness_texture: PBR_DEFAULT_WHITE_TEXTURE.to_string(),
+            normal_texture: PBR_DEFAULT_NORMAL_TEXTURE.to_string(),
+            occlusion_texture: PBR_DEFAULT_WHITE_TEXTURE.to_string(),
+            emissive_texture: PBR_DEFAULT_WHITE_TEXTURE.to_string(),
+            sampler: PBR_DEFAULT_SAMPLER.to_string(),
+        }
+    }
+
+    pub fn get_id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn get_pipeline_id(&self) -> &str {
+        self.pipeline_id.as_str()
+    }
+
+    pub fn get_parameter(&self, name: &str) -> Option<MaterialParameter> {
+        self.parameters
+            .iter()
+            .find(|(parameter_name, _)| parameter_name == name)
+            .map(|(_, value)| *value)
+    }
+
+    // changing the type of a parameter is not allowed since it would change the struct layout
+    pub fn set_parameter(&mut self, queue: &wgpu::Queue, name: &str, value: MaterialParameter) {
+        let Some(parameter) = self
+            .parameters
+            .iter_mut()
+            .find(|(parameter_name, _)| parameter_name == name)
+        else {
+            println!("material: {} has no parameter: {}", self.id, name);
+            return;
+        };
+        if std::mem::discriminant(&parameter.1) != std::mem::discriminant(&value) {
+            println!(
+                "parameter: {} of material: {} can't change its type",
+                name, self.id
+            );
+            return;
+        }
+        parameter.1 = value;
+        if let Some(buffer) = self.parameter_buffer.as_mut() {
+            buffer.write(queue, 0, &pack_material_parameters(&self.parameters));
+        }
+    }
+
+    pub fn get_resource_ids(&self) -> &[String] {
+        &self.resource_ids
+    }
+
+    pub fn get_parameter_buffer(&self) -> Option<&Buffer> {
+        self.parameter_buffer.as_ref()
+    }
+
+    // the parameter buffer is bound first, resources holds the textures and samplers that follow it
+    pub fn bind_resource(
+        &mut self,
+        device: &wgpu::Device,
+        pipeline: &RenderPipeline,
+        resources: &[wgpu::BindingResource],
+    ) {
+        let group = super::BindingGroupType::Resource as u8;
+        let Some(layout) = pipeline.get_bind_group_layout(group) else {
+            println!(
+                "pipeline: {} has no bind group for materials",
+                pipeline.get_id()
+            );
+            return;
+        };
+        let mut binding_resources: Vec<wgpu::BindingResource> = Vec::new();
+        if let Some(buffer) = self.parameter_buffer.as_ref() {
+            binding_resources.push(buffer.get_binding());
+        }
+        binding_resources.extend(resources.iter().cloned());
+        let mut entries: Vec<wgpu::BindGroupEntry> = Vec::new();
+        for (i, resource) in binding_resources.into_iter().enumerate() {
+            entries.push(wgpu::BindGroupEntry {
+                binding: i as u32,
+                resource,
+            });
+        }
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&self.id),
+            layout,
+            entries: &entries,
+        }));
+    }
+
+    pub fn get_bind_group(&self) -> Option<&wgpu::BindGroup> {
+        self.bind_group.as_ref()
+    }
+}
@@ -0,0 +1,506 @@
+use std::mem;
+
+use bytemuck::{Pod, Zeroable};
+
+use super::render_graph::{
+    RenderGraph, RenderGraphResources, RenderGraphTexture, RenderGraphTextureSize, SWAPCHAIN_COLOR,
+};
+use super::resources::{
+    Buffer, CommandBuffer, RenderPassBuilder, RenderPipeline, Sampler, Texture,
+};
+use super::{SCENE_COLOR, SCENE_COLOR_FORMAT};
+
+// graph textures the effects render into, every effect reads the output of the previous one
+pub const POST_PING: &str = "post_ping";
+pub const POST_PONG: &str = "post_pong";
+// bloom is blurred by downsampling and upsampling again through textures of half, quarter, ..
+// resolution. Every level is its own texture since GL can't sample a single mip through a view
+pub const BLOOM_DOWNSAMPLE: &str = "bloom_downsample";
+pub const BLOOM_UPSAMPLE: &str = "bloom_upsample";
+pub const BLOOM_LEVEL_COUNT: u32 = 6;
+
+// effects run in order, one post process pass can run more than one step per frame
+const MAX_STEPS: u64 = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tonemapper {
+    Aces,
+    Reinhard,
+}
+
+// Full screen effect of the post processing chain. Effects that expect displayable colors
+// (fxaa, vignette and color grading) belong after the tonemapper
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostEffect {
+    // scales the HDR color by 2^stops
+    Exposure {
+        stops: f32,
+    },
+    Tonemap(Tonemapper),
+    // light brighter than the threshold bleeds into its surroundings
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+    },
+    Fxaa,
+    // radius and smoothness are in units of the screen height
+    Vignette {
+        intensity: f32,
+        radius: f32,
+        smoothness: f32,
+    },
+    // lut_texture is the id of a texture loaded with load_linear_texture, see post_color_grading.wgsl
+    // and get_neutral_lut_image() for the layout
+    ColorGrading {
+        lut_texture: String,
+        intensity: f32,
+    },
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GPUPostParams {
+    values: [f32; 4],
+    texel_size: [f32; 2],
+    mode: u32,
+    padding: u32,
+}
+
+impl GPUPostParams {
+    fn new(values: [f32; 4], mode: u32) -> GPUPostParams {
+        GPUPostParams {
+            values,
+            texel_size: [0.0; 2],
+            mode,
+            padding: 0,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum PostTexture {
+    Scene,
+    Ping,
+    Pong,
+    BloomDownsample(u32),
+    BloomUpsample(u32),
+    // engine texture id
+    Engine(String),
+    Output,
+}
+
+// one fullscreen draw, prepare() turns the effects into steps that record() draws
+struct PostStep {
+    pipeline_id: &'static str,
+    params: GPUPostParams,
+    source: PostTexture,
+    extra: Option<PostTexture>,
+    target: PostTexture,
+}
+
+// Ordered chain of effects applied to the HDR scene color before it is presented. The engine
+// draws the scene into SCENE_COLOR, an empty chain copies it to the swapchain unchanged
+pub struct PostProcess {
+    effects: Vec<(String, PostEffect)>,
+    pipelines: Vec<RenderPipeline>,
+    params_buffer: Buffer,
+    slot_size: u64,
+    sampler: Sampler,
+    steps: Vec<PostStep>,
+}
+
+impl PostProcess {
+    pub fn new(device: &wgpu::Device, output_format: wgpu::TextureFormat) -> PostProcess {
+        let slot_size = wgpu::util::align_to(
+            mem::size_of::<GPUPostParams>() as u64,
+            device.limits().min_uniform_buffer_offset_alignment as u64,
+        );
+        let params_buffer = Buffer::new("post_process_params")
+            .new_size(slot_size * MAX_STEPS)
+            .new_usage(wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST)
+            .new_binding_size(mem::size_of::<GPUPostParams>() as u64)
+            .build(device);
+        let sampler = Sampler::new("post_process_sampler")
+            .new_address_mode(wgpu::AddressMode::ClampToEdge)
+            .new_filter(wgpu::FilterMode::Linear)
+            .build(device);
+
+        // effects that can be the last step get a second pipeline writing the swapchain format
+        let mut pipelines = Vec::new();
+        for (id, source, last_step) in [
+            ("copy", include_str!("shaders/post_copy.wgsl"), true),
+            ("exposure", include_str!("shaders/post_exposure.wgsl"), true),
+            ("tonemap", include_str!("shaders/post_tonemap.wgsl"), true),
+            (
+                "bloom_downsample",
+                include_str!("shaders/post_bloom_downsample.wgsl"),
+                false,
+            ),
+            (
+                "bloom_upsample",
+                include_str!("shaders/post_bloom_upsample.wgsl"),
+                false,
+            ),
+            (
+                "bloom_composite",
+                include_str!("shaders/post_bloom_composite.wgsl"),
+                true,
+            ),
+            ("fxaa", include_str!("shaders/post_fxaa.wgsl"), true),
+            ("vignette", include_str!("shaders/post_vignette.wgsl"), true),
+            (
+                "color_grading",
+                include_str!("shaders/post_color_grading.wgsl"),
+                true,
+            ),
+        ] {
+            let shader = format!("{}{}", include_str!("shaders/post.wgsl"), source);
+            pipelines.push(
+                RenderPipeline::new()
+                    .new_shader(&shader)
+                    .new_target(SCENE_COLOR_FORMAT.into())
+                    .build(id, device),
+            );
+            if last_step {
+                pipelines.push(
+                    RenderPipeline::new()
+                        .new_shader(&shader)
+                        .new_target(output_format.into())
+                        .build(&get_output_pipeline_id(id), device),
+                );
+            }
+        }
+        PostProcess {
+            effects: Vec::new(),
+            pipelines,
+            params_buffer,
+            slot_size,
+            sampler,
+            steps: Vec::new(),
+        }
+    }
+
+    // effects with an already registered id are replaced in place
+    pub fn add_effect(&mut self, id: &str, effect: PostEffect) {
+        if let Some(index) = self.effects.iter().position(|(e, _)| e == id) {
+            self.effects[index].1 = effect;
+        } else {
+            self.effects.push((id.to_string(), effect));
+        }
+    }
+
+    // index is clamped to the end of the chain
+    pub fn insert_effect(&mut self, index: usize, id: &str, effect: PostEffect) {
+        self.remove_effect(id);
+        let index = index.min(self.effects.len());
+        self.effects.insert(index, (id.to_string(), effect));
+    }
+
+    pub fn remove_effect(&mut self, id: &str) -> Option<PostEffect> {
+        let index = self.effects.iter().position(|(e, _)| e == id)?;
+        Some(self.effects.remove(index).1)
+    }
+
+    pub fn get_effect(&self, id: &str) -> Option<&PostEffect> {
+        self.effects.iter().find(|(e, _)| e == id).map(|(_, e)| e)
+    }
+
+    pub fn get_effect_mut(&mut self, id: &str) -> Option<&mut PostEffect> {
+        self.effects
+            .iter_mut()
+            .find(|(e, _)| e == id)
+            .map(|(_, e)| e)
+    }
+
+    // effect ids in the order they are applied
+    pub fn get_effect_ids(&self) -> Vec<&str> {
+        self.effects.iter().map(|(id, _)| id.as_str()).collect()
+    }
+
+    pub fn clear_effects(&mut self) {
+        self.effects.clear();
+    }
+
+    // names of the graph textures the steps render into
+    pub fn get_texture_names() -> Vec<String> {
+        let mut names = vec![POST_PING.to_string(), POST_PONG.to_string()];
+        for level in 0..BLOOM_LEVEL_COUNT {
+            names.push(get_bloom_texture_name(BLOOM_DOWNSAMPLE, level));
+        }
+        // the smallest level is only downsampled
+        for level in 0..BLOOM_LEVEL_COUNT - 1 {
+            names.push(get_bloom_texture_name(BLOOM_UPSAMPLE, level));
+        }
+        names
+    }
+
+    pub fn add_textures(render_graph: &mut RenderGraph) {
+        let description = RenderGraphTexture {
+            format: SCENE_COLOR_FORMAT,
+            ..Default::default()
+        };
+        render_graph.add_texture(POST_PING, description);
+        render_graph.add_texture(POST_PONG, description);
+        for level in 0..BLOOM_LEVEL_COUNT {
+            let bloom_description = RenderGraphTexture {
+                size: RenderGraphTextureSize::Swapchain(1, 2 << level),
+                ..description
+            };
+            render_graph.add_texture(
+                &get_bloom_texture_name(BLOOM_DOWNSAMPLE, level),
+                bloom_description,
+            );
+            if level + 1 < BLOOM_LEVEL_COUNT {
+                render_graph.add_texture(
+                    &get_bloom_texture_name(BLOOM_UPSAMPLE, level),
+                    bloom_description,
+                );
+            }
+        }
+    }
+
+    // Turns the effects into steps and writes their parameters, has_texture tells whether
+    // an engine texture such as a color grading LUT exists
+    pub fn prepare<F: Fn(&str) -> bool>(
+        &mut self,
+        queue: &wgpu::Queue,
+        resolution: (u32, u32),
+        has_texture: F,
+    ) {
+        let mut steps: Vec<PostStep> = Vec::new();
+        // output of the previous effect
+        let mut source = PostTexture::Scene;
+        for (id, effect) in self.effects.iter() {
+            let (pipeline_id, params, extra) = match effect {
+                PostEffect::Exposure { stops } => (
+                    "exposure",
+                    GPUPostParams::new([2.0_f32.powf(*stops), 0.0, 0.0, 0.0], 0),
+                    None,
+                ),
+                PostEffect::Tonemap(tonemapper) => {
+                    let mode = match tonemapper {
+                        Tonemapper::Aces => 0,
+                        Tonemapper::Reinhard => 1,
+                    };
+                    ("tonemap", GPUPostParams::new([0.0; 4], mode), None)
+                }
+                PostEffect::Bloom {
+                    threshold,
+                    intensity,
+                } => {
+                    steps.push(PostStep {
+                        pipeline_id: "bloom_downsample",
+                        params: GPUPostParams::new([*threshold, *threshold * 0.5, 0.0, 0.0], 1),
+                        source: source.clone(),
+                        extra: None,
+                        target: PostTexture::BloomDownsample(0),
+                    });
+                    for level in 1..BLOOM_LEVEL_COUNT {
+                        steps.push(PostStep {
+                            pipeline_id: "bloom_downsample",
+                            params: GPUPostParams::new([0.0; 4], 0),
+                            source: PostTexture::BloomDownsample(level - 1),
+                            extra: None,
+                            target: PostTexture::BloomDownsample(level),
+                        });
+                    }
+                    // the smallest level has nothing to add, so the first upsample reads it directly
+                    let mut bloom = PostTexture::BloomDownsample(BLOOM_LEVEL_COUNT - 1);
+                    for level in (0..BLOOM_LEVEL_COUNT - 1).rev() {
+                        steps.push(PostStep {
+                            pipeline_id: "bloom_upsample",
+                            params: GPUPostParams::new([0.0; 4], 0),
+                            source: bloom,
+                            extra: Some(PostTexture::BloomDownsample(level)),
+                            target: PostTexture::BloomUpsample(level),
+                        });
+                        bloom = PostTexture::BloomUpsample(level);
+                    }
+                    (
+                        "bloom_composite",
+                        GPUPostParams::new([*intensity, 0.0, 0.0, 0.0], 0),
+                        Some(bloom),
+                    )
+                }
+                PostEffect::Fxaa => ("fxaa", GPUPostParams::new([0.0; 4], 0), None),
+                PostEffect::Vignette {
+                    intensity,
+                    radius,
+                    smoothness,
+                } => (
+                    "vignette",
+                    GPUPostParams::new([*intensity, *radius, *smoothness, 0.0], 0),
+                    None,
+                ),
+                PostEffect::ColorGrading {
+                    lut_texture,
+                    intensity,
+                } => {
+                    if !has_texture(lut_texture) {
+                        println!(
+                            "Couldn't find LUT texture: {} for post effect: {}",
+                            lut_texture, id
+                        );
+                        continue;
+                    }
+                    (
+                        "color_grading",
+                        GPUPostParams::new([*intensity, 0.0, 0.0, 0.0], 0),
+                        Some(PostTexture::Engine(lut_texture.clone())),
+                    )
+                }
+            };
+            let target = if source == PostTexture::Ping {
+                PostTexture::Pong
+            } else {
+                PostTexture::Ping
+            };
+            steps.push(PostStep {
+                pipeline_id,
+                params,
+                source,
+                extra,
+                target: target.clone(),
+            });
+            source = target;
+        }
+        if steps.len() as u64 > MAX_STEPS {
+            println!(
+                "Post process chain needs {} steps but only {} are supported, skipping its effects",
+                steps.len(),
+                MAX_STEPS
+            );
+            steps.clear();
+        }
+        // the last effect writes the swapchain, an empty chain still has to copy the scene there
+        match steps.last_mut() {
+            Some(step) => step.target = PostTexture::Output,
+            None => steps.push(PostStep {
+                pipeline_id: "copy",
+                params: GPUPostParams::new([0.0; 4], 0),
+                source: PostTexture::Scene,
+                extra: None,
+                target: PostTexture::Output,
+            }),
+        }
+
+        for (index, step) in steps.iter_mut().enumerate() {
+            let size = get_texture_size(&step.source, resolution);
+            step.params.texel_size = [1.0 / size.0 as f32, 1.0 / size.1 as f32];
+            self.params_buffer.write(
+                queue,
+                index as u64 * self.slot_size,
+                bytemuck::bytes_of(&step.params),
+            );
+        }
+        self.steps = steps;
+    }
+
+    // records the steps prepared for this frame, get_texture looks up engine textures
+    pub fn record<'a, F: Fn(&str) -> Option<&'a Texture>>(
+        &self,
+        device: &wgpu::Device,
+        command_buffer: &mut CommandBuffer,
+        resources: &RenderGraphResources,
+        get_texture: F,
+    ) {
+        let get_view = |texture: &PostTexture| -> Option<&wgpu::TextureView> {
+            match texture {
+                PostTexture::Scene => resources.get_texture_view(SCENE_COLOR),
+                PostTexture::Ping => resources.get_texture_view(POST_PING),
+                PostTexture::Pong => resources.get_texture_view(POST_PONG),
+                PostTexture::BloomDownsample(level) => {
+                    resources.get_texture_view(&get_bloom_texture_name(BLOOM_DOWNSAMPLE, *level))
+                }
+                PostTexture::BloomUpsample(level) => {
+                    resources.get_texture_view(&get_bloom_texture_name(BLOOM_UPSAMPLE, *level))
+                }
+                PostTexture::Engine(id) => get_texture(id).map(|t| t.get_view()),
+                PostTexture::Output => resources.get_texture_view(SWAPCHAIN_COLOR),
+            }
+        };
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let pipeline_id = if step.target == PostTexture::Output {
+                get_output_pipeline_id(step.pipeline_id)
+            } else {
+                step.pipeline_id.to_string()
+            };
+            let Some(pipeline) = self.pipelines.iter().find(|p| p.get_id() == pipeline_id) else {
+                continue;
+            };
+            let (Some(source), Some(target), Some(layout)) = (
+                get_view(&step.source),
+                get_view(&step.target),
+                pipeline.get_bind_group_layout(0),
+            ) else {
+                println!(
+                    "Couldn't find the textures of post process step: {}",
+                    pipeline_id
+                );
+                continue;
+            };
+            let mut binding_resources = vec![
+                self.params_buffer.get_binding(),
+                wgpu::BindingResource::TextureView(source),
+                self.sampler.get_binding(),
+            ];
+            if let Some(extra) = step.extra.as_ref().and_then(&get_view) {
+                binding_resources.push(wgpu::BindingResource::TextureView(extra));
+            }
+            let entries: Vec<wgpu::BindGroupEntry> = binding_resources
+                .into_iter()
+                .enumerate()
+                .map(|(binding, resource)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource,
+                })
+                .collect();
+            // the textures change with the graph allocations, so bind groups are made every frame
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&pipeline_id),
+                layout,
+                entries: &entries,
+            });
+            let mut pass = RenderPassBuilder::new(&pipeline_id)
+                .color_attachment(target, [0.0, 0.0, 0.0, 1.0])
+                .build(command_buffer);
+            pass.set_pipeline(pipeline.get_native_pipeline());
+            pass.set_bind_group(0, &bind_group, &[(index as u64 * self.slot_size) as u32]);
+            pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+fn get_output_pipeline_id(id: &str) -> String {
+    format!("{}_output", id)
+}
+
+fn get_bloom_texture_name(prefix: &str, level: u32) -> String {
+    format!("{}_{}", prefix, level)
+}
+
+fn get_texture_size(texture: &PostTexture, resolution: (u32, u32)) -> (u32, u32) {
+    match texture {
+        // matches RenderGraphTextureSize::Swapchain(1, 2 << level)
+        PostTexture::BloomDownsample(level) | PostTexture::BloomUpsample(level) => (
+            (resolution.0 / (2 << level)).max(1),
+            (resolution.1 / (2 << level)).max(1),
+        ),
+        _ => resolution,
+    }
+}
+
+// Identity LUT with the layout color grading expects, grade a screenshot in an image editor
+// and apply the same adjustments to this image to get a LUT
+pub fn get_neutral_lut_image(size: u32) -> image::RgbaImage {
+    let scale = 255.0 / (size.max(2) - 1) as f32;
+    image::RgbaImage::from_fn(size * size, size, |x, y| {
+        image::Rgba([
+            ((x % size) as f32 * scale).round() as u8,
+            (y as f32 * scale).round() as u8,
+            ((x / size) as f32 * scale).round() as u8,
+            255,
+        ])
+    })
+}
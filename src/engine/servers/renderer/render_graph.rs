@@ -0,0 +1,579 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::engine::Engine;
+
+use super::resources::CommandBuffer;
+
+// textures provided by the engine every frame, they don't have to be declared with add_texture()
+pub const SWAPCHAIN_COLOR: &str = "swapchain_color";
+pub const SWAPCHAIN_DEPTH: &str = "swapchain_depth";
+
+pub type RenderGraphPassExecutor =
+    Box<dyn FnMut(&Engine, &mut CommandBuffer, &RenderGraphResources)>;
+
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+pub enum RenderGraphResource {
+    Texture(String),
+    Buffer(String),
+}
+
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+pub enum RenderGraphTextureSize {
+    // swapchain resolution scaled by numerator / denominator, e.g. Swapchain(1, 2) for half resolution
+    Swapchain(u32, u32),
+    Fixed(u32, u32),
+}
+
+// Transient texture owned by the graph, textures with equal descriptions and
+// non overlapping lifetimes share the same allocation
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+pub struct RenderGraphTexture {
+    pub format: wgpu::TextureFormat,
+    pub size: RenderGraphTextureSize,
+    pub usage: wgpu::TextureUsages,
+    pub array_layers: u32,
+    pub mip_level_count: u32,
+}
+
+impl Default for RenderGraphTexture {
+    fn default() -> Self {
+        Self {
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            size: RenderGraphTextureSize::Swapchain(1, 1),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            array_layers: 1,
+            mip_level_count: 1,
+        }
+    }
+}
+
+impl RenderGraphTexture {
+    fn get_extent(&self, resolution: (u32, u32)) -> wgpu::Extent3d {
+        let (width, height) = match self.size {
+            RenderGraphTextureSize::Swapchain(numerator, denominator) => (
+                resolution.0 * numerator / denominator.max(1),
+                resolution.1 * numerator / denominator.max(1),
+            ),
+            RenderGraphTextureSize::Fixed(width, height) => (width, height),
+        };
+        wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: self.array_layers.max(1),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderGraphError {
+    Cycle(Vec<String>),
+    UnknownTexture { pass: String, texture: String },
+}
+
+impl fmt::Display for RenderGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderGraphError::Cycle(passes) => {
+                write!(
+                    f,
+                    "render passes depend on each other: {}",
+                    passes.join(", ")
+                )
+            }
+            RenderGraphError::UnknownTexture { pass, texture } => write!(
+                f,
+                "render pass {} uses texture {} which hasn't been added to the graph",
+                pass, texture
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RenderGraphError {}
+
+pub struct RenderGraphPassBuilder {
+    id: String,
+    reads: Vec<RenderGraphResource>,
+    writes: Vec<RenderGraphResource>,
+}
+
+impl RenderGraphPassBuilder {
+    pub fn new_texture_read(&mut self, name: &str) -> &mut Self {
+        self.reads
+            .push(RenderGraphResource::Texture(name.to_string()));
+        self
+    }
+    pub fn new_texture_write(&mut self, name: &str) -> &mut Self {
+        self.writes
+            .push(RenderGraphResource::Texture(name.to_string()));
+        self
+    }
+    // buffers are owned by the engine, declaring them only affects the pass order
+    pub fn new_buffer_read(&mut self, id: &str) -> &mut Self {
+        self.reads.push(RenderGraphResource::Buffer(id.to_string()));
+        self
+    }
+    pub fn new_buffer_write(&mut self, id: &str) -> &mut Self {
+        self.writes
+            .push(RenderGraphResource::Buffer(id.to_string()));
+        self
+    }
+    pub fn build(&mut self, executor: RenderGraphPassExecutor) -> RenderGraphPass {
+        RenderGraphPass {
+            id: self.id.clone(),
+            reads: self.reads.clone(),
+            writes: self.writes.clone(),
+            executor,
+        }
+    }
+}
+
+pub struct RenderGraphPass {
+    id: String,
+    reads: Vec<RenderGraphResource>,
+    writes: Vec<RenderGraphResource>,
+    executor: RenderGraphPassExecutor,
+}
+
+impl RenderGraphPass {
+    pub fn new(id: &str) -> RenderGraphPassBuilder {
+        RenderGraphPassBuilder {
+            id: id.to_string(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+        }
+    }
+    pub fn get_id(&self) -> &str {
+        self.id.as_str()
+    }
+    fn reads(&self, resource: &RenderGraphResource) -> bool {
+        self.reads.contains(resource)
+    }
+    fn writes(&self, resource: &RenderGraphResource) -> bool {
+        self.writes.contains(resource)
+    }
+}
+
+// Textures a pass can access while it is executed
+pub struct RenderGraphResources<'a> {
+    textures: HashMap<String, &'a wgpu::Texture>,
+    views: HashMap<String, &'a wgpu::TextureView>,
+}
+
+impl<'a> RenderGraphResources<'a> {
+    pub fn get_texture(&self, name: &str) -> Option<&'a wgpu::Texture> {
+        self.textures.get(name).copied()
+    }
+    pub fn get_texture_view(&self, name: &str) -> Option<&'a wgpu::TextureView> {
+        self.views.get(name).copied()
+    }
+}
+
+struct AllocatedTexture {
+    description: RenderGraphTexture,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+pub struct RenderGraph {
+    passes: Vec<RenderGraphPass>,
+    textures: HashMap<String, RenderGraphTexture>,
+    // textures owned by the engine, passes using them are only ordered by them
+    imported_textures: HashSet<String>,
+    // indices into passes in execution order
+    order: Vec<usize>,
+    allocations: Vec<AllocatedTexture>,
+    // transient texture name -> index into allocations
+    texture_allocations: HashMap<String, usize>,
+    compiled_resolution: Option<(u32, u32)>,
+}
+
+impl RenderGraph {
+    pub fn new() -> RenderGraph {
+        RenderGraph {
+            passes: Vec::new(),
+            textures: HashMap::new(),
+            imported_textures: HashSet::new(),
+            order: Vec::new(),
+            allocations: Vec::new(),
+            texture_allocations: HashMap::new(),
+            compiled_resolution: None,
+        }
+    }
+
+    pub fn add_texture(&mut self, name: &str, description: RenderGraphTexture) {
+        self.textures.insert(name.to_string(), description);
+        self.compiled_resolution = None;
+    }
+
+    pub fn remove_texture(&mut self, name: &str) {
+        self.textures.remove(name);
+        self.compiled_resolution = None;
+    }
+
+    // lets passes declare reads and writes of a texture the engine owns, e.g. the shadow maps
+    pub fn import_texture(&mut self, name: &str) {
+        self.imported_textures.insert(name.to_string());
+        self.compiled_resolution = None;
+    }
+
+    // passes with an already registered id are replaced
+    pub fn add_pass(&mut self, pass: RenderGraphPass) {
+        if let Some(index) = self.passes.iter().position(|p| p.id == pass.id) {
+            self.passes[index] = pass;
+        } else {
+            self.passes.push(pass);
+        }
+        self.compiled_resolution = None;
+    }
+
+    pub fn remove_pass(&mut self, id: &str) {
+        if let Some(index) = self.passes.iter().position(|p| p.id == id) {
+            self.passes.remove(index);
+            self.compiled_resolution = None;
+        } else {
+            println!("couldn't find a render pass: {}", id);
+        }
+    }
+
+    pub fn has_pass(&self, id: &str) -> bool {
+        self.passes.iter().any(|p| p.id == id)
+    }
+
+    // pass ids in the order they are executed
+    pub fn get_pass_order(&self) -> Vec<&str> {
+        self.order
+            .iter()
+            .map(|index| self.passes[*index].id.as_str())
+            .collect()
+    }
+
+    // Orders the passes and allocates transient textures, only does work if the graph or resolution changed
+    pub fn compile(
+        &mut self,
+        device: &wgpu::Device,
+        resolution: (u32, u32),
+    ) -> Result<(), RenderGraphError> {
+        if self.compiled_resolution == Some(resolution) {
+            return Ok(());
+        }
+        self.validate()?;
+        self.order = self.sort_passes()?;
+        self.allocate_textures(device, resolution);
+        self.compiled_resolution = Some(resolution);
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), RenderGraphError> {
+        for pass in self.passes.iter() {
+            for resource in pass.reads.iter().chain(pass.writes.iter()) {
+                if let RenderGraphResource::Texture(name) = resource {
+                    if !self.textures.contains_key(name)
+                        && !self.imported_textures.contains(name)
+                        && name != SWAPCHAIN_COLOR
+                        && name != SWAPCHAIN_DEPTH
+                    {
+                        return Err(RenderGraphError::UnknownTexture {
+                            pass: pass.id.clone(),
+                            texture: name.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Passes that only write a resource run first in registration order, followed by passes
+    // that read and write it (e.g. overlays loading the previous content), then the readers
+    fn sort_passes(&self) -> Result<Vec<usize>, RenderGraphError> {
+        let pass_count = self.passes.len();
+        let mut dependencies: Vec<HashSet<usize>> = vec![HashSet::new(); pass_count];
+        let mut resources: Vec<&RenderGraphResource> = Vec::new();
+        for pass in self.passes.iter() {
+            for resource in pass.reads.iter().chain(pass.writes.iter()) {
+                if !resources.contains(&resource) {
+                    resources.push(resource);
+                }
+            }
+        }
+        for resource in resources {
+            let producers: Vec<usize> = (0..pass_count)
+                .filter(|i| self.passes[*i].writes(resource) && !self.passes[*i].reads(resource))
+                .collect();
+            let modifiers: Vec<usize> = (0..pass_count)
+                .filter(|i| self.passes[*i].writes(resource) && self.passes[*i].reads(resource))
+                .collect();
+            let consumers: Vec<usize> = (0..pass_count)
+                .filter(|i| self.passes[*i].reads(resource) && !self.passes[*i].writes(resource))
+                .collect();
+            for writers in [&producers, &modifiers] {
+                for pair in writers.windows(2) {
+                    dependencies[pair[1]].insert(pair[0]);
+                }
+            }
+            for modifier in modifiers.iter() {
+                dependencies[*modifier].extend(producers.iter());
+            }
+            for consumer in consumers.iter() {
+                dependencies[*consumer].extend(producers.iter().chain(modifiers.iter()));
+            }
+        }
+
+        let mut order: Vec<usize> = Vec::new();
+        let mut scheduled = vec![false; pass_count];
+        while order.len() < pass_count {
+            // pick the first registered pass whose dependencies have all been scheduled
+            let next = (0..pass_count).find(|i| {
+                !scheduled[*i]
+                    && dependencies[*i]
+                        .iter()
+                        .all(|dependency| scheduled[*dependency])
+            });
+            if let Some(index) = next {
+                scheduled[index] = true;
+                order.push(index);
+            } else {
+                return Err(RenderGraphError::Cycle(
+                    (0..pass_count)
+                        .filter(|i| !scheduled[*i])
+                        .map(|i| self.passes[i].id.clone())
+                        .collect(),
+                ));
+            }
+        }
+        Ok(order)
+    }
+
+    fn allocate_textures(&mut self, device: &wgpu::Device, resolution: (u32, u32)) {
+        // lifetime of every transient texture as first and last position in the pass order
+        let mut lifetimes: Vec<(String, usize, usize)> = Vec::new();
+        for (position, pass_index) in self.order.iter().enumerate() {
+            let pass = &self.passes[*pass_index];
+            for resource in pass.reads.iter().chain(pass.writes.iter()) {
+                if let RenderGraphResource::Texture(name) = resource {
+                    if !self.textures.contains_key(name) {
+                        continue;
+                    }
+                    if let Some(lifetime) = lifetimes.iter_mut().find(|l| &l.0 == name) {
+                        lifetime.2 = position;
+                    } else {
+                        lifetimes.push((name.clone(), position, position));
+                    }
+                }
+            }
+        }
+
+        self.allocations.clear();
+        self.texture_allocations.clear();
+        // last position an allocation is in use
+        let mut allocation_ends: Vec<usize> = Vec::new();
+        for (name, first_use, last_use) in lifetimes {
+            let description = self.textures[&name];
+            let reusable = self
+                .allocations
+                .iter()
+                .zip(allocation_ends.iter())
+                .position(|(allocation, end)| {
+                    allocation.description == description && *end < first_use
+                });
+            let index = if let Some(index) = reusable {
+                allocation_ends[index] = last_use;
+                index
+            } else {
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(name.as_str()),
+                    size: description.get_extent(resolution),
+                    mip_level_count: description.mip_level_count.max(1),
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: description.format,
+                    usage: description.usage,
+                    view_formats: &[],
+                });
+                let view = texture.create_view(&wgpu::TextureViewDescriptor {
+                    dimension: if description.array_layers > 1 {
+                        Some(wgpu::TextureViewDimension::D2Array)
+                    } else {
+                        Some(wgpu::TextureViewDimension::D2)
+                    },
+                    ..Default::default()
+                });
+                self.allocations.push(AllocatedTexture {
+                    description,
+                    texture,
+                    view,
+                });
+                allocation_ends.push(last_use);
+                self.allocations.len() - 1
+            };
+            self.texture_allocations.insert(name, index);
+        }
+    }
+
+    pub fn get_allocated_texture_count(&self) -> usize {
+        self.allocations.len()
+    }
+
+    // Runs every pass in order, compile() has to be called before
+    pub fn execute(
+        &mut self,
+        engine: &Engine,
+        command_buffer: &mut CommandBuffer,
+        frame_texture: &wgpu::Texture,
+        frame_view: &wgpu::TextureView,
+        depth_view: &wgpu::TextureView,
+    ) {
+        let mut resources = RenderGraphResources {
+            textures: HashMap::new(),
+            views: HashMap::new(),
+        };
+        resources
+            .textures
+            .insert(SWAPCHAIN_COLOR.to_string(), frame_texture);
+        resources
+            .views
+            .insert(SWAPCHAIN_COLOR.to_string(), frame_view);
+        resources
+            .views
+            .insert(SWAPCHAIN_DEPTH.to_string(), depth_view);
+        for (name, index) in self.texture_allocations.iter() {
+            let allocation = &self.allocations[*index];
+            resources.textures.insert(name.clone(), &allocation.texture);
+            resources.views.insert(name.clone(), &allocation.view);
+        }
+        for index in self.order.iter() {
+            let pass = &mut self.passes[*index];
+            (pass.executor)(engine, command_buffer, &resources);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::servers::renderer::RendererServer;
+
+    fn get_renderer_server() -> RendererServer {
+        pollster::block_on(RendererServer::new_headless((64, 64), true))
+    }
+
+    fn add_pass(graph: &mut RenderGraph, id: &str, reads: &[&str], writes: &[&str]) {
+        let mut builder = RenderGraphPass::new(id);
+        for name in reads {
+            builder.new_texture_read(name);
+        }
+        for name in writes {
+            builder.new_texture_write(name);
+        }
+        graph.add_pass(builder.build(Box::new(|_, _, _| {})));
+    }
+
+    fn get_order(graph: &RenderGraph) -> Vec<&str> {
+        graph
+            .sort_passes()
+            .unwrap()
+            .iter()
+            .map(|index| graph.passes[*index].get_id())
+            .collect()
+    }
+
+    #[test]
+    fn producers_run_before_consumers() {
+        let mut graph = RenderGraph::new();
+        for name in ["color", "bloom"] {
+            graph.add_texture(name, RenderGraphTexture::default());
+        }
+        // registered in the opposite order they have to run in
+        add_pass(
+            &mut graph,
+            "present",
+            &["color", "bloom"],
+            &[SWAPCHAIN_COLOR],
+        );
+        add_pass(&mut graph, "bloom", &["color"], &["bloom"]);
+        add_pass(&mut graph, "overlay", &["color"], &["color"]);
+        add_pass(&mut graph, "scene", &[], &["color"]);
+        assert_eq!(get_order(&graph), ["scene", "overlay", "bloom", "present"]);
+    }
+
+    #[test]
+    fn independent_passes_keep_their_registration_order() {
+        let mut graph = RenderGraph::new();
+        add_pass(&mut graph, "first", &[], &[SWAPCHAIN_COLOR]);
+        add_pass(&mut graph, "second", &[], &[SWAPCHAIN_COLOR]);
+        add_pass(&mut graph, "third", &[], &[SWAPCHAIN_DEPTH]);
+        assert_eq!(get_order(&graph), ["first", "second", "third"]);
+    }
+
+    #[test]
+    fn cycles_fail_to_compile() {
+        let renderer_server = get_renderer_server();
+        let mut graph = RenderGraph::new();
+        for name in ["a", "b"] {
+            graph.add_texture(name, RenderGraphTexture::default());
+        }
+        add_pass(&mut graph, "unrelated", &[], &[SWAPCHAIN_COLOR]);
+        add_pass(&mut graph, "ping", &["b"], &["a"]);
+        add_pass(&mut graph, "pong", &["a"], &["b"]);
+        match graph.compile(&renderer_server.device, (64, 64)) {
+            Err(RenderGraphError::Cycle(passes)) => assert_eq!(passes, ["ping", "pong"]),
+            result => panic!("expected a cycle, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn unknown_textures_fail_to_compile() {
+        let renderer_server = get_renderer_server();
+        let mut graph = RenderGraph::new();
+        add_pass(&mut graph, "scene", &[], &["missing"]);
+        assert!(matches!(
+            graph.compile(&renderer_server.device, (64, 64)),
+            Err(RenderGraphError::UnknownTexture { .. })
+        ));
+    }
+
+    #[test]
+    fn transient_textures_share_allocations_when_lifetimes_do_not_overlap() {
+        let renderer_server = get_renderer_server();
+        let mut graph = RenderGraph::new();
+        for name in ["first", "second", "third"] {
+            graph.add_texture(name, RenderGraphTexture::default());
+        }
+        // first is used by passes 0 and 1, second by 1 and 2 and third by 2 and 3
+        add_pass(&mut graph, "0", &[], &["first"]);
+        add_pass(&mut graph, "1", &["first"], &["second"]);
+        add_pass(&mut graph, "2", &["second"], &["third"]);
+        add_pass(&mut graph, "3", &["third"], &[SWAPCHAIN_COLOR]);
+        graph.compile(&renderer_server.device, (64, 64)).unwrap();
+        assert_eq!(graph.get_allocated_texture_count(), 2);
+        let allocations = &graph.texture_allocations;
+        assert_eq!(allocations["first"], allocations["third"]);
+        assert_ne!(allocations["first"], allocations["second"]);
+    }
+
+    #[test]
+    fn transient_textures_with_different_descriptions_are_not_shared() {
+        let renderer_server = get_renderer_server();
+        let mut graph = RenderGraph::new();
+        graph.add_texture("full", RenderGraphTexture::default());
+        graph.add_texture(
+            "half",
+            RenderGraphTexture {
+                size: RenderGraphTextureSize::Swapchain(1, 2),
+                ..Default::default()
+            },
+        );
+        add_pass(&mut graph, "0", &[], &["full"]);
+        add_pass(&mut graph, "1", &["full"], &[SWAPCHAIN_COLOR]);
+        add_pass(&mut graph, "2", &[], &["half"]);
+        add_pass(&mut graph, "3", &["half"], &[SWAPCHAIN_COLOR]);
+        graph.compile(&renderer_server.device, (64, 64)).unwrap();
+        assert_eq!(graph.get_allocated_texture_count(), 2);
+        let half = &graph.allocations[graph.texture_allocations["half"]];
+        assert_eq!(half.texture.width(), 32);
+    }
+}
@@ -2,6 +2,8 @@ use std::{borrow::Cow, collections::HashMap};
 
 use wgpu::util::DeviceExt;
 
+use super::RenderPipelineSettings;
+
 pub struct CommandBuffer {
     encoder: wgpu::CommandEncoder,
 }
@@ -17,6 +19,12 @@ impl CommandBuffer {
         let buffers = self.encoder.finish();
         queue.submit(Some(buffers));
     }
+
+    // compute passes have no attachments, so unlike render passes they don't need a builder
+    pub fn begin_compute_pass(&mut self, id: &str) -> wgpu::ComputePass<'_> {
+        self.encoder
+            .begin_compute_pass(&wgpu::ComputePassDescriptor { label: Some(id) })
+    }
 }
 
 pub struct BufferBuilder {
@@ -25,6 +33,7 @@ pub struct BufferBuilder {
     contents: Option<Vec<u8>>,
     usage: wgpu::BufferUsages,
     mapped_at_creation: bool,
+    binding_size: Option<u64>,
 }
 
 impl BufferBuilder {
@@ -45,6 +54,11 @@ impl BufferBuilder {
         self.mapped_at_creation = mapped_at_creation;
         self
     }
+    // binds only a window of this size instead of the entire buffer, the window is moved with dynamic offsets
+    pub fn new_binding_size(&mut self, binding_size: u64) -> &mut Self {
+        self.binding_size = Some(binding_size);
+        self
+    }
     pub fn build(&mut self, device: &wgpu::Device) -> Buffer {
         if let Some(contents) = self.contents.clone() {
             let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -54,7 +68,8 @@ impl BufferBuilder {
             });
             Buffer {
                 id: self.id.clone(),
-                buffer: buffer,
+                buffer,
+                binding_size: self.binding_size,
             }
         } else {
             let buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -65,7 +80,8 @@ impl BufferBuilder {
             });
             Buffer {
                 id: self.id.clone(),
-                buffer: buffer,
+                buffer,
+                binding_size: self.binding_size,
             }
         }
     }
@@ -74,6 +90,7 @@ impl BufferBuilder {
 pub struct Buffer {
     id: String,
     buffer: wgpu::Buffer,
+    binding_size: Option<u64>,
 }
 
 impl Buffer {
@@ -84,6 +101,7 @@ impl Buffer {
             usage: wgpu::BufferUsages::empty(),
             contents: None,
             mapped_at_creation: false,
+            binding_size: None,
         }
     }
 
@@ -98,6 +116,374 @@ impl Buffer {
     pub fn get_native_buffer(&self) -> &wgpu::Buffer {
         &self.buffer
     }
+
+    pub fn get_binding(&self) -> wgpu::BindingResource<'_> {
+        if let Some(size) = self.binding_size {
+            wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &self.buffer,
+                offset: 0,
+                size: wgpu::BufferSize::new(size),
+            })
+        } else {
+            self.buffer.as_entire_binding()
+        }
+    }
+}
+
+pub struct TextureBuilder {
+    id: String,
+    size: (u32, u32),
+    array_layers: u32,
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsages,
+    mip_level_count: u32,
+    generate_mipmaps: bool,
+    view_dimension: Option<wgpu::TextureViewDimension>,
+    contents: Option<Vec<u8>>,
+}
+
+impl TextureBuilder {
+    pub fn new_size(&mut self, size: (u32, u32)) -> &mut Self {
+        self.size = size;
+        self
+    }
+    pub fn new_array_layers(&mut self, array_layers: u32) -> &mut Self {
+        self.array_layers = array_layers;
+        self
+    }
+    pub fn new_format(&mut self, format: wgpu::TextureFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+    pub fn new_usage(&mut self, usage: wgpu::TextureUsages) -> &mut Self {
+        self.usage = usage;
+        self
+    }
+    pub fn new_mip_level_count(&mut self, mip_level_count: u32) -> &mut Self {
+        self.mip_level_count = mip_level_count;
+        self
+    }
+    // creates the full mip chain and fills it from the content, only supported for Rgba8 formats
+    pub fn new_mipmaps(&mut self, generate_mipmaps: bool) -> &mut Self {
+        self.generate_mipmaps = generate_mipmaps;
+        self
+    }
+    // by default the view dimension is picked from the amount of array layers
+    pub fn new_view_dimension(&mut self, view_dimension: wgpu::TextureViewDimension) -> &mut Self {
+        self.view_dimension = Some(view_dimension);
+        self
+    }
+    // tightly packed content of the first mip level
+    pub fn new_content(&mut self, contents: &[u8]) -> &mut Self {
+        self.contents = Some(contents.to_vec());
+        self
+    }
+    // color images are stored as sRGB so that sampling them returns linear values
+    pub fn new_image(&mut self, image: &image::RgbaImage) -> &mut Self {
+        self.size = image.dimensions();
+        self.format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        self.contents = Some(image.as_raw().clone());
+        self
+    }
+    pub fn build(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> Texture {
+        let mut mip_level_count = self.mip_level_count;
+        let mut mips: Vec<Vec<u8>> = Vec::new();
+        if let Some(contents) = self.contents.as_ref() {
+            if self.generate_mipmaps {
+                match self.format {
+                    wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+                        mips = generate_mipmaps(contents, self.size, self.format.is_srgb());
+                        mip_level_count = mips.len() as u32;
+                    }
+                    format => {
+                        println!(
+                            "Generating mipmaps for textures of format {:?} is not supported, texture: {}",
+                            format, self.id
+                        );
+                        mips.push(contents.clone());
+                    }
+                }
+            } else {
+                mips.push(contents.clone());
+            }
+        } else if self.generate_mipmaps {
+            mip_level_count = get_mip_level_count(self.size);
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&self.id),
+            size: wgpu::Extent3d {
+                width: self.size.0,
+                height: self.size.1,
+                depth_or_array_layers: self.array_layers,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: self.usage,
+            view_formats: &[],
+        });
+        let mut texture = Texture {
+            id: self.id.clone(),
+            view: texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some(&self.id),
+                dimension: Some(self.view_dimension.unwrap_or(if self.array_layers > 1 {
+                    wgpu::TextureViewDimension::D2Array
+                } else {
+                    wgpu::TextureViewDimension::D2
+                })),
+                ..Default::default()
+            }),
+            texture,
+        };
+        for (mip_level, data) in mips.iter().enumerate() {
+            texture.write(queue, mip_level as u32, data);
+        }
+        texture
+    }
+}
+
+pub struct Texture {
+    id: String,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl Texture {
+    pub fn new(id: &str) -> TextureBuilder {
+        TextureBuilder {
+            id: id.to_string(),
+            size: (1, 1),
+            array_layers: 1,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            mip_level_count: 1,
+            generate_mipmaps: false,
+            view_dimension: None,
+            contents: None,
+        }
+    }
+
+    pub fn get_texture_id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    // writes tightly packed data of every array layer into a mip level, the texture needs COPY_DST usage
+    pub fn write(&mut self, queue: &wgpu::Queue, mip_level: u32, data: &[u8]) {
+        let size = self
+            .texture
+            .size()
+            .mip_level_size(mip_level, self.texture.dimension());
+        let block_size = self.texture.format().block_size(None).unwrap_or(4);
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width * block_size),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+    }
+
+    pub fn get_native_texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    pub fn get_view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    pub fn get_binding(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::TextureView(&self.view)
+    }
+
+    // view of every array layer at a single mip level, e.g. to sample a mip while rendering the next one
+    pub fn create_mip_view(
+        &self,
+        mip_level: u32,
+        dimension: wgpu::TextureViewDimension,
+    ) -> wgpu::TextureView {
+        self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(&self.id),
+            dimension: Some(dimension),
+            base_mip_level: mip_level,
+            mip_level_count: Some(1),
+            ..Default::default()
+        })
+    }
+
+    // 2D view of a single array layer and mip level, e.g. to render into one layer of an array
+    pub fn create_layer_view(&self, array_layer: u32, mip_level: u32) -> wgpu::TextureView {
+        self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(&self.id),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_array_layer: array_layer,
+            array_layer_count: Some(1),
+            base_mip_level: mip_level,
+            mip_level_count: Some(1),
+            ..Default::default()
+        })
+    }
+}
+
+// amount of mip levels until the largest side reaches one pixel
+pub fn get_mip_level_count(size: (u32, u32)) -> u32 {
+    32 - size.0.max(size.1).max(1).leading_zeros()
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let value = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (value * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+// box filters every level down from the previous one, sRGB colors are averaged in linear space
+fn generate_mipmaps(contents: &[u8], size: (u32, u32), srgb: bool) -> Vec<Vec<u8>> {
+    let to_linear = |value: u8, channel: usize| {
+        if srgb && channel < 3 {
+            srgb_to_linear(value)
+        } else {
+            value as f32 / 255.0
+        }
+    };
+    let from_linear = |value: f32, channel: usize| {
+        if srgb && channel < 3 {
+            linear_to_srgb(value)
+        } else {
+            (value * 255.0).round().clamp(0.0, 255.0) as u8
+        }
+    };
+    let mut mips: Vec<Vec<u8>> = vec![contents.to_vec()];
+    let (mut width, mut height) = size;
+    for _ in 1..get_mip_level_count(size) {
+        let previous = mips.last().unwrap();
+        let (mip_width, mip_height) = ((width / 2).max(1), (height / 2).max(1));
+        let mut mip: Vec<u8> = Vec::with_capacity((mip_width * mip_height * 4) as usize);
+        for y in 0..mip_height {
+            for x in 0..mip_width {
+                for channel in 0..4 {
+                    let mut sum = 0.0;
+                    // odd sizes clamp to the last row or column
+                    for (sample_x, sample_y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                        let source_x = (x * 2 + sample_x).min(width - 1);
+                        let source_y = (y * 2 + sample_y).min(height - 1);
+                        let index = ((source_y * width + source_x) * 4) as usize + channel;
+                        sum += to_linear(previous[index], channel);
+                    }
+                    mip.push(from_linear(sum / 4.0, channel));
+                }
+            }
+        }
+        mips.push(mip);
+        width = mip_width;
+        height = mip_height;
+    }
+    mips
+}
+
+pub struct SamplerBuilder {
+    id: String,
+    address_mode: wgpu::AddressMode,
+    mag_filter: wgpu::FilterMode,
+    min_filter: wgpu::FilterMode,
+    mipmap_filter: wgpu::FilterMode,
+    compare: Option<wgpu::CompareFunction>,
+    anisotropy_clamp: u16,
+}
+
+impl SamplerBuilder {
+    pub fn new_address_mode(&mut self, address_mode: wgpu::AddressMode) -> &mut Self {
+        self.address_mode = address_mode;
+        self
+    }
+    // used for both magnification and minification
+    pub fn new_filter(&mut self, filter: wgpu::FilterMode) -> &mut Self {
+        self.mag_filter = filter;
+        self.min_filter = filter;
+        self
+    }
+    pub fn new_mipmap_filter(&mut self, filter: wgpu::FilterMode) -> &mut Self {
+        self.mipmap_filter = filter;
+        self
+    }
+    // comparison samplers are used with depth textures, e.g. for shadow maps
+    pub fn new_compare(&mut self, compare: wgpu::CompareFunction) -> &mut Self {
+        self.compare = Some(compare);
+        self
+    }
+    // values above 1 require all filters to be linear
+    pub fn new_anisotropy(&mut self, anisotropy_clamp: u16) -> &mut Self {
+        self.anisotropy_clamp = anisotropy_clamp;
+        self
+    }
+    pub fn build(&mut self, device: &wgpu::Device) -> Sampler {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(&self.id),
+            address_mode_u: self.address_mode,
+            address_mode_v: self.address_mode,
+            address_mode_w: self.address_mode,
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            compare: self.compare,
+            anisotropy_clamp: self.anisotropy_clamp,
+            ..Default::default()
+        });
+        Sampler {
+            id: self.id.clone(),
+            sampler,
+        }
+    }
+}
+
+pub struct Sampler {
+    id: String,
+    sampler: wgpu::Sampler,
+}
+
+impl Sampler {
+    pub fn new(id: &str) -> SamplerBuilder {
+        SamplerBuilder {
+            id: id.to_string(),
+            address_mode: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            compare: None,
+            anisotropy_clamp: 1,
+        }
+    }
+
+    pub fn get_sampler_id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn get_native_sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+
+    pub fn get_binding(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::Sampler(&self.sampler)
+    }
 }
 
 pub struct RenderPassBuilder<'a> {
@@ -107,7 +493,7 @@ pub struct RenderPassBuilder<'a> {
 }
 
 impl<'a> RenderPassBuilder<'a> {
-    pub fn new(id: &str) -> RenderPassBuilder {
+    pub fn new(id: &str) -> RenderPassBuilder<'_> {
         RenderPassBuilder {
             id: id.to_string(),
             color_attachment: None,
@@ -120,7 +506,7 @@ impl<'a> RenderPassBuilder<'a> {
         color: [f64; 4],
     ) -> &mut Self {
         self.color_attachment = Some(wgpu::RenderPassColorAttachment {
-            view: &texture_view,
+            view: texture_view,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color {
@@ -134,9 +520,21 @@ impl<'a> RenderPassBuilder<'a> {
         });
         self
     }
+    // keeps the current content of the texture instead of clearing it, e.g. for overlays
+    pub fn color_attachment_load(&mut self, texture_view: &'a wgpu::TextureView) -> &mut Self {
+        self.color_attachment = Some(wgpu::RenderPassColorAttachment {
+            view: texture_view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: true,
+            },
+        });
+        self
+    }
     pub fn depth_stencil_attachment(&mut self, view: &'a wgpu::TextureView) -> &mut Self {
         self.depth_stencil_attachment = Some(wgpu::RenderPassDepthStencilAttachment {
-            view: &view,
+            view,
             depth_ops: None,
             stencil_ops: None,
         });
@@ -146,25 +544,44 @@ impl<'a> RenderPassBuilder<'a> {
         if let Some(attachment) = self.depth_stencil_attachment.as_mut() {
             attachment.depth_ops = Some(wgpu::Operations {
                 load: wgpu::LoadOp::Clear(value),
-                store: false,
+                store: true,
             });
         } else {
             println!("depth_stencil_attachment() has not been called before depth_ops() for RenderPass: {}", self.id);
         }
         self
     }
+    pub fn depth_ops_load(&mut self) -> &mut Self {
+        if let Some(attachment) = self.depth_stencil_attachment.as_mut() {
+            attachment.depth_ops = Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: true,
+            });
+        } else {
+            println!("depth_stencil_attachment() has not been called before depth_ops_load() for RenderPass: {}", self.id);
+        }
+        self
+    }
+    // passes without a color attachment only write depth
     pub fn build(&mut self, command_buffer: &'a mut CommandBuffer) -> wgpu::RenderPass<'a> {
+        let color_attachments: &[Option<wgpu::RenderPassColorAttachment>] =
+            if self.color_attachment.is_some() {
+                std::slice::from_ref(&self.color_attachment)
+            } else {
+                &[]
+            };
         let render_pass: wgpu::RenderPass<'a> =
             command_buffer
                 .encoder
                 .begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: Some(self.id.as_str()),
-                    color_attachments: &[self.color_attachment.clone()],
+                    color_attachments,
                     depth_stencil_attachment: self.depth_stencil_attachment.clone(),
                 });
         render_pass
     }
 }
+
 pub struct VertexBufferLayout {
     array_stride: u64,
     step_mode: wgpu::VertexStepMode,
@@ -200,10 +617,16 @@ impl VertexBufferLayout {
 
 pub struct RenderPipelineBuilder<'a> {
     shader_source: String,
+    #[allow(dead_code)]
     group_layout_overwrite: HashMap<u8, wgpu::BindGroupLayout>,
     vertex_buffers: Vec<wgpu::VertexBufferLayout<'a>>,
     cull_mode: Option<wgpu::Face>,
     targets: Vec<Option<wgpu::ColorTargetState>>,
+    depth_format: Option<wgpu::TextureFormat>,
+    depth_testing: bool,
+    depth_write_enabled: bool,
+    depth_compare: wgpu::CompareFunction,
+    depth_bias: wgpu::DepthBiasState,
 }
 
 impl<'a> RenderPipelineBuilder<'a> {
@@ -231,6 +654,34 @@ impl<'a> RenderPipelineBuilder<'a> {
         self
     }
 
+    // pipelines with a depth format can be used in passes that have a depth attachment
+    pub fn new_depth_format(&mut self, format: wgpu::TextureFormat) -> &mut Self {
+        self.depth_format = Some(format);
+        self
+    }
+
+    // offsets the written depth, e.g. to keep surfaces from shadowing themselves
+    pub fn new_depth_bias(&mut self, constant: i32, slope_scale: f32) -> &mut Self {
+        self.depth_bias = wgpu::DepthBiasState {
+            constant,
+            slope_scale,
+            clamp: 0.0,
+        };
+        self
+    }
+
+    pub fn new_settings(&mut self, settings: &RenderPipelineSettings) -> &mut Self {
+        if !settings.shader.is_empty() {
+            self.shader_source = settings.shader.to_string();
+        }
+        self.cull_mode = Some(settings.cull_mode);
+        self.depth_format = Some(settings.depth_format);
+        self.depth_testing = settings.depth_testing;
+        self.depth_write_enabled = settings.depth_write_enabled;
+        self.depth_compare = settings.depth_compare;
+        self
+    }
+
     //overwrites automatically generated group layout by naga
     // pub fn group_layout_overwrite() -> &mut Self {
 
@@ -243,60 +694,21 @@ impl<'a> RenderPipelineBuilder<'a> {
             source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&self.shader_source)),
         });
         let naga_module = naga::front::wgsl::parse_str(&self.shader_source).unwrap();
-        let mut group_layouts: HashMap<u8, wgpu::BindGroupLayout> = HashMap::new();
-        let mut entries: HashMap<u32, Vec<wgpu::BindGroupLayoutEntry>> = HashMap::new();
-        for global_handle in naga_module.global_variables.iter() {
-            let handle = &naga_module.global_variables[global_handle.0];
-            if let Some(bindings) = &handle.binding {
-                let ty = match naga_module.types[handle.ty].inner {
-                    naga::TypeInner::Struct { .. } => wgpu::BindingType::Buffer {
-                        ty: if handle.space == naga::AddressSpace::Uniform {
-                            wgpu::BufferBindingType::Uniform
-                        } else {
-                            wgpu::BufferBindingType::Storage { read_only: true }
-                        },
-                        has_dynamic_offset: true,
-                        min_binding_size: None,
-                    },
-                    //naga::TypeInner::Image { .. } => quote!(&'a wgpu::TextureView),
-                    naga::TypeInner::Image { .. } => wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    //naga::TypeInner::Array { .. } => quote!(wgpu::BufferBinding<'a>),
-                    _ => panic!("Unsupported type for binding fields."),
-                };
-                let entry = wgpu::BindGroupLayoutEntry {
-                    binding: bindings.binding,
-                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                    ty: ty,
-                    count: None,
-                };
-                if let Some(entries) = entries.get_mut(&bindings.group) {
-                    entries.push(entry);
-                } else {
-                    let mut temp_vec: Vec<wgpu::BindGroupLayoutEntry> = Vec::new();
-                    temp_vec.push(entry);
-                    entries.insert(bindings.group, temp_vec);
-                }
-            }
-        }
-        for (key, value) in entries {
-            let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: None,
-                entries: &value,
-            });
-            group_layouts.insert(key as u8, layout);
-        }
-        let mut layout_ref: Vec<&wgpu::BindGroupLayout> = Vec::new();
-        for i in 0..group_layouts.len() as u8 {
-            if let Some(layout) = group_layouts.get(&i) {
-                layout_ref.push(layout);
-            }
-        }
+        let bindings =
+            PipelineBindings::reflect(device, &naga_module, wgpu::ShaderStages::VERTEX_FRAGMENT);
+        let layout_ref = bindings.get_layouts();
+        let vertex_entry = naga_module
+            .entry_points
+            .iter()
+            .find(|entry| entry.stage == naga::ShaderStage::Vertex)
+            .expect("shader has no vertex entry point");
+        // shaders without a fragment entry point only write depth
+        let fragment_entry = naga_module
+            .entry_points
+            .iter()
+            .find(|entry| entry.stage == naga::ShaderStage::Fragment);
         let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
+            label: Some(id),
             layout: Some(
                 &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                     label: None,
@@ -306,36 +718,337 @@ impl<'a> RenderPipelineBuilder<'a> {
             ),
             vertex: wgpu::VertexState {
                 module: &module,
-                entry_point: &naga_module.entry_points[0].name,
+                entry_point: &vertex_entry.name,
                 buffers: &self.vertex_buffers,
             },
-            fragment: Some(wgpu::FragmentState {
+            fragment: fragment_entry.map(|entry| wgpu::FragmentState {
                 module: &module,
-                entry_point: &naga_module.entry_points[1].name,
+                entry_point: &entry.name,
                 targets: &self.targets,
             }),
             primitive: wgpu::PrimitiveState {
                 cull_mode: self.cull_mode,
                 ..Default::default()
             },
-            depth_stencil: None,
+            depth_stencil: self.depth_format.map(|format| wgpu::DepthStencilState {
+                format,
+                // without depth testing the pipeline still has to be compatible with the depth attachment
+                depth_write_enabled: self.depth_testing && self.depth_write_enabled,
+                depth_compare: if self.depth_testing {
+                    self.depth_compare
+                } else {
+                    wgpu::CompareFunction::Always
+                },
+                stencil: wgpu::StencilState::default(),
+                bias: self.depth_bias,
+            }),
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
         });
         RenderPipeline {
             id: id.to_string(),
             pipeline: render_pipeline,
-            group_layouts: group_layouts,
-            bind_groups: HashMap::new(),
+            bindings,
+        }
+    }
+}
+
+// bind group layouts, bind groups and bound resource ids shared by render and compute pipelines
+struct PipelineBindings {
+    group_layouts: HashMap<u8, wgpu::BindGroupLayout>,
+    bind_groups: HashMap<u8, wgpu::BindGroup>,
+    // bindings of each group that take a dynamic offset
+    dynamic_bindings: HashMap<u8, Vec<u32>>,
+    // ids of the engine resources bound to each group, indexed by binding
+    resource_ids: HashMap<u8, Vec<String>>,
+}
+
+impl PipelineBindings {
+    // generates the bind group layouts from the resources declared in the shader
+    fn reflect(
+        device: &wgpu::Device,
+        naga_module: &naga::Module,
+        visibility: wgpu::ShaderStages,
+    ) -> PipelineBindings {
+        let mut group_layouts: HashMap<u8, wgpu::BindGroupLayout> = HashMap::new();
+        let mut entries: HashMap<u32, Vec<wgpu::BindGroupLayoutEntry>> = HashMap::new();
+        let mut dynamic_bindings: HashMap<u8, Vec<u32>> = HashMap::new();
+        for global_handle in naga_module.global_variables.iter() {
+            let handle = &naga_module.global_variables[global_handle.0];
+            if let Some(bindings) = &handle.binding {
+                let ty = reflect_binding_type(naga_module, handle);
+                // vertex shaders can't write to storage buffers and textures
+                let visibility = match ty {
+                    wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        ..
+                    }
+                    | wgpu::BindingType::StorageTexture { .. }
+                        if visibility.contains(wgpu::ShaderStages::VERTEX) =>
+                    {
+                        visibility - wgpu::ShaderStages::VERTEX
+                    }
+                    _ => visibility,
+                };
+                if let wgpu::BindingType::Buffer {
+                    has_dynamic_offset: true,
+                    ..
+                } = ty
+                {
+                    dynamic_bindings
+                        .entry(bindings.group as u8)
+                        .or_default()
+                        .push(bindings.binding);
+                }
+                let entry = wgpu::BindGroupLayoutEntry {
+                    binding: bindings.binding,
+                    visibility,
+                    ty,
+                    count: None,
+                };
+                entries.entry(bindings.group).or_default().push(entry);
+            }
+        }
+        // groups the shader skips still need a layout so that the following groups keep their index
+        let group_count = entries.keys().max().map(|max| max + 1).unwrap_or(0);
+        for group in 0..group_count {
+            let value = entries.remove(&group).unwrap_or_default();
+            let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &value,
+            });
+            group_layouts.insert(group as u8, layout);
+        }
+        // empty groups are bound automatically
+        let mut bind_groups: HashMap<u8, wgpu::BindGroup> = HashMap::new();
+        for (group, layout) in group_layouts.iter() {
+            if !entries_of_group_exist(naga_module, *group) {
+                bind_groups.insert(
+                    *group,
+                    device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: None,
+                        layout,
+                        entries: &[],
+                    }),
+                );
+            }
+        }
+        // dynamic offsets are passed in binding order
+        for bindings in dynamic_bindings.values_mut() {
+            bindings.sort();
+        }
+        PipelineBindings {
+            group_layouts,
+            bind_groups,
+            dynamic_bindings,
+            resource_ids: HashMap::new(),
+        }
+    }
+
+    fn get_layouts(&self) -> Vec<&wgpu::BindGroupLayout> {
+        let mut layout_ref: Vec<&wgpu::BindGroupLayout> = Vec::new();
+        for i in 0..self.group_layouts.len() as u8 {
+            if let Some(layout) = self.group_layouts.get(&i) {
+                layout_ref.push(layout);
+            }
+        }
+        layout_ref
+    }
+
+    fn bind_resource(
+        &mut self,
+        device: &wgpu::Device,
+        group: u8,
+        resources: &[wgpu::BindingResource],
+    ) {
+        let mut entries: Vec<wgpu::BindGroupEntry> = Vec::new();
+        for (i, resource) in resources.iter().enumerate() {
+            let entry = wgpu::BindGroupEntry {
+                binding: i as u32,
+                resource: resource.clone(),
+            };
+            entries.push(entry);
+        }
+        if let Some(layout) = self.group_layouts.get(&group) {
+            self.bind_groups.insert(
+                group,
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: None,
+                    layout,
+                    entries: &entries,
+                }),
+            );
+        }
+    }
+
+    fn set_resource_ids(&mut self, group: u8, resource_ids: &[&str]) {
+        self.resource_ids.insert(
+            group,
+            resource_ids.iter().map(|id| id.to_string()).collect(),
+        );
+    }
+
+    fn get_dynamic_offsets<F: Fn(&str) -> u32>(&self, group: u8, offset_for: F) -> Vec<u32> {
+        let mut offsets: Vec<u32> = Vec::new();
+        if let Some(bindings) = self.dynamic_bindings.get(&group) {
+            for binding in bindings {
+                let offset = self
+                    .resource_ids
+                    .get(&group)
+                    .and_then(|ids| ids.get(*binding as usize))
+                    .map(|id| offset_for(id))
+                    .unwrap_or(0);
+                offsets.push(offset);
+            }
+        }
+        offsets
+    }
+
+    fn get_bind_group_count(&self) -> u8 {
+        self.group_layouts.len() as u8
+    }
+
+    fn get_bind_groups(&self) -> Vec<&wgpu::BindGroup> {
+        let mut bind_groups: Vec<&wgpu::BindGroup> = Vec::new();
+        for i in 0..self.bind_groups.len() {
+            let idx = i as u8;
+            if let Some(group) = self.bind_groups.get(&idx) {
+                bind_groups.push(group);
+            }
+        }
+        bind_groups
+    }
+}
+
+fn entries_of_group_exist(naga_module: &naga::Module, group: u8) -> bool {
+    naga_module.global_variables.iter().any(|(_, variable)| {
+        variable
+            .binding
+            .as_ref()
+            .map(|binding| binding.group == group as u32)
+            .unwrap_or(false)
+    })
+}
+
+fn reflect_binding_type(
+    naga_module: &naga::Module,
+    variable: &naga::GlobalVariable,
+) -> wgpu::BindingType {
+    match naga_module.types[variable.ty].inner {
+        naga::TypeInner::Struct { .. } | naga::TypeInner::Array { .. } => {
+            wgpu::BindingType::Buffer {
+                ty: match variable.space {
+                    naga::AddressSpace::Uniform => wgpu::BufferBindingType::Uniform,
+                    naga::AddressSpace::Storage { access } => wgpu::BufferBindingType::Storage {
+                        read_only: !access.contains(naga::StorageAccess::STORE),
+                    },
+                    _ => panic!("Unsupported address space for buffer bindings."),
+                },
+                has_dynamic_offset: true,
+                min_binding_size: None,
+            }
+        }
+        naga::TypeInner::Image {
+            dim,
+            arrayed,
+            class,
+        } => {
+            let view_dimension = match (dim, arrayed) {
+                (naga::ImageDimension::D1, _) => wgpu::TextureViewDimension::D1,
+                (naga::ImageDimension::D2, false) => wgpu::TextureViewDimension::D2,
+                (naga::ImageDimension::D2, true) => wgpu::TextureViewDimension::D2Array,
+                (naga::ImageDimension::D3, _) => wgpu::TextureViewDimension::D3,
+                (naga::ImageDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+                (naga::ImageDimension::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+            };
+            match class {
+                naga::ImageClass::Sampled { kind, multi } => wgpu::BindingType::Texture {
+                    multisampled: multi,
+                    sample_type: match kind {
+                        naga::ScalarKind::Sint => wgpu::TextureSampleType::Sint,
+                        naga::ScalarKind::Uint => wgpu::TextureSampleType::Uint,
+                        _ => wgpu::TextureSampleType::Float { filterable: !multi },
+                    },
+                    view_dimension,
+                },
+                naga::ImageClass::Depth { multi } => wgpu::BindingType::Texture {
+                    multisampled: multi,
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension,
+                },
+                naga::ImageClass::Storage { format, access } => wgpu::BindingType::StorageTexture {
+                    access: if access
+                        .contains(naga::StorageAccess::LOAD | naga::StorageAccess::STORE)
+                    {
+                        wgpu::StorageTextureAccess::ReadWrite
+                    } else if access.contains(naga::StorageAccess::STORE) {
+                        wgpu::StorageTextureAccess::WriteOnly
+                    } else {
+                        wgpu::StorageTextureAccess::ReadOnly
+                    },
+                    format: storage_format_to_texture_format(format),
+                    view_dimension,
+                },
+            }
         }
+        naga::TypeInner::Sampler { comparison } => wgpu::BindingType::Sampler(if comparison {
+            wgpu::SamplerBindingType::Comparison
+        } else {
+            wgpu::SamplerBindingType::Filtering
+        }),
+        _ => panic!("Unsupported type for binding fields."),
+    }
+}
+
+fn storage_format_to_texture_format(format: naga::StorageFormat) -> wgpu::TextureFormat {
+    use naga::StorageFormat as Sf;
+    use wgpu::TextureFormat as Tf;
+    match format {
+        Sf::R8Unorm => Tf::R8Unorm,
+        Sf::R8Snorm => Tf::R8Snorm,
+        Sf::R8Uint => Tf::R8Uint,
+        Sf::R8Sint => Tf::R8Sint,
+        Sf::R16Uint => Tf::R16Uint,
+        Sf::R16Sint => Tf::R16Sint,
+        Sf::R16Float => Tf::R16Float,
+        Sf::Rg8Unorm => Tf::Rg8Unorm,
+        Sf::Rg8Snorm => Tf::Rg8Snorm,
+        Sf::Rg8Uint => Tf::Rg8Uint,
+        Sf::Rg8Sint => Tf::Rg8Sint,
+        Sf::R32Uint => Tf::R32Uint,
+        Sf::R32Sint => Tf::R32Sint,
+        Sf::R32Float => Tf::R32Float,
+        Sf::Rg16Uint => Tf::Rg16Uint,
+        Sf::Rg16Sint => Tf::Rg16Sint,
+        Sf::Rg16Float => Tf::Rg16Float,
+        Sf::Rgba8Unorm => Tf::Rgba8Unorm,
+        Sf::Rgba8Snorm => Tf::Rgba8Snorm,
+        Sf::Rgba8Uint => Tf::Rgba8Uint,
+        Sf::Rgba8Sint => Tf::Rgba8Sint,
+        Sf::Rgb10a2Unorm => Tf::Rgb10a2Unorm,
+        Sf::Rg11b10Float => Tf::Rg11b10Float,
+        Sf::Rg32Uint => Tf::Rg32Uint,
+        Sf::Rg32Sint => Tf::Rg32Sint,
+        Sf::Rg32Float => Tf::Rg32Float,
+        Sf::Rgba16Uint => Tf::Rgba16Uint,
+        Sf::Rgba16Sint => Tf::Rgba16Sint,
+        Sf::Rgba16Float => Tf::Rgba16Float,
+        Sf::Rgba32Uint => Tf::Rgba32Uint,
+        Sf::Rgba32Sint => Tf::Rgba32Sint,
+        Sf::Rgba32Float => Tf::Rgba32Float,
+        Sf::R16Unorm => Tf::R16Unorm,
+        Sf::R16Snorm => Tf::R16Snorm,
+        Sf::Rg16Unorm => Tf::Rg16Unorm,
+        Sf::Rg16Snorm => Tf::Rg16Snorm,
+        Sf::Rgba16Unorm => Tf::Rgba16Unorm,
+        Sf::Rgba16Snorm => Tf::Rgba16Snorm,
     }
 }
 
 pub struct RenderPipeline {
     id: String,
     pipeline: wgpu::RenderPipeline,
-    group_layouts: HashMap<u8, wgpu::BindGroupLayout>,
-    bind_groups: HashMap<u8, wgpu::BindGroup>,
+    bindings: PipelineBindings,
 }
 
 impl RenderPipeline {
@@ -346,6 +1059,11 @@ impl RenderPipeline {
             vertex_buffers: Vec::new(),
             cull_mode: None,
             targets: Vec::new(),
+            depth_format: None,
+            depth_testing: false,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            depth_bias: wgpu::DepthBiasState::default(),
         }
     }
     pub fn bind_resource(
@@ -354,40 +1072,164 @@ impl RenderPipeline {
         group: u8,
         resources: &[wgpu::BindingResource],
     ) {
-        let mut entries: Vec<wgpu::BindGroupEntry> = Vec::new();
-        for i in 0..resources.len() {
-            let entry = wgpu::BindGroupEntry {
-                binding: i as u32,
-                resource: resources[i].clone(),
-            };
-            entries.push(entry);
-        }
-        let i = group;
-        if let Some(layout) = self.group_layouts.get(&i) {
-            self.bind_groups
-                .entry(group)
-                .or_insert(device.create_bind_group(&wgpu::BindGroupDescriptor {
+        self.bindings.bind_resource(device, group, resources);
+    }
+    pub fn set_resource_ids(&mut self, group: u8, resource_ids: &[&str]) {
+        self.bindings.set_resource_ids(group, resource_ids);
+    }
+    // offset_for returns the dynamic offset of a bound engine resource, unknown resources get 0
+    pub fn get_dynamic_offsets<F: Fn(&str) -> u32>(&self, group: u8, offset_for: F) -> Vec<u32> {
+        self.bindings.get_dynamic_offsets(group, offset_for)
+    }
+    pub fn get_id(&self) -> &str {
+        self.id.as_str()
+    }
+    pub fn get_native_pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+    pub fn get_bind_groups(&self) -> Vec<&wgpu::BindGroup> {
+        self.bindings.get_bind_groups()
+    }
+    pub fn get_bind_group(&self, group: u8) -> Option<&wgpu::BindGroup> {
+        self.bindings.bind_groups.get(&group)
+    }
+    // amount of groups in the pipeline layout, groups the shader skips are included
+    pub fn get_bind_group_count(&self) -> u8 {
+        self.bindings.get_bind_group_count()
+    }
+    pub fn get_bind_group_layout(&self, group: u8) -> Option<&wgpu::BindGroupLayout> {
+        self.bindings.group_layouts.get(&group)
+    }
+}
+
+pub struct ComputePipelineBuilder {
+    shader_source: String,
+    entry_point: Option<String>,
+}
+
+impl ComputePipelineBuilder {
+    pub fn new_shader(&mut self, source: &str) -> &mut Self {
+        self.shader_source = source.to_string();
+        self
+    }
+
+    // by default the first compute entry point of the shader is used
+    pub fn new_entry_point(&mut self, entry_point: &str) -> &mut Self {
+        self.entry_point = Some(entry_point.to_string());
+        self
+    }
+
+    pub fn build(&mut self, id: &str, device: &wgpu::Device) -> ComputePipeline {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&self.shader_source)),
+        });
+        let naga_module = naga::front::wgsl::parse_str(&self.shader_source).unwrap();
+        let bindings = PipelineBindings::reflect(device, &naga_module, wgpu::ShaderStages::COMPUTE);
+        let layout_ref = bindings.get_layouts();
+        let entry = naga_module
+            .entry_points
+            .iter()
+            .find(|entry| {
+                entry.stage == naga::ShaderStage::Compute
+                    && self
+                        .entry_point
+                        .as_ref()
+                        .map(|name| *name == entry.name)
+                        .unwrap_or(true)
+            })
+            .expect("shader has no matching compute entry point");
+        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(id),
+            layout: Some(
+                &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                     label: None,
-                    layout: layout,
-                    entries: &entries,
-                }));
+                    bind_group_layouts: &layout_ref,
+                    push_constant_ranges: &[],
+                }),
+            ),
+            module: &module,
+            entry_point: &entry.name,
+        });
+        ComputePipeline {
+            id: id.to_string(),
+            pipeline: compute_pipeline,
+            workgroup_size: entry.workgroup_size,
+            bindings,
         }
     }
+}
+
+pub struct ComputePipeline {
+    id: String,
+    pipeline: wgpu::ComputePipeline,
+    workgroup_size: [u32; 3],
+    bindings: PipelineBindings,
+}
+
+impl ComputePipeline {
+    pub fn new() -> ComputePipelineBuilder {
+        ComputePipelineBuilder {
+            shader_source: "".to_string(),
+            entry_point: None,
+        }
+    }
+    pub fn bind_resource(
+        &mut self,
+        device: &wgpu::Device,
+        group: u8,
+        resources: &[wgpu::BindingResource],
+    ) {
+        self.bindings.bind_resource(device, group, resources);
+    }
+    pub fn set_resource_ids(&mut self, group: u8, resource_ids: &[&str]) {
+        self.bindings.set_resource_ids(group, resource_ids);
+    }
+    // offset_for returns the dynamic offset of a bound engine resource, unknown resources get 0
+    pub fn get_dynamic_offsets<F: Fn(&str) -> u32>(&self, group: u8, offset_for: F) -> Vec<u32> {
+        self.bindings.get_dynamic_offsets(group, offset_for)
+    }
     pub fn get_id(&self) -> &str {
         self.id.as_str()
     }
-    pub fn get_native_pipeline(&self) -> &wgpu::RenderPipeline {
+    pub fn get_native_pipeline(&self) -> &wgpu::ComputePipeline {
         &self.pipeline
     }
     pub fn get_bind_groups(&self) -> Vec<&wgpu::BindGroup> {
-        let mut bind_groups: Vec<&wgpu::BindGroup> = Vec::new();
-        for i in 0..self.bind_groups.len() {
-            let idx = i as u8;
-            let mut bind_group = self.bind_groups.get(&idx);
-            if let Some(group) = bind_group.as_mut() {
-                bind_groups.push(group);
-            }
+        self.bindings.get_bind_groups()
+    }
+    pub fn get_bind_group(&self, group: u8) -> Option<&wgpu::BindGroup> {
+        self.bindings.bind_groups.get(&group)
+    }
+    // amount of groups in the pipeline layout, groups the shader skips are included
+    pub fn get_bind_group_count(&self) -> u8 {
+        self.bindings.get_bind_group_count()
+    }
+    // workgroup size declared by the entry point with @workgroup_size
+    pub fn get_workgroup_size(&self) -> [u32; 3] {
+        self.workgroup_size
+    }
+    // number of workgroups needed so that every invocation gets its own thread
+    pub fn get_workgroup_count(&self, invocations: [u32; 3]) -> [u32; 3] {
+        std::array::from_fn(|i| invocations[i].div_ceil(self.workgroup_size[i].max(1)))
+    }
+    // sets the pipeline with its bind groups and dispatches enough workgroups to cover the invocations,
+    // offset_for gives the dynamic offset of every buffer like in get_dynamic_offsets
+    pub fn dispatch<'a, F: Fn(&str) -> u32>(
+        &'a self,
+        compute_pass: &mut wgpu::ComputePass<'a>,
+        invocations: [u32; 3],
+        offset_for: F,
+    ) {
+        compute_pass.set_pipeline(&self.pipeline);
+        for group in 0..self.get_bind_group_count() {
+            let Some(bind_group) = self.get_bind_group(group) else {
+                continue;
+            };
+            let offsets = self.get_dynamic_offsets(group, &offset_for);
+            compute_pass.set_bind_group(group as u32, bind_group, &offsets);
         }
-        bind_groups
+        let count = self.get_workgroup_count(invocations);
+        compute_pass.dispatch_workgroups(count[0], count[1], count[2]);
     }
 }
@@ -0,0 +1,33 @@
+// Split sum lookup table: scale (red) and bias (green) applied to F0 for the specular
+// environment light, indexed by n.v horizontally and roughness vertically
+fn visibility_smith_ggx(n_dot_l: f32, n_dot_v: f32, roughness: f32) -> f32 {
+    // k of image based lighting
+    let k = roughness * roughness * 0.5;
+    let ggx_v = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    let ggx_l = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    return ggx_v * ggx_l;
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let n_dot_v = max(vertex.uv.x, 0.001);
+    let roughness = vertex.uv.y;
+    let view_direction = vec3<f32>(sqrt(1.0 - n_dot_v * n_dot_v), 0.0, n_dot_v);
+    let normal = vec3<f32>(0.0, 0.0, 1.0);
+    var scale = 0.0;
+    var bias = 0.0;
+    for (var i = 0u; i < params.sample_count; i++) {
+        let half_vector = importance_sample_ggx(hammersley(i, params.sample_count), normal, roughness);
+        let light_direction = normalize(2.0 * dot(view_direction, half_vector) * half_vector - view_direction);
+        let n_dot_l = max(light_direction.z, 0.0);
+        let n_dot_h = max(half_vector.z, 0.0);
+        let v_dot_h = max(dot(view_direction, half_vector), 0.0);
+        if n_dot_l > 0.0 {
+            let visibility = visibility_smith_ggx(n_dot_l, n_dot_v, roughness) * v_dot_h / (n_dot_h * n_dot_v);
+            let fresnel = pow(1.0 - v_dot_h, 5.0);
+            scale += (1.0 - fresnel) * visibility;
+            bias += fresnel * visibility;
+        }
+    }
+    return vec4<f32>(scale, bias, 0.0, 1.0) / vec4<f32>(f32(params.sample_count), f32(params.sample_count), 1.0, 1.0);
+}
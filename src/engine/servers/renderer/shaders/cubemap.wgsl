@@ -0,0 +1,70 @@
+// Shared by the environment baking shaders, every draw renders a fullscreen triangle into one
+// cube face. Faces follow the usual +X, -X, +Y, -Y, +Z, -Z order and directions are in world space
+struct Params {
+    face: u32,
+    roughness: f32,
+    // resolution of mip 0 of the sampled cube
+    source_resolution: f32,
+    sample_count: u32,
+}
+@group(0)
+@binding(0)
+var<uniform> params: Params;
+
+const PI: f32 = 3.14159265359;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var result: VertexOutput;
+    result.position = vec4<f32>(position, 0.0, 1.0);
+    result.uv = vec2<f32>(position.x * 0.5 + 0.5, 0.5 - position.y * 0.5);
+    return result;
+}
+
+fn get_cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let s = uv.x * 2.0 - 1.0;
+    let t = uv.y * 2.0 - 1.0;
+    var direction: vec3<f32>;
+    switch face {
+        case 0u: { direction = vec3<f32>(1.0, -t, -s); }
+        case 1u: { direction = vec3<f32>(-1.0, -t, s); }
+        case 2u: { direction = vec3<f32>(s, 1.0, t); }
+        case 3u: { direction = vec3<f32>(s, -1.0, -t); }
+        case 4u: { direction = vec3<f32>(s, -t, 1.0); }
+        default: { direction = vec3<f32>(-s, -t, -1.0); }
+    }
+    return normalize(direction);
+}
+
+// low discrepancy points for the importance sampled integrals
+fn hammersley(i: u32, count: u32) -> vec2<f32> {
+    var bits = i;
+    bits = (bits << 16u) | (bits >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    return vec2<f32>(f32(i) / f32(count), f32(bits) * 2.3283064365386963e-10);
+}
+
+// half vector around the normal distributed like the GGX lobe of the roughness
+fn importance_sample_ggx(xi: vec2<f32>, normal: vec3<f32>, roughness: f32) -> vec3<f32> {
+    let alpha = roughness * roughness;
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    let half_vector = vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+    var up = vec3<f32>(0.0, 0.0, 1.0);
+    if abs(normal.z) > 0.999 {
+        up = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let tangent = normalize(cross(up, normal));
+    let bitangent = cross(normal, tangent);
+    return normalize(tangent * half_vector.x + bitangent * half_vector.y + normal * half_vector.z);
+}
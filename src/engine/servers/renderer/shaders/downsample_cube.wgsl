@@ -0,0 +1,14 @@
+// Fills a mip of the environment cube from the previous one, bilinear filtering averages the
+// four source texels below every target texel
+@group(0)
+@binding(1)
+var source_texture: texture_cube<f32>;
+@group(0)
+@binding(2)
+var source_sampler: sampler;
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let direction = get_cube_direction(params.face, vertex.uv);
+    return textureSampleLevel(source_texture, source_sampler, direction, 0.0);
+}
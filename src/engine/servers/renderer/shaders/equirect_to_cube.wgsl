@@ -0,0 +1,17 @@
+// Projects an equirectangular image with Z up onto a cube face
+@group(0)
+@binding(1)
+var equirect_texture: texture_2d<f32>;
+@group(0)
+@binding(2)
+var equirect_sampler: sampler;
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let direction = get_cube_direction(params.face, vertex.uv);
+    let uv = vec2<f32>(
+        0.5 + atan2(direction.y, direction.x) / (2.0 * PI),
+        0.5 - asin(clamp(direction.z, -1.0, 1.0)) / PI,
+    );
+    return vec4<f32>(textureSampleLevel(equirect_texture, equirect_sampler, uv, 0.0).rgb, 1.0);
+}
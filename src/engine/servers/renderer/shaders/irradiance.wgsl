@@ -0,0 +1,34 @@
+// Cosine weighted integral of the environment over the hemisphere around every direction,
+// the lambertian diffuse light arriving from the environment
+@group(0)
+@binding(1)
+var environment_texture: texture_cube<f32>;
+@group(0)
+@binding(2)
+var environment_sampler: sampler;
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = get_cube_direction(params.face, vertex.uv);
+    var up = vec3<f32>(0.0, 0.0, 1.0);
+    if abs(normal.z) > 0.999 {
+        up = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let tangent = normalize(cross(up, normal));
+    let bitangent = cross(normal, tangent);
+    // a low mip already averages the texels between the sample directions
+    let lod = max(log2(params.source_resolution / 16.0), 0.0);
+    let step = 0.1;
+    var irradiance = vec3<f32>(0.0);
+    var sample_count = 0.0;
+    for (var phi = 0.0; phi < 2.0 * PI; phi += step) {
+        for (var theta = 0.0; theta < 0.5 * PI; theta += step) {
+            let local = vec3<f32>(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+            let direction = tangent * local.x + bitangent * local.y + normal * local.z;
+            let radiance = textureSampleLevel(environment_texture, environment_sampler, direction, lod).rgb;
+            irradiance += radiance * cos(theta) * sin(theta);
+            sample_count += 1.0;
+        }
+    }
+    return vec4<f32>(PI * irradiance / sample_count, 1.0);
+}
@@ -0,0 +1,58 @@
+// Built-in forward shader: Lambert diffuse with a Blinn-Phong specular highlight,
+// scene.wgsl provides the camera, meshes, lights and shadows
+struct Material {
+    color: vec4<f32>,
+    ambient: f32,
+    specular: f32,
+    shininess: f32,
+}
+@group(2)
+@binding(0)
+var<uniform> material: Material;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) @interpolate(flat) receive_shadows: u32,
+};
+
+@vertex
+fn vs_main(
+    @builtin(instance_index) instance: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+) -> VertexOutput {
+    let mesh = meshes[instance];
+    let model_matrix = mesh.model_matrix;
+    let world_position = model_matrix * vec4<f32>(position, 1.0);
+    var result: VertexOutput;
+    result.position = camera.vp_matrix * world_position;
+    result.world_position = world_position.xyz;
+    result.normal = mesh.normal_matrix * normal;
+    result.receive_shadows = mesh.flags.x;
+    return result;
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(vertex.normal);
+    let view_direction = normalize(camera.position.xyz - vertex.world_position);
+    var color = material.color.rgb * material.ambient;
+    for (var i = 0u; i < lights.count; i++) {
+        let light = lights.lights[i];
+        let light_sample = sample_light(light, vertex.world_position);
+        let diffuse = max(dot(normal, light_sample.direction), 0.0);
+        if diffuse <= 0.0 {
+            continue;
+        }
+        let half_direction = normalize(light_sample.direction + view_direction);
+        let specular = pow(max(dot(normal, half_direction), 0.0), material.shininess) * material.specular;
+        var radiance = light_sample.radiance;
+        if vertex.receive_shadows != 0u {
+            radiance *= get_shadow(light, vertex.world_position, normal);
+        }
+        color += (material.color.rgb * diffuse + vec3<f32>(specular)) * radiance;
+    }
+    return vec4<f32>(color, material.color.a);
+}
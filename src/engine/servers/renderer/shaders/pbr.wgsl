@@ -0,0 +1,183 @@
+// Built-in metallic-roughness shader following the glTF material model: GGX distribution,
+// Smith visibility and Schlick fresnel with image based ambient light once an environment
+// is loaded, scene.wgsl provides the camera, meshes, lights and shadows
+struct Material {
+    base_color: vec4<f32>,
+    emissive: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+}
+@group(2)
+@binding(0)
+var<uniform> material: Material;
+// sRGB
+@group(2)
+@binding(1)
+var base_color_texture: texture_2d<f32>;
+// roughness in green and metallic in blue
+@group(2)
+@binding(2)
+var metallic_roughness_texture: texture_2d<f32>;
+// tangent space normals
+@group(2)
+@binding(3)
+var normal_texture: texture_2d<f32>;
+// ambient occlusion in red
+@group(2)
+@binding(4)
+var occlusion_texture: texture_2d<f32>;
+// sRGB
+@group(2)
+@binding(5)
+var emissive_texture: texture_2d<f32>;
+@group(2)
+@binding(6)
+var material_sampler: sampler;
+
+struct Environment {
+    intensity: f32,
+    prefiltered_mip_count: f32,
+    enabled: u32,
+}
+@group(0)
+@binding(6)
+var<uniform> environment: Environment;
+@group(0)
+@binding(7)
+var irradiance_texture: texture_cube<f32>;
+@group(0)
+@binding(8)
+var prefiltered_texture: texture_cube<f32>;
+@group(0)
+@binding(9)
+var brdf_lut: texture_2d<f32>;
+@group(0)
+@binding(10)
+var environment_sampler: sampler;
+
+const PI: f32 = 3.14159265359;
+// light reaching surfaces from every direction when no environment has been loaded
+const AMBIENT: f32 = 0.03;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tex_coord: vec2<f32>,
+    @location(3) @interpolate(flat) receive_shadows: u32,
+    @location(4) tangent: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    @builtin(instance_index) instance: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tex_coord: vec2<f32>,
+    @location(4) tangent: vec4<f32>,
+) -> VertexOutput {
+    let mesh = meshes[instance];
+    let world_position = mesh.model_matrix * vec4<f32>(position, 1.0);
+    var result: VertexOutput;
+    result.position = camera.vp_matrix * world_position;
+    result.world_position = world_position.xyz;
+    result.normal = mesh.normal_matrix * normal;
+    result.tex_coord = tex_coord;
+    result.receive_shadows = mesh.flags.x;
+    result.tangent = vec4<f32>((mesh.model_matrix * vec4<f32>(tangent.xyz, 0.0)).xyz, tangent.w);
+    return result;
+}
+
+// meshes without tangents get a tangent frame from screen space derivatives
+fn perturb_normal(normal: vec3<f32>, world_position: vec3<f32>, tex_coord: vec2<f32>, vertex_tangent: vec4<f32>) -> vec3<f32> {
+    let dp1 = dpdx(world_position);
+    let dp2 = dpdy(world_position);
+    let duv1 = dpdx(tex_coord);
+    let duv2 = dpdy(tex_coord);
+    let dp2_perp = cross(dp2, normal);
+    let dp1_perp = cross(normal, dp1);
+    var tangent = dp2_perp * duv1.x + dp1_perp * duv2.x;
+    var bitangent = dp2_perp * duv1.y + dp1_perp * duv2.y;
+    let inverse_max = inverseSqrt(max(max(dot(tangent, tangent), dot(bitangent, bitangent)), 1e-12));
+    // glTF texture coordinates start at the top, so the bitangent points down the image
+    tangent *= inverse_max;
+    bitangent *= -inverse_max;
+    if vertex_tangent.w != 0.0 {
+        tangent = normalize(vertex_tangent.xyz - normal * dot(normal, vertex_tangent.xyz));
+        bitangent = cross(normal, tangent) * sign(vertex_tangent.w);
+    }
+    var tangent_normal = textureSample(normal_texture, material_sampler, tex_coord).xyz * 2.0 - 1.0;
+    tangent_normal = vec3<f32>(tangent_normal.xy * material.normal_scale, tangent_normal.z);
+    return normalize(mat3x3<f32>(tangent, bitangent, normal) * tangent_normal);
+}
+
+fn distribution_ggx(n_dot_h: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    return alpha2 / (PI * denominator * denominator);
+}
+
+// height correlated Smith term divided by 4 n.l n.v
+fn visibility_smith_ggx(n_dot_l: f32, n_dot_v: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let ggx_v = n_dot_l * sqrt(n_dot_v * n_dot_v * (1.0 - alpha2) + alpha2);
+    let ggx_l = n_dot_v * sqrt(n_dot_l * n_dot_l * (1.0 - alpha2) + alpha2);
+    return 0.5 / max(ggx_v + ggx_l, 1e-5);
+}
+
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let base_color = material.base_color * textureSample(base_color_texture, material_sampler, vertex.tex_coord);
+    let metallic_roughness = textureSample(metallic_roughness_texture, material_sampler, vertex.tex_coord);
+    let metallic = clamp(material.metallic * metallic_roughness.b, 0.0, 1.0);
+    let roughness = clamp(material.roughness * metallic_roughness.g, 0.04, 1.0);
+    let occlusion = mix(1.0, textureSample(occlusion_texture, material_sampler, vertex.tex_coord).r, material.occlusion_strength);
+    let emissive = material.emissive * textureSample(emissive_texture, material_sampler, vertex.tex_coord).rgb;
+    let geometry_normal = normalize(vertex.normal);
+    let normal = perturb_normal(geometry_normal, vertex.world_position, vertex.tex_coord, vertex.tangent);
+
+    let view_direction = normalize(camera.position.xyz - vertex.world_position);
+    let n_dot_v = max(dot(normal, view_direction), 1e-4);
+    let alpha = roughness * roughness;
+    let f0 = mix(vec3<f32>(0.04), base_color.rgb, metallic);
+    let diffuse_color = base_color.rgb * (1.0 - metallic);
+
+    var color = (diffuse_color + f0 * 0.5) * AMBIENT * occlusion;
+    if environment.enabled != 0u {
+        // split sum approximation of the environment light
+        let reflection = reflect(-view_direction, normal);
+        let irradiance = textureSampleLevel(irradiance_texture, environment_sampler, normal, 0.0).rgb;
+        let lod = roughness * (environment.prefiltered_mip_count - 1.0);
+        let prefiltered = textureSampleLevel(prefiltered_texture, environment_sampler, reflection, lod).rgb;
+        let brdf = textureSampleLevel(brdf_lut, environment_sampler, vec2<f32>(n_dot_v, roughness), 0.0).rg;
+        let fresnel = f0 + (max(vec3<f32>(1.0 - roughness), f0) - f0) * pow(1.0 - n_dot_v, 5.0);
+        let specular = prefiltered * (fresnel * brdf.x + brdf.y);
+        let diffuse = irradiance * diffuse_color * (1.0 - fresnel);
+        color = (diffuse + specular) * occlusion * environment.intensity;
+    }
+    for (var i = 0u; i < lights.count; i++) {
+        let light = lights.lights[i];
+        let light_sample = sample_light(light, vertex.world_position);
+        let n_dot_l = dot(normal, light_sample.direction);
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+        let half_direction = normalize(light_sample.direction + view_direction);
+        let n_dot_h = max(dot(normal, half_direction), 0.0);
+        let fresnel = fresnel_schlick(max(dot(view_direction, half_direction), 0.0), f0);
+        let specular = fresnel * distribution_ggx(n_dot_h, alpha) * visibility_smith_ggx(n_dot_l, n_dot_v, alpha);
+        let diffuse = (1.0 - fresnel) * diffuse_color / PI;
+        var radiance = light_sample.radiance;
+        if vertex.receive_shadows != 0u {
+            radiance *= get_shadow(light, vertex.world_position, geometry_normal);
+        }
+        color += (diffuse + specular) * radiance * n_dot_l;
+    }
+    return vec4<f32>(color + emissive, base_color.a);
+}
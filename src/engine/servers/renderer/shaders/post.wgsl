@@ -0,0 +1,40 @@
+// Shared by the post processing shaders, every effect draws a fullscreen triangle that reads
+// the output of the previous effect
+struct Params {
+    // meaning depends on the effect
+    values: vec4<f32>,
+    // size of one texel of the source texture in uv units
+    texel_size: vec2<f32>,
+    mode: u32,
+}
+@group(0)
+@binding(0)
+var<uniform> params: Params;
+@group(0)
+@binding(1)
+var source_texture: texture_2d<f32>;
+@group(0)
+@binding(2)
+var source_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var result: VertexOutput;
+    result.position = vec4<f32>(position, 0.0, 1.0);
+    result.uv = vec2<f32>(position.x * 0.5 + 0.5, 0.5 - position.y * 0.5);
+    return result;
+}
+
+fn sample_source(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(source_texture, source_sampler, uv, 0.0);
+}
+
+fn get_luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
@@ -0,0 +1,11 @@
+// Adds the upsampled bloom in bloom_texture to the source scaled by values.x
+@group(0)
+@binding(3)
+var bloom_texture: texture_2d<f32>;
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(vertex.uv);
+    let bloom = textureSampleLevel(bloom_texture, source_sampler, vertex.uv, 0.0).rgb;
+    return vec4<f32>(color.rgb + bloom * params.values.x, color.a);
+}
@@ -0,0 +1,57 @@
+// Halves the resolution with a 13 tap filter (Jimenez 2014). With mode 1 the first mip is
+// filled, only light above the threshold in values.x passes with a soft knee of values.y
+// weighting by the inverse luminance keeps single bright pixels from flickering
+fn get_karis_weight(color: vec3<f32>) -> f32 {
+    return 1.0 / (1.0 + get_luminance(color));
+}
+
+fn apply_threshold(color: vec3<f32>) -> vec3<f32> {
+    let brightness = max(color.r, max(color.g, color.b));
+    let knee = params.values.y;
+    var soft = clamp(brightness - params.values.x + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-5);
+    let contribution = max(soft, brightness - params.values.x) / max(brightness, 1e-5);
+    return color * contribution;
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let t = params.texel_size;
+    let uv = vertex.uv;
+    let a = sample_source(uv + t * vec2<f32>(-2.0, -2.0)).rgb;
+    let b = sample_source(uv + t * vec2<f32>(0.0, -2.0)).rgb;
+    let c = sample_source(uv + t * vec2<f32>(2.0, -2.0)).rgb;
+    let d = sample_source(uv + t * vec2<f32>(-2.0, 0.0)).rgb;
+    let e = sample_source(uv).rgb;
+    let f = sample_source(uv + t * vec2<f32>(2.0, 0.0)).rgb;
+    let g = sample_source(uv + t * vec2<f32>(-2.0, 2.0)).rgb;
+    let h = sample_source(uv + t * vec2<f32>(0.0, 2.0)).rgb;
+    let i = sample_source(uv + t * vec2<f32>(2.0, 2.0)).rgb;
+    let j = sample_source(uv + t * vec2<f32>(-1.0, -1.0)).rgb;
+    let k = sample_source(uv + t * vec2<f32>(1.0, -1.0)).rgb;
+    let l = sample_source(uv + t * vec2<f32>(-1.0, 1.0)).rgb;
+    let m = sample_source(uv + t * vec2<f32>(1.0, 1.0)).rgb;
+    var color: vec3<f32>;
+    if params.mode == 1u {
+        let group_center = (j + k + l + m) * 0.25;
+        let group_a = (a + b + d + e) * 0.25;
+        let group_b = (b + c + e + f) * 0.25;
+        let group_c = (d + e + g + h) * 0.25;
+        let group_d = (e + f + h + i) * 0.25;
+        let weight_center = get_karis_weight(group_center) * 0.5;
+        let weight_a = get_karis_weight(group_a) * 0.125;
+        let weight_b = get_karis_weight(group_b) * 0.125;
+        let weight_c = get_karis_weight(group_c) * 0.125;
+        let weight_d = get_karis_weight(group_d) * 0.125;
+        color = (group_center * weight_center + group_a * weight_a + group_b * weight_b
+            + group_c * weight_c + group_d * weight_d)
+            / (weight_center + weight_a + weight_b + weight_c + weight_d);
+        color = apply_threshold(color);
+    } else {
+        color = (j + k + l + m) * 0.125
+            + (a + c + g + i) * 0.03125
+            + (b + d + f + h) * 0.0625
+            + e * 0.125;
+    }
+    return vec4<f32>(max(color, vec3<f32>(0.0)), 1.0);
+}
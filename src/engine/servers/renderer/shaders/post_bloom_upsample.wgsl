@@ -0,0 +1,22 @@
+// Adds a 3x3 tent filtered upsample of the next smaller level in the source texture to the
+// matching level of the downsample chain in blur_texture
+@group(0)
+@binding(3)
+var blur_texture: texture_2d<f32>;
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let t = params.texel_size;
+    let uv = vertex.uv;
+    var color = sample_source(uv).rgb * 4.0;
+    color += (sample_source(uv + t * vec2<f32>(-1.0, 0.0)).rgb
+        + sample_source(uv + t * vec2<f32>(1.0, 0.0)).rgb
+        + sample_source(uv + t * vec2<f32>(0.0, -1.0)).rgb
+        + sample_source(uv + t * vec2<f32>(0.0, 1.0)).rgb) * 2.0;
+    color += sample_source(uv + t * vec2<f32>(-1.0, -1.0)).rgb
+        + sample_source(uv + t * vec2<f32>(1.0, -1.0)).rgb
+        + sample_source(uv + t * vec2<f32>(-1.0, 1.0)).rgb
+        + sample_source(uv + t * vec2<f32>(1.0, 1.0)).rgb;
+    let blur = textureSampleLevel(blur_texture, source_sampler, uv, 0.0).rgb;
+    return vec4<f32>(blur + color / 16.0, 1.0);
+}
@@ -0,0 +1,39 @@
+// Looks colors up in a LUT stored as a strip of size * size by size texels: blue picks the
+// slice, red goes right and green down within a slice. LUTs are authored on gamma encoded
+// colors, values.x blends between the original and the graded color
+@group(0)
+@binding(3)
+var lut_texture: texture_2d<f32>;
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+fn sample_lut(color: vec3<f32>) -> vec3<f32> {
+    let size = f32(textureDimensions(lut_texture).y);
+    let slice = color.b * (size - 1.0);
+    let first_slice = floor(slice);
+    let second_slice = min(first_slice + 1.0, size - 1.0);
+    // texel centers of the red and green coordinates within a slice
+    let x = (color.r * (size - 1.0) + 0.5) / (size * size);
+    let y = (color.g * (size - 1.0) + 0.5) / size;
+    let first = textureSampleLevel(lut_texture, source_sampler, vec2<f32>(x + first_slice / size, y), 0.0).rgb;
+    let second = textureSampleLevel(lut_texture, source_sampler, vec2<f32>(x + second_slice / size, y), 0.0).rgb;
+    return mix(first, second, slice - first_slice);
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(vertex.uv);
+    let encoded = linear_to_srgb(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0)));
+    let graded = srgb_to_linear(sample_lut(encoded));
+    return vec4<f32>(mix(color.rgb, graded, params.values.x), color.a);
+}
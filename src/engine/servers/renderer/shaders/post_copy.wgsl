@@ -0,0 +1,5 @@
+// Writes the source unchanged, used to present the scene when the chain is empty
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    return sample_source(vertex.uv);
+}
@@ -0,0 +1,6 @@
+// values.x is the linear scale, 2 to the power of the exposure in stops
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(vertex.uv);
+    return vec4<f32>(color.rgb * params.values.x, color.a);
+}
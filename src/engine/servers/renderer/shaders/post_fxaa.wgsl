@@ -0,0 +1,45 @@
+// Fast approximate anti-aliasing, blurs along the edge direction found from the luminance of
+// the neighbouring pixels. Expects colors in the displayable range, so it runs after tonemapping
+const FXAA_REDUCE_MIN: f32 = 0.0078125;
+const FXAA_REDUCE_MUL: f32 = 0.125;
+const FXAA_SPAN_MAX: f32 = 8.0;
+
+// luminance of the gamma encoded color, edges are found the way they are perceived
+fn get_luma(uv: vec2<f32>) -> f32 {
+    return get_luminance(sqrt(max(sample_source(uv).rgb, vec3<f32>(0.0))));
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let t = params.texel_size;
+    let uv = vertex.uv;
+    let luma_nw = get_luma(uv + t * vec2<f32>(-1.0, -1.0));
+    let luma_ne = get_luma(uv + t * vec2<f32>(1.0, -1.0));
+    let luma_sw = get_luma(uv + t * vec2<f32>(-1.0, 1.0));
+    let luma_se = get_luma(uv + t * vec2<f32>(1.0, 1.0));
+    let center = sample_source(uv);
+    let luma_m = get_luminance(sqrt(max(center.rgb, vec3<f32>(0.0))));
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var direction = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let direction_reduce = max(
+        (luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL,
+        FXAA_REDUCE_MIN,
+    );
+    let inverse_min = 1.0 / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+    direction = clamp(direction * inverse_min, vec2<f32>(-FXAA_SPAN_MAX), vec2<f32>(FXAA_SPAN_MAX)) * t;
+
+    let result_a = 0.5 * (sample_source(uv + direction * (1.0 / 3.0 - 0.5)).rgb
+        + sample_source(uv + direction * (2.0 / 3.0 - 0.5)).rgb);
+    let result_b = result_a * 0.5 + 0.25 * (sample_source(uv - direction * 0.5).rgb
+        + sample_source(uv + direction * 0.5).rgb);
+    let luma_b = get_luminance(sqrt(max(result_b, vec3<f32>(0.0))));
+    if luma_b < luma_min || luma_b > luma_max {
+        return vec4<f32>(result_a, center.a);
+    }
+    return vec4<f32>(result_b, center.a);
+}
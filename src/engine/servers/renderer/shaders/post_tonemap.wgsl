@@ -0,0 +1,38 @@
+// Maps HDR colors into the displayable range, mode 0 is ACES and mode 1 is Reinhard
+
+// Stephen Hill's fit of the ACES reference rendering and output transforms
+fn tonemap_aces(color: vec3<f32>) -> vec3<f32> {
+    // sRGB to the RRT input space, the matrices are column major
+    let input_matrix = mat3x3<f32>(
+        vec3<f32>(0.59719, 0.07600, 0.02840),
+        vec3<f32>(0.35458, 0.90834, 0.13383),
+        vec3<f32>(0.04823, 0.01566, 0.83777),
+    );
+    let output_matrix = mat3x3<f32>(
+        vec3<f32>(1.60475, -0.10208, -0.00327),
+        vec3<f32>(-0.53108, 1.10813, -0.07276),
+        vec3<f32>(-0.07367, -0.00605, 1.07602),
+    );
+    let v = input_matrix * color;
+    let a = v * (v + 0.0245786) - 0.000090537;
+    let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+    return clamp(output_matrix * (a / b), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// scales by luminance so that saturated colors keep their hue
+fn tonemap_reinhard(color: vec3<f32>) -> vec3<f32> {
+    let luminance = get_luminance(color);
+    return color / (1.0 + luminance);
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(vertex.uv);
+    var result: vec3<f32>;
+    if params.mode == 0u {
+        result = tonemap_aces(max(color.rgb, vec3<f32>(0.0)));
+    } else {
+        result = tonemap_reinhard(max(color.rgb, vec3<f32>(0.0)));
+    }
+    return vec4<f32>(result, color.a);
+}
@@ -0,0 +1,11 @@
+// Darkens the corners, values.x is the strength, values.y the distance from the center where
+// darkening starts and values.z how far it takes to reach full strength
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(vertex.uv);
+    // distance in units of the screen height so that the vignette stays round
+    let aspect = params.texel_size.y / params.texel_size.x;
+    let offset = (vertex.uv - 0.5) * vec2<f32>(aspect, 1.0);
+    let falloff = smoothstep(params.values.y, params.values.y + params.values.z, length(offset));
+    return vec4<f32>(color.rgb * (1.0 - falloff * params.values.x), color.a);
+}
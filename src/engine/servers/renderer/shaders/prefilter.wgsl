@@ -0,0 +1,41 @@
+// Convolves the environment with the GGX lobe of the roughness stored in params, every
+// mip of the prefiltered cube holds a higher roughness. Samples read lower environment
+// mips the wider they are spread to avoid fireflies
+@group(0)
+@binding(1)
+var environment_texture: texture_cube<f32>;
+@group(0)
+@binding(2)
+var environment_sampler: sampler;
+
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let alpha2 = roughness * roughness * roughness * roughness;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    return alpha2 / (PI * denominator * denominator);
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    // view and reflection direction are assumed to be the normal
+    let normal = get_cube_direction(params.face, vertex.uv);
+    if params.roughness <= 0.0 {
+        return vec4<f32>(textureSampleLevel(environment_texture, environment_sampler, normal, 0.0).rgb, 1.0);
+    }
+    let texel_solid_angle = 4.0 * PI / (6.0 * params.source_resolution * params.source_resolution);
+    var color = vec3<f32>(0.0);
+    var total_weight = 0.0;
+    for (var i = 0u; i < params.sample_count; i++) {
+        let half_vector = importance_sample_ggx(hammersley(i, params.sample_count), normal, params.roughness);
+        let light_direction = normalize(2.0 * dot(normal, half_vector) * half_vector - normal);
+        let n_dot_l = dot(normal, light_direction);
+        if n_dot_l > 0.0 {
+            let n_dot_h = max(dot(normal, half_vector), 0.0);
+            let pdf = distribution_ggx(n_dot_h, params.roughness) * 0.25 + 0.0001;
+            let sample_solid_angle = 1.0 / (f32(params.sample_count) * pdf);
+            let lod = max(0.5 * log2(sample_solid_angle / texel_solid_angle) + 1.0, 0.0);
+            color += textureSampleLevel(environment_texture, environment_sampler, light_direction, lod).rgb * n_dot_l;
+            total_weight += n_dot_l;
+        }
+    }
+    return vec4<f32>(color / max(total_weight, 0.0001), 1.0);
+}
@@ -0,0 +1,128 @@
+// Bindings of the Global group shared by the built-in lit and pbr shaders, their
+// sources are appended to this file
+struct Camera {
+    vp_matrix: mat4x4<f32>,
+    view_matrix: mat4x4<f32>,
+    projection_matrix: mat4x4<f32>,
+    position: vec4<f32>,
+}
+@group(0)
+@binding(0)
+var<uniform> camera: Camera;
+
+struct Mesh {
+    model_matrix: mat4x4<f32>,
+    normal_matrix: mat3x3<f32>,
+    // x is 1 when the mesh receives shadows
+    flags: vec4<u32>,
+}
+@group(0)
+@binding(1)
+var<storage> meshes: array<Mesh>;
+
+struct Light {
+    // w is the light type: 0 directional, 1 point, 2 spot
+    position: vec4<f32>,
+    // w is the range
+    direction: vec4<f32>,
+    // w is the intensity
+    color: vec4<f32>,
+    // cosines of the inner and outer spot angles, first shadow map layer (-1 without shadows)
+    // and the amount of shadow map layers
+    spot: vec4<f32>,
+}
+struct Lights {
+    count: u32,
+    lights: array<Light>,
+}
+@group(0)
+@binding(2)
+var<storage> lights: Lights;
+
+struct Shadow {
+    view_proj_matrix: mat4x4<f32>,
+    // x is the view distance where a cascade ends, y the normal offset
+    params: vec4<f32>,
+}
+@group(0)
+@binding(3)
+var<storage> shadows: array<Shadow>;
+@group(0)
+@binding(4)
+var shadow_maps: texture_depth_2d_array;
+@group(0)
+@binding(5)
+var shadow_sampler: sampler_comparison;
+
+// 3x3 percentage closer filtering, 1.0 is fully lit
+fn sample_shadow(layer: i32, world_position: vec3<f32>) -> f32 {
+    let light_position = shadows[layer].view_proj_matrix * vec4<f32>(world_position, 1.0);
+    let coords = light_position.xyz / light_position.w;
+    let uv = coords.xy * vec2<f32>(0.5, -0.5) + 0.5;
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || coords.z > 1.0 {
+        return 1.0;
+    }
+    let texel_size = 1.0 / vec2<f32>(textureDimensions(shadow_maps));
+    var visibility = 0.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel_size;
+            visibility += textureSampleCompareLevel(shadow_maps, shadow_sampler, uv + offset, layer, coords.z);
+        }
+    }
+    return visibility / 9.0;
+}
+
+fn get_shadow(light: Light, world_position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    let first_layer = i32(light.spot.z);
+    if first_layer < 0 {
+        return 1.0;
+    }
+    if u32(light.position.w) == 0u {
+        // the cascade is picked by the distance to the camera
+        let view_depth = -(camera.view_matrix * vec4<f32>(world_position, 1.0)).z;
+        for (var i = 0; i < i32(light.spot.w); i++) {
+            let shadow = shadows[first_layer + i];
+            if view_depth < shadow.params.x {
+                return sample_shadow(first_layer + i, world_position + normal * shadow.params.y);
+            }
+        }
+        return 1.0;
+    }
+    // spot light texels grow with the distance to the light
+    let offset = shadows[first_layer].params.y * distance(light.position.xyz, world_position);
+    return sample_shadow(first_layer, world_position + normal * offset);
+}
+
+// smooth falloff that reaches zero at the light's range
+fn range_attenuation(distance: f32, range: f32) -> f32 {
+    let ratio = clamp(1.0 - pow(distance / range, 4.0), 0.0, 1.0);
+    return ratio * ratio / (distance * distance + 1.0);
+}
+
+struct LightSample {
+    // direction towards the light
+    direction: vec3<f32>,
+    // color times intensity and attenuation, without shadows
+    radiance: vec3<f32>,
+}
+
+fn sample_light(light: Light, world_position: vec3<f32>) -> LightSample {
+    var result: LightSample;
+    var attenuation = 1.0;
+    let light_type = u32(light.position.w);
+    if light_type == 0u {
+        result.direction = -normalize(light.direction.xyz);
+    } else {
+        let to_light = light.position.xyz - world_position;
+        let distance = length(to_light);
+        result.direction = to_light / max(distance, 0.0001);
+        attenuation = range_attenuation(distance, light.direction.w);
+        if light_type == 2u {
+            let cos_angle = dot(-result.direction, normalize(light.direction.xyz));
+            attenuation *= smoothstep(light.spot.y, light.spot.x, cos_angle);
+        }
+    }
+    result.radiance = light.color.rgb * light.color.w * attenuation;
+    return result;
+}
@@ -0,0 +1,24 @@
+// Depth only shader the shadow maps are rendered with, one draw per shadow map layer
+struct ShadowView {
+    view_proj_matrix: mat4x4<f32>,
+}
+@group(0)
+@binding(0)
+var<uniform> shadow_view: ShadowView;
+
+struct Mesh {
+    model_matrix: mat4x4<f32>,
+    normal_matrix: mat3x3<f32>,
+    flags: vec4<u32>,
+}
+@group(0)
+@binding(1)
+var<storage> meshes: array<Mesh>;
+
+@vertex
+fn vs_main(
+    @builtin(instance_index) instance: u32,
+    @location(0) position: vec3<f32>,
+) -> @builtin(position) vec4<f32> {
+    return shadow_view.view_proj_matrix * meshes[instance].model_matrix * vec4<f32>(position, 1.0);
+}
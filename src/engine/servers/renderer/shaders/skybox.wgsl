@@ -0,0 +1,58 @@
+// Draws the environment cube behind everything else, the fullscreen triangle lies on the far
+// plane so it only covers pixels the scene left empty
+struct Camera {
+    vp_matrix: mat4x4<f32>,
+    view_matrix: mat4x4<f32>,
+    projection_matrix: mat4x4<f32>,
+    position: vec4<f32>,
+}
+@group(0)
+@binding(0)
+var<uniform> camera: Camera;
+
+struct Environment {
+    intensity: f32,
+    prefiltered_mip_count: f32,
+    enabled: u32,
+}
+@group(0)
+@binding(1)
+var<uniform> environment: Environment;
+@group(0)
+@binding(2)
+var environment_texture: texture_cube<f32>;
+@group(0)
+@binding(3)
+var environment_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) view_direction: vec3<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var result: VertexOutput;
+    result.position = vec4<f32>(position, 1.0, 1.0);
+    // undo the perspective in view space, then rotate into world space with the
+    // transposed rotation of the view matrix
+    let view_space = vec3<f32>(
+        position.x / camera.projection_matrix[0][0],
+        position.y / camera.projection_matrix[1][1],
+        -1.0,
+    );
+    let rotation = mat3x3<f32>(
+        camera.view_matrix[0].xyz,
+        camera.view_matrix[1].xyz,
+        camera.view_matrix[2].xyz,
+    );
+    result.view_direction = transpose(rotation) * view_space;
+    return result;
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(environment_texture, environment_sampler, normalize(vertex.view_direction), 0.0).rgb;
+    return vec4<f32>(color * environment.intensity, 1.0);
+}
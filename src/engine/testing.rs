@@ -0,0 +1,239 @@
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::engine::Engine;
+
+// Set this environment variable to (re)write the reference images instead of comparing against them
+pub const UPDATE_GOLDEN_IMAGES_ENV: &str = "UPDATE_GOLDEN_IMAGES";
+
+#[derive(Debug)]
+pub enum GoldenImageError {
+    NotHeadless,
+    MissingReference(PathBuf),
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    Mismatch {
+        differing_percentage: f32,
+        max_differing_percentage: f32,
+        diff_image: PathBuf,
+    },
+    Image(image::ImageError),
+}
+
+impl fmt::Display for GoldenImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoldenImageError::NotHeadless => {
+                write!(
+                    f,
+                    "golden image tests need an engine created with Engine::new_headless()"
+                )
+            }
+            GoldenImageError::MissingReference(path) => write!(
+                f,
+                "reference image {} doesn't exist, run with {}=1 to create it",
+                path.display(),
+                UPDATE_GOLDEN_IMAGES_ENV
+            ),
+            GoldenImageError::SizeMismatch { expected, actual } => write!(
+                f,
+                "rendered image is {}x{} but the reference is {}x{}",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+            GoldenImageError::Mismatch {
+                differing_percentage,
+                max_differing_percentage,
+                diff_image,
+            } => write!(
+                f,
+                "{:.3}% of the pixels differ from the reference (allowed {:.3}%), see {}",
+                differing_percentage,
+                max_differing_percentage,
+                diff_image.display()
+            ),
+            GoldenImageError::Image(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for GoldenImageError {}
+
+impl From<image::ImageError> for GoldenImageError {
+    fn from(error: image::ImageError) -> Self {
+        GoldenImageError::Image(error)
+    }
+}
+
+pub struct ImageComparison {
+    pub differing_pixels: u64,
+    pub total_pixels: u64,
+    pub max_channel_difference: u8,
+    // differing pixels are drawn red on top of a darkened grayscale copy of the reference
+    pub diff_image: image::RgbaImage,
+}
+
+impl ImageComparison {
+    pub fn get_differing_percentage(&self) -> f32 {
+        if self.total_pixels == 0 {
+            return 0.0;
+        }
+        self.differing_pixels as f32 / self.total_pixels as f32 * 100.0
+    }
+}
+
+// A pixel counts as different when any of its channels differs by more than channel_threshold
+pub fn compare_images(
+    actual: &image::RgbaImage,
+    reference: &image::RgbaImage,
+    channel_threshold: u8,
+) -> Result<ImageComparison, GoldenImageError> {
+    if actual.dimensions() != reference.dimensions() {
+        return Err(GoldenImageError::SizeMismatch {
+            expected: reference.dimensions(),
+            actual: actual.dimensions(),
+        });
+    }
+    let mut diff_image = image::RgbaImage::new(reference.width(), reference.height());
+    let mut differing_pixels = 0;
+    let mut max_channel_difference = 0;
+    for ((actual_pixel, reference_pixel), diff_pixel) in actual
+        .pixels()
+        .zip(reference.pixels())
+        .zip(diff_image.pixels_mut())
+    {
+        let difference = actual_pixel
+            .0
+            .iter()
+            .zip(reference_pixel.0.iter())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or(0);
+        max_channel_difference = max_channel_difference.max(difference);
+        if difference > channel_threshold {
+            differing_pixels += 1;
+            *diff_pixel = image::Rgba([255, 0, 0, 255]);
+        } else {
+            let [r, g, b, _] = reference_pixel.0;
+            let luma = ((r as u32 * 3 + g as u32 * 6 + b as u32) / 10 / 3) as u8;
+            *diff_pixel = image::Rgba([luma, luma, luma, 255]);
+        }
+    }
+    Ok(ImageComparison {
+        differing_pixels,
+        total_pixels: reference.width() as u64 * reference.height() as u64,
+        max_channel_difference,
+        diff_image,
+    })
+}
+
+// Renders a scene on a headless engine and compares the last frame against a stored reference image.
+// Use Engine::new_headless(resolution, true) to run on a software adapter such as lavapipe or llvmpipe
+pub struct GoldenImageTest {
+    name: String,
+    reference_dir: PathBuf,
+    output_dir: PathBuf,
+    frame_count: u32,
+    channel_threshold: u8,
+    max_differing_percentage: f32,
+}
+
+impl GoldenImageTest {
+    pub fn new(name: &str) -> GoldenImageTest {
+        GoldenImageTest {
+            name: name.to_string(),
+            reference_dir: PathBuf::from("tests/golden"),
+            output_dir: PathBuf::from("target/golden_images"),
+            frame_count: 1,
+            channel_threshold: 0,
+            max_differing_percentage: 0.0,
+        }
+    }
+    pub fn new_reference_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.reference_dir = dir.as_ref().to_path_buf();
+        self
+    }
+    // actual and diff images of failed comparisons are written here
+    pub fn new_output_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.output_dir = dir.as_ref().to_path_buf();
+        self
+    }
+    pub fn new_frame_count(&mut self, frame_count: u32) -> &mut Self {
+        self.frame_count = frame_count.max(1);
+        self
+    }
+    pub fn new_channel_threshold(&mut self, threshold: u8) -> &mut Self {
+        self.channel_threshold = threshold;
+        self
+    }
+    pub fn new_max_differing_percentage(&mut self, percentage: f32) -> &mut Self {
+        self.max_differing_percentage = percentage;
+        self
+    }
+
+    pub fn get_reference_path(&self) -> PathBuf {
+        self.reference_dir.join(format!("{}.png", self.name))
+    }
+
+    // Calls frame() and steps the engine frame_count times before reading back the result
+    pub fn run<F: FnMut(&mut Engine)>(
+        &self,
+        engine: &mut Engine,
+        mut frame: F,
+    ) -> Result<ImageComparison, GoldenImageError> {
+        for _ in 0..self.frame_count {
+            frame(engine);
+            engine.step();
+        }
+        let actual = engine
+            .capture_frame()
+            .ok_or(GoldenImageError::NotHeadless)?;
+        let reference_path = self.get_reference_path();
+        if env::var_os(UPDATE_GOLDEN_IMAGES_ENV).is_some() {
+            create_parent_dir(&reference_path)?;
+            actual.save(&reference_path)?;
+            return compare_images(&actual, &actual, self.channel_threshold);
+        }
+        if !reference_path.exists() {
+            self.save_output(&actual, "actual")?;
+            return Err(GoldenImageError::MissingReference(reference_path));
+        }
+        let reference = image::open(&reference_path)?.to_rgba8();
+        let comparison = compare_images(&actual, &reference, self.channel_threshold)?;
+        let differing_percentage = comparison.get_differing_percentage();
+        if differing_percentage > self.max_differing_percentage {
+            self.save_output(&actual, "actual")?;
+            let diff_image = self.save_output(&comparison.diff_image, "diff")?;
+            return Err(GoldenImageError::Mismatch {
+                differing_percentage,
+                max_differing_percentage: self.max_differing_percentage,
+                diff_image,
+            });
+        }
+        Ok(comparison)
+    }
+
+    fn save_output(
+        &self,
+        image: &image::RgbaImage,
+        suffix: &str,
+    ) -> Result<PathBuf, GoldenImageError> {
+        let path = self
+            .output_dir
+            .join(format!("{}.{}.png", self.name, suffix));
+        create_parent_dir(&path)?;
+        image.save(&path)?;
+        Ok(path)
+    }
+}
+
+fn create_parent_dir(path: &Path) -> Result<(), GoldenImageError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|error| GoldenImageError::Image(image::ImageError::IoError(error)))?;
+    }
+    Ok(())
+}
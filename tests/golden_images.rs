@@ -0,0 +1,121 @@
+// Renders the examples on a headless engine and compares them against the images in tests/golden.
+// Run with UPDATE_GOLDEN_IMAGES=1 to regenerate the references after intended rendering changes
+use wgpu_engine::engine::testing::GoldenImageTest;
+use wgpu_engine::engine::Engine;
+
+#[path = "../examples/hello_cube.rs"]
+#[allow(dead_code)]
+mod hello_cube;
+#[path = "../examples/hello_ibl.rs"]
+#[allow(dead_code)]
+mod hello_ibl;
+#[path = "../examples/hello_lights.rs"]
+#[allow(dead_code)]
+mod hello_lights;
+#[path = "../examples/hello_nodes.rs"]
+#[allow(dead_code)]
+mod hello_nodes;
+#[path = "../examples/hello_pbr.rs"]
+#[allow(dead_code)]
+mod hello_pbr;
+#[path = "../examples/hello_post_processing.rs"]
+#[allow(dead_code)]
+mod hello_post_processing;
+
+const RESOLUTION: (u32, u32) = (160, 90);
+
+fn new_engine() -> Engine {
+    pollster::block_on(Engine::new_headless(RESOLUTION, true))
+}
+
+#[test]
+fn hello_cube_matches_reference() {
+    let mut engine = new_engine();
+    let hello_cube = hello_cube::HelloCube::new(&engine);
+    if let Err(error) = GoldenImageTest::new("hello_cube")
+        .new_channel_threshold(16)
+        .new_max_differing_percentage(1.0)
+        .run(&mut engine, |engine| hello_cube.render(engine))
+    {
+        panic!("{}", error);
+    }
+}
+
+#[test]
+fn hello_nodes_matches_reference() {
+    let mut engine = new_engine();
+    hello_nodes::setup(&mut engine);
+    let mut time = 0.0;
+    if let Err(error) = GoldenImageTest::new("hello_nodes")
+        .new_frame_count(3)
+        .new_channel_threshold(16)
+        .new_max_differing_percentage(1.0)
+        .run(&mut engine, |engine| hello_nodes::update(engine, &mut time))
+    {
+        panic!("{}", error);
+    }
+}
+
+#[test]
+fn hello_lights_matches_reference() {
+    let mut engine = new_engine();
+    hello_lights::setup(&mut engine);
+    let mut time = 0.0;
+    if let Err(error) = GoldenImageTest::new("hello_lights")
+        .new_frame_count(3)
+        .new_channel_threshold(16)
+        .new_max_differing_percentage(1.0)
+        .run(&mut engine, |engine| {
+            hello_lights::update(engine, &mut time)
+        })
+    {
+        panic!("{}", error);
+    }
+}
+
+#[test]
+fn hello_pbr_matches_reference() {
+    let mut engine = new_engine();
+    hello_pbr::setup(&mut engine);
+    let mut time = 0.0;
+    if let Err(error) = GoldenImageTest::new("hello_pbr")
+        .new_frame_count(3)
+        .new_channel_threshold(16)
+        .new_max_differing_percentage(1.0)
+        .run(&mut engine, |engine| hello_pbr::update(engine, &mut time))
+    {
+        panic!("{}", error);
+    }
+}
+
+#[test]
+fn hello_ibl_matches_reference() {
+    let mut engine = new_engine();
+    hello_ibl::setup(&mut engine);
+    let mut time = 0.0;
+    if let Err(error) = GoldenImageTest::new("hello_ibl")
+        .new_frame_count(3)
+        .new_channel_threshold(16)
+        .new_max_differing_percentage(1.0)
+        .run(&mut engine, |engine| hello_ibl::update(engine, &mut time))
+    {
+        panic!("{}", error);
+    }
+}
+
+#[test]
+fn hello_post_processing_matches_reference() {
+    let mut engine = new_engine();
+    hello_post_processing::setup(&mut engine);
+    let mut time = 0.0;
+    if let Err(error) = GoldenImageTest::new("hello_post_processing")
+        .new_frame_count(3)
+        .new_channel_threshold(16)
+        .new_max_differing_percentage(1.0)
+        .run(&mut engine, |engine| {
+            hello_post_processing::update(engine, &mut time)
+        })
+    {
+        panic!("{}", error);
+    }
+}